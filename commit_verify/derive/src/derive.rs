@@ -19,27 +19,104 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amplify_syn::{DeriveInner, EnumKind, Field, FieldKind, Fields, Items, NamedField, Variant};
+use amplify_syn::{
+    DataInner, DeriveInner, EnumKind, Field, FieldKind, Fields, Items, NamedField, Variant,
+};
 use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
 use quote::ToTokens;
-use syn::{Error, Index, Result};
+use syn::{Error, Index, LitByteStr, Result};
 
-use crate::params::{CommitDerive, FieldAttr, StrategyAttr};
+use crate::params::{CommitDerive, FieldAttr, IdDerive, StrategyAttr};
 
 struct DeriveCommit<'a>(&'a CommitDerive);
 
 impl CommitDerive {
     pub fn derive_encode(&self) -> Result<TokenStream2> {
         match self.conf.strategy {
-            StrategyAttr::CommitEncoding => self.data.derive(
-                &self.conf.commit_crate,
-                &ident!(CommitEncode),
-                &DeriveCommit(self),
-            ),
+            StrategyAttr::CommitEncoding => {
+                let encode = self.data.derive(
+                    &self.conf.commit_crate,
+                    &ident!(CommitEncode),
+                    &DeriveCommit(self),
+                )?;
+                let conceal = self.derive_conceal()?;
+                Ok(quote! {
+                    #encode
+                    #conceal
+                })
+            }
             other => self.derive_strategy(other),
         }
     }
 
+    /// Derives `Conceal` implementation concealing the fields marked with
+    /// `#[commit_encode(conceal)]`, if requested with
+    /// `#[commit_encode(derive_conceal)]` on the structure.
+    fn derive_conceal(&self) -> Result<TokenStream2> {
+        if !self.conf.derive_conceal {
+            return Ok(quote! {});
+        }
+        let DataInner::Struct(fields) = &self.data.inner else {
+            return Err(Error::new(
+                Span::call_site(),
+                "`Conceal` can be derived only for structures",
+            ));
+        };
+
+        let trait_crate = &self.conf.commit_crate;
+        let mut has_concealed = false;
+        let mut field_values = Vec::new();
+        let items: Vec<(Option<&Ident>, &Field)> = match fields {
+            Fields::Unit => vec![],
+            Fields::Named(fields) => fields.iter().map(|f| (Some(&f.name), &f.field)).collect(),
+            Fields::Unnamed(fields) => fields.iter().map(|f| (None, f)).collect(),
+        };
+        for (no, (field_name, field)) in items.into_iter().enumerate() {
+            let kind = match field_name {
+                Some(_) => FieldKind::Named,
+                None => FieldKind::Unnamed,
+            };
+            let attr = FieldAttr::with(field.attr.clone(), kind)?;
+            let access = field_name
+                .map(Ident::to_token_stream)
+                .unwrap_or_else(|| Index::from(no).to_token_stream());
+            let value = if attr.conceal {
+                has_concealed = true;
+                quote! { ::core::convert::From::from(#trait_crate::Conceal::conceal(&self.#access)) }
+            } else {
+                quote! { ::core::clone::Clone::clone(&self.#access) }
+            };
+            field_values.push(match field_name {
+                Some(name) => quote! { #name: #value },
+                None => value,
+            });
+        }
+        if !has_concealed {
+            return Err(Error::new(
+                Span::call_site(),
+                "`Conceal` derivation requires at least one field marked with \
+                 `#[commit_encode(conceal)]`",
+            ));
+        }
+
+        let construct = match fields {
+            Fields::Named(_) => quote! { Self { #( #field_values ),* } },
+            _ => quote! { Self( #( #field_values ),* ) },
+        };
+        let (impl_generics, ty_generics, where_clause) = self.data.generics.split_for_impl();
+        let ident_name = &self.data.name;
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #trait_crate::Conceal for #ident_name #ty_generics #where_clause {
+                type Concealed = Self;
+
+                fn conceal(&self) -> Self::Concealed {
+                    #construct
+                }
+            }
+        })
+    }
+
     fn derive_strategy(&self, strategy: StrategyAttr) -> Result<TokenStream2> {
         let (impl_generics, ty_generics, where_clause) = self.data.generics.split_for_impl();
         let trait_crate = &self.conf.commit_crate;
@@ -90,6 +167,10 @@ impl CommitDerive {
                         #crate_name::merkle::MerkleNode::merklize(#tag.to_be_bytes(), &me.#field_name).commit_encode(e);
                     }
                 }
+            } else if attr.conceal {
+                quote! {
+                    #crate_name::Conceal::conceal(&me.#field_name).commit_encode(e);
+                }
             } else {
                 quote! {
                     me.#field_name.commit_encode(e);
@@ -108,6 +189,55 @@ impl CommitDerive {
     }
 }
 
+impl IdDerive {
+    pub fn derive_id(&self) -> Result<TokenStream2> {
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let trait_crate = &self.conf.commit_crate;
+        let ident_name = &self.name;
+        let tag = LitByteStr::new(self.conf.tag.value().as_bytes(), self.conf.tag.span());
+        let id = &self.conf.id;
+
+        let test_name = format_ident!("commitment_id_tag_{}", ident_name);
+        let type_name = ident_name.to_string();
+        let collision = format!("commitment id tag of `{ident_name}` is already in use:\n{{}}");
+        let tag_pin = match self.conf.tag_hash {
+            None => quote! {},
+            Some(tag_hash) => {
+                let message = format!("commitment id tag of `{ident_name}` doesn't match its hash");
+                quote! {
+                    use #trait_crate::Digest;
+                    let hash = <[u8; 32]>::from(#trait_crate::Sha256::digest(tag));
+                    assert_eq!(hash, [#( #tag_hash ),*], #message);
+                }
+            }
+        };
+        let tag_test = quote! {
+            #[cfg(test)]
+            #[test]
+            #[allow(non_snake_case)]
+            fn #test_name() {
+                let tag: [u8; 32] = *#tag;
+                let mut registry = #trait_crate::stability::TagRegistry::library();
+                registry.register_tag(#type_name, tag);
+                if let Err(collisions) = registry.check() {
+                    panic!(#collision, collisions);
+                }
+                #tag_pin
+            }
+        };
+
+        Ok(quote! {
+            #[automatically_derived]
+            impl #impl_generics #trait_crate::CommitmentId for #ident_name #ty_generics #where_clause {
+                const TAG: [u8; 32] = *#tag;
+                type Id = #id;
+            }
+
+            #tag_test
+        })
+    }
+}
+
 impl DeriveInner for DeriveCommit<'_> {
     fn derive_unit_inner(&self) -> Result<TokenStream2> {
         Err(Error::new(
@@ -180,6 +310,10 @@ impl DeriveInner for DeriveCommit<'_> {
                             field_fragments.push(quote! {
                                 MerkleNode::merklize(#tag.to_be_bytes(), #index).commit_encode(e);
                             })
+                        } else if attr.conceal {
+                            field_fragments.push(quote! {
+                                #crate_name::Conceal::conceal(#index).commit_encode(e);
+                            })
                         } else {
                             field_fragments.push(quote! {
                                 #index.commit_encode(e);
@@ -208,6 +342,10 @@ impl DeriveInner for DeriveCommit<'_> {
                             field_fragments.push(quote! {
                                 MerkleNode::merklize(#tag.to_be_bytes(), #name).commit_encode(e);
                             })
+                        } else if attr.conceal {
+                            field_fragments.push(quote! {
+                                #crate_name::Conceal::conceal(#name).commit_encode(e);
+                            })
                         } else {
                             field_fragments.push(quote! {
                                 #name.commit_encode(e);
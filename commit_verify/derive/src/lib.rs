@@ -29,7 +29,8 @@
 //!
 //! Library exports derivation macros `#[derive(`[`CommitEncode`]`)]`,
 //! which can be added on top of any structure you'd like to support commitment
-//! encoding.
+//! encoding, and `#[derive(`[`CommitmentId`]`)]`, which implements
+//! `commit_verify::CommitmentId` trait for such structures.
 //!
 //! Encoding/decoding implemented by both of these macros may be configured at
 //! type and individual field level using `#[commit_encode(...)]` attributes.
@@ -61,9 +62,16 @@ mod derive;
 use proc_macro::TokenStream;
 use syn::DeriveInput;
 
-use crate::params::CommitDerive;
+use crate::params::{CommitDerive, IdDerive};
 
 /// Derives [`CommitEncode`] implementation for the type.
+///
+/// Structure fields marked with `#[commit_encode(conceal)]` are committed in
+/// their concealed form, such that the commitment doesn't change when the
+/// field gets concealed. With `#[commit_encode(derive_conceal)]` on the
+/// structure the macro also derives `Conceal` implementation, concealing each
+/// of the marked fields; this requires field types to implement
+/// `From<Field::Concealed>` and all other fields to be `Clone`.
 #[proc_macro_derive(CommitEncode, attributes(commit_encode))]
 pub fn derive_commit_encode(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
@@ -72,3 +80,28 @@ pub fn derive_commit_encode(input: TokenStream) -> TokenStream {
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
+
+/// Derives [`CommitmentId`] implementation for the type.
+///
+/// The type must also implement `CommitEncode` (for instance, by deriving it
+/// with `#[derive(CommitEncode)]`).
+///
+/// The commitment tag is provided with `#[commitment_id(tag = "...")]`. The
+/// macro generates `commitment_id_tag_<Type>` unit test checking that the tag
+/// is not used by any of the `commit_verify` library types (see
+/// `TagRegistry::library`). The test doesn't know about other types of the
+/// same crate, so it doesn't guard from collisions between them: crates
+/// should register all their types in a `TagRegistry` and check it in their
+/// own test. If the attribute also contains `tag_hash = "<hex>"` with the
+/// SHA256 hash of the tag, the test also checks the tag against this hash,
+/// protecting from accidental tag changes. Since the generated test is a
+/// module-level function, types deriving `CommitmentId` must not be declared
+/// inside function bodies.
+#[proc_macro_derive(CommitmentId, attributes(commitment_id))]
+pub fn derive_commitment_id(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    IdDerive::try_from(derive_input)
+        .and_then(|engine| engine.derive_id())
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
@@ -19,16 +19,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use amplify::hex::FromHex;
 use amplify_syn::{
     ArgValueReq, AttrReq, DataType, FieldKind, ListReq, ParametrizedAttr, TypeClass, ValueClass,
 };
 use proc_macro2::{Ident, Span};
 use quote::ToTokens;
-use syn::{DeriveInput, Error, Expr, Path, Result};
+use syn::{DeriveInput, Error, Expr, Generics, LitStr, Path, Result};
 
 const ATTR: &str = "commit_encode";
 const ATTR_CRATE: &str = "crate";
 const ATTR_CONCEAL: &str = "conceal";
+const ATTR_DERIVE_CONCEAL: &str = "derive_conceal";
 const ATTR_STRATEGY: &str = "strategy";
 const ATTR_STRATEGY_COMMIT: &str = "propagate";
 const ATTR_STRATEGY_STRICT: &str = "strict";
@@ -37,10 +39,16 @@ const ATTR_STRATEGY_INTO_U8: &str = "into_u8";
 const ATTR_MERKLIZE: &str = "merklize";
 const ATTR_SKIP: &str = "skip";
 
+const ATTR_ID: &str = "commitment_id";
+const ATTR_ID_TAG: &str = "tag";
+const ATTR_ID_TYPE: &str = "id";
+const ATTR_ID_TAG_HASH: &str = "tag_hash";
+
 pub struct ContainerAttr {
     pub commit_crate: Path,
     pub strategy: StrategyAttr,
     pub conceal: bool,
+    pub derive_conceal: bool,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -90,6 +98,7 @@ impl StrategyAttr {
 pub struct FieldAttr {
     pub merklize: Option<Expr>,
     pub skip: bool,
+    pub conceal: bool,
 }
 
 impl TryFrom<ParametrizedAttr> for ContainerAttr {
@@ -100,7 +109,7 @@ impl TryFrom<ParametrizedAttr> for ContainerAttr {
             ATTR_CRATE => ArgValueReq::optional(TypeClass::Path),
             ATTR_STRATEGY => ArgValueReq::optional(TypeClass::Path),
         ]);
-        req.path_req = ListReq::maybe_one(path!(conceal));
+        req.path_req = ListReq::any_of(vec![path!(conceal), path!(derive_conceal)], false);
         params.check(req)?;
 
         let path = params
@@ -109,6 +118,24 @@ impl TryFrom<ParametrizedAttr> for ContainerAttr {
 
         let mut strategy = StrategyAttr::try_from(&path)?;
         let conceal = params.has_verbatim(ATTR_CONCEAL);
+        let derive_conceal = params.has_verbatim(ATTR_DERIVE_CONCEAL);
+        if derive_conceal && conceal {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "`{ATTR_CONCEAL}` and `{ATTR_DERIVE_CONCEAL}` attributes can't be used \
+                     together"
+                ),
+            ));
+        }
+        if derive_conceal && strategy != StrategyAttr::CommitEncoding {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "`{ATTR_DERIVE_CONCEAL}` attribute requires `{ATTR_STRATEGY_COMMIT}` strategy"
+                ),
+            ));
+        }
         if conceal && strategy == StrategyAttr::StrictEncoding {
             strategy = StrategyAttr::ConcealStrictEncoding
         }
@@ -118,6 +145,7 @@ impl TryFrom<ParametrizedAttr> for ContainerAttr {
                 .unwrap_or_else(|_| path!(commit_verify)),
             strategy,
             conceal,
+            derive_conceal,
         })
     }
 }
@@ -127,12 +155,25 @@ impl FieldAttr {
         let mut req = AttrReq::with(map![
             ATTR_MERKLIZE => ArgValueReq::optional(ValueClass::Expr),
         ]);
-        req.path_req = ListReq::maybe_one(path!(skip));
+        req.path_req = ListReq::any_of(vec![path!(skip), path!(conceal)], false);
         params.check(req)?;
 
+        let merklize = params.arg_value(ATTR_MERKLIZE).ok();
+        let conceal = params.has_verbatim(ATTR_CONCEAL);
+        if conceal && merklize.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "`{ATTR_CONCEAL}` and `{ATTR_MERKLIZE}` attributes can't be used on the same \
+                     field"
+                ),
+            ));
+        }
+
         Ok(FieldAttr {
             skip: params.has_verbatim(ATTR_SKIP),
-            merklize: params.arg_value(ATTR_MERKLIZE).ok(),
+            merklize,
+            conceal,
         })
     }
 }
@@ -152,3 +193,81 @@ impl TryFrom<DeriveInput> for CommitDerive {
         Ok(Self { data, conf })
     }
 }
+
+pub struct IdAttr {
+    pub commit_crate: Path,
+    pub tag: LitStr,
+    pub tag_hash: Option<[u8; 32]>,
+    pub id: Path,
+}
+
+impl TryFrom<ParametrizedAttr> for IdAttr {
+    type Error = Error;
+
+    fn try_from(mut params: ParametrizedAttr) -> Result<Self> {
+        let req = AttrReq::with(map![
+            ATTR_CRATE => ArgValueReq::optional(TypeClass::Path),
+            ATTR_ID_TAG => ArgValueReq::required(ValueClass::str()),
+            ATTR_ID_TYPE => ArgValueReq::required(TypeClass::Path),
+            ATTR_ID_TAG_HASH => ArgValueReq::optional(ValueClass::str()),
+        ]);
+        params.check(req)?;
+
+        let tag: LitStr = params.arg_value(ATTR_ID_TAG)?;
+        let len = tag.value().len();
+        if len != 32 {
+            return Err(Error::new(
+                tag.span(),
+                format!(
+                    "commitment id tag must be exactly 32 bytes long, while the provided tag has \
+                     {len} bytes"
+                ),
+            ));
+        }
+
+        let tag_hash = params
+            .arg_value(ATTR_ID_TAG_HASH)
+            .ok()
+            .map(|hash: LitStr| {
+                Vec::<u8>::from_hex(&hash.value())
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .ok_or_else(|| {
+                        Error::new(
+                            hash.span(),
+                            "commitment id tag hash must be a hex-encoded 32-byte SHA256 hash",
+                        )
+                    })
+            })
+            .transpose()?;
+
+        Ok(IdAttr {
+            commit_crate: params
+                .arg_value(ATTR_CRATE)
+                .unwrap_or_else(|_| path!(commit_verify)),
+            tag,
+            tag_hash,
+            id: params.arg_value(ATTR_ID_TYPE)?,
+        })
+    }
+}
+
+pub struct IdDerive {
+    pub name: Ident,
+    pub generics: Generics,
+    pub conf: IdAttr,
+}
+
+impl TryFrom<DeriveInput> for IdDerive {
+    type Error = Error;
+
+    fn try_from(input: DeriveInput) -> Result<Self> {
+        let params = ParametrizedAttr::with(ATTR_ID, &input.attrs)?;
+        let conf = IdAttr::try_from(params)?;
+        Ok(Self {
+            name: input.ident,
+            generics: input.generics,
+            conf,
+        })
+    }
+}
@@ -31,9 +31,11 @@ mod common;
 use std::convert::Infallible;
 
 use amplify::confinement::SmallVec;
+use amplify::hex::FromHex;
 use amplify::Wrapper;
 use commit_verify::merkle::MerkleNode;
 use commit_verify::mpc::MERKLE_LNPBP4_TAG;
+use commit_verify::stability::TagRegistry;
 use commit_verify::{CommitEncode, CommitmentId, Conceal};
 use strict_encoding::{StrictDecode, StrictDumb, StrictEncode};
 
@@ -400,6 +402,85 @@ fn skip() -> common::Result {
     Ok(())
 }
 
+#[test]
+fn conceal_field() -> common::Result {
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    enum Secret {
+        Revealed(u8),
+        Concealed(u16),
+    }
+
+    impl Conceal for Secret {
+        type Concealed = Self;
+        fn conceal(&self) -> Self {
+            match *self {
+                Secret::Revealed(val) => Secret::Concealed(val as u16 * 0x101),
+                concealed => concealed,
+            }
+        }
+    }
+
+    impl CommitEncode for Secret {
+        fn commit_encode(&self, e: &mut impl std::io::Write) {
+            match self.conceal() {
+                Secret::Concealed(val) => val.commit_encode(e),
+                Secret::Revealed(_) => unreachable!(),
+            }
+        }
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(CommitEncode)]
+    #[commit_encode(derive_conceal)]
+    struct Data {
+        data: u8,
+        #[commit_encode(conceal)]
+        secret: Secret,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(CommitEncode)]
+    #[commit_encode(derive_conceal)]
+    struct Pair(#[commit_encode(conceal)] Secret, u8);
+
+    // Without `derive_conceal` the structure may implement `Conceal` itself
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(CommitEncode)]
+    struct Record {
+        #[commit_encode(conceal)]
+        secret: Secret,
+    }
+
+    impl Conceal for Record {
+        type Concealed = Secret;
+        fn conceal(&self) -> Secret { self.secret.conceal() }
+    }
+
+    let revealed = Data {
+        data: 0xfe,
+        secret: Secret::Revealed(0xad),
+    };
+    let concealed = revealed.conceal();
+    assert_eq!(concealed, Data {
+        data: 0xfe,
+        secret: Secret::Concealed(0xadad),
+    });
+    verify_commit(revealed, [0xfe, 0xad, 0xad]);
+    verify_commit(concealed, [0xfe, 0xad, 0xad]);
+
+    let pair = Pair(Secret::Revealed(0x01), 0x02);
+    assert_eq!(pair.conceal(), Pair(Secret::Concealed(0x0101), 0x02));
+    verify_commit(pair, [0x01, 0x01, 0x02]);
+
+    let record = Record {
+        secret: Secret::Revealed(0x03),
+    };
+    assert_eq!(record.conceal(), Secret::Concealed(0x0303));
+    verify_commit(record, [0x03, 0x03]);
+
+    Ok(())
+}
+
 #[test]
 fn merklize() -> common::Result {
     #[derive(Clone, PartialEq, Eq, Debug)]
@@ -427,3 +508,53 @@ fn merklize() -> common::Result {
 
     Ok(())
 }
+
+// Generates `commitment_id_tag_TaggedInfo` test checking the tag for
+// collisions with the library tags
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(CommitEncode, CommitmentId)]
+#[commitment_id(tag = "urn:lnpbp:test:commitment_id#23A", id = MerkleNode)]
+struct TaggedInfo {
+    a: u16,
+    b: u64,
+}
+
+// Generates `commitment_id_tag_PinnedInfo` test also checking the tag hash
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(CommitEncode, CommitmentId)]
+#[commitment_id(
+    tag = "urn:lnpbp:test:pinned_tag_id#23A",
+    id = MerkleNode,
+    tag_hash = "828a2b6ab10fd4ea96fd5d8116c27f330e12a161bab8fa8ef543a474e516160f"
+)]
+struct PinnedInfo(u16);
+
+#[test]
+fn commitment_id() -> common::Result {
+    assert_eq!(&TaggedInfo::TAG, b"urn:lnpbp:test:commitment_id#23A");
+    assert_eq!(&PinnedInfo::TAG, b"urn:lnpbp:test:pinned_tag_id#23A");
+    verify_commit(PinnedInfo(0xdead), [0xad, 0xde]);
+    assert_eq!(
+        TaggedInfo {
+            a: 0xdead,
+            b: 0xbeefcafebaddafec,
+        }
+        .commitment_id(),
+        MerkleNode::from_hex("2634d01bac93c7f99d8a64ae65b8cd4c4f5ce0a45c2ca5a0cf1addb1dd627c55")
+            .unwrap()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tag_registry() {
+    // Generated tests check tags only against the library ones, so crates must
+    // check their own types together
+    let mut registry = TagRegistry::library();
+    registry.register::<TaggedInfo>().register::<PinnedInfo>();
+    assert!(registry.check().is_ok());
+
+    registry.register_tag("Copy", TaggedInfo::TAG);
+    assert!(registry.check().is_err());
+}
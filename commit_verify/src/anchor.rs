@@ -0,0 +1,307 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lifecycle of anchors (publications of commitments in a blockchain-like
+//! medium), providing applications with a shared vocabulary and transition
+//! rules for tracking anchors across chain reorganizations.
+
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+/// Status of an anchor in the publication medium.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY, tags = order)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum AnchorStatus {
+    /// Anchor is not published.
+    #[default]
+    #[display("unpublished")]
+    Unpublished,
+
+    /// Anchor is published, but not yet mined.
+    #[display("mempool")]
+    Mempool,
+
+    /// Anchor is mined at the given height, but may be still reorged.
+    #[display("confirmed at {0}")]
+    Confirmed(u32),
+
+    /// Anchor was mined, but then was removed by a chain reorganization.
+    #[display("reorged")]
+    Reorged,
+
+    /// Anchor is mined deep enough to be considered final.
+    #[display("final")]
+    Final,
+}
+
+impl StrictSerialize for AnchorStatus {}
+impl StrictDeserialize for AnchorStatus {}
+
+/// anchor status can't change from {from} to {to}.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct InvalidTransition {
+    /// Current status.
+    pub from: AnchorStatus,
+    /// Requested status.
+    pub to: AnchorStatus,
+}
+
+impl ErrorCode for InvalidTransition {
+    fn code(&self) -> &'static str { "anchor.invalid-transition" }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("from", self.from.to_string()), ("to", self.to.to_string())]
+    }
+}
+
+impl ErrorCodes for InvalidTransition {
+    const CODES: &'static [&'static str] = &["anchor.invalid-transition"];
+}
+
+/// State of an anchor as observed in the publication medium by a resolver.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum AnchorObservation {
+    /// The anchor is not known to the medium.
+    Absent,
+    /// The anchor is known, but is not mined.
+    Mempool,
+    /// The anchor is mined at the given height.
+    Mined(u32),
+}
+
+/// Resolver observing anchors in the publication medium.
+pub trait AnchorResolver<Id> {
+    /// Error accessing the medium.
+    type Error: std::error::Error;
+
+    /// Returns current height of the medium (blockchain tip).
+    fn tip_height(&self) -> Result<u32, Self::Error>;
+
+    /// Observes the state of the anchor with the given id.
+    fn observe_anchor(&self, id: &Id) -> Result<AnchorObservation, Self::Error>;
+}
+
+/// Errors updating anchor status from a resolver.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(inner)]
+pub enum AnchorUpdateError<E: std::error::Error> {
+    /// Error of the resolver.
+    Resolver(E),
+
+    /// Invalid status transition.
+    #[from]
+    Transition(InvalidTransition),
+}
+
+impl<E: ErrorCode> ErrorCode for AnchorUpdateError<E> {
+    fn code(&self) -> &'static str {
+        match self {
+            AnchorUpdateError::Resolver(_) => "anchor.resolver",
+            AnchorUpdateError::Transition(err) => err.code(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            AnchorUpdateError::Resolver(err) => {
+                let mut params = vec![("cause", err.code().to_owned())];
+                params.extend(err.params());
+                params
+            }
+            AnchorUpdateError::Transition(err) => err.params(),
+        }
+    }
+}
+
+impl<E: ErrorCode> ErrorCodes for AnchorUpdateError<E> {
+    const CODES: &'static [&'static str] = &["anchor.resolver", "anchor.invalid-transition"];
+}
+
+impl AnchorStatus {
+    /// Detects whether the anchor is mined (either confirmed or final).
+    pub fn is_mined(self) -> bool {
+        matches!(self, AnchorStatus::Confirmed(_) | AnchorStatus::Final)
+    }
+
+    /// Detects whether the status can't change anymore.
+    pub fn is_final(self) -> bool { self == AnchorStatus::Final }
+
+    /// Checks whether the status may change to the `next` one. Keeping the
+    /// same status is always allowed.
+    pub fn can_transition(self, next: AnchorStatus) -> bool {
+        use AnchorStatus::*;
+        match (self, next) {
+            (from, to) if from == to => true,
+            (Final, _) => false,
+            (Unpublished | Mempool | Reorged, Unpublished | Mempool | Confirmed(_) | Final) => true,
+            (Confirmed(_), Reorged | Final) => true,
+            _ => false,
+        }
+    }
+
+    /// Changes the status to the `next` one, if the transition is valid.
+    pub fn transition(&mut self, next: AnchorStatus) -> Result<(), InvalidTransition> {
+        if !self.can_transition(next) {
+            return Err(InvalidTransition {
+                from: *self,
+                to: next,
+            });
+        }
+        *self = next;
+        Ok(())
+    }
+
+    /// Computes the status following the current one after the anchor
+    /// `observation` at the chain tip of `tip_height`. The anchor becomes
+    /// final once it has at least `final_depth` confirmations.
+    pub fn next(
+        self,
+        observation: AnchorObservation,
+        tip_height: u32,
+        final_depth: u32,
+    ) -> AnchorStatus {
+        match (self, observation) {
+            (AnchorStatus::Final, _) => AnchorStatus::Final,
+            (AnchorStatus::Confirmed(height), AnchorObservation::Mined(h)) if h != height => {
+                AnchorStatus::Reorged
+            }
+            (
+                AnchorStatus::Confirmed(_),
+                AnchorObservation::Absent | AnchorObservation::Mempool,
+            ) => AnchorStatus::Reorged,
+            (AnchorStatus::Reorged, AnchorObservation::Absent) => AnchorStatus::Reorged,
+            (_, AnchorObservation::Absent) => AnchorStatus::Unpublished,
+            (_, AnchorObservation::Mempool) => AnchorStatus::Mempool,
+            (_, AnchorObservation::Mined(height)) => {
+                if tip_height.saturating_sub(height).saturating_add(1) >= final_depth {
+                    AnchorStatus::Final
+                } else {
+                    AnchorStatus::Confirmed(height)
+                }
+            }
+        }
+    }
+
+    /// Updates the status of the anchor with the given id using the
+    /// `resolver`.
+    pub fn update<Id, R: AnchorResolver<Id>>(
+        &mut self,
+        resolver: &R,
+        id: &Id,
+        final_depth: u32,
+    ) -> Result<AnchorStatus, AnchorUpdateError<R::Error>> {
+        let tip_height = resolver.tip_height().map_err(AnchorUpdateError::Resolver)?;
+        let observation = resolver
+            .observe_anchor(id)
+            .map_err(AnchorUpdateError::Resolver)?;
+        self.transition(self.next(observation, tip_height, final_depth))?;
+        Ok(*self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::convert::Infallible;
+
+    use super::*;
+
+    struct Chain {
+        tip: Cell<u32>,
+        anchor: Cell<AnchorObservation>,
+    }
+
+    impl AnchorResolver<u8> for Chain {
+        type Error = Infallible;
+
+        fn tip_height(&self) -> Result<u32, Self::Error> { Ok(self.tip.get()) }
+
+        fn observe_anchor(&self, _: &u8) -> Result<AnchorObservation, Self::Error> {
+            Ok(self.anchor.get())
+        }
+    }
+
+    #[test]
+    fn transitions() {
+        use AnchorStatus::*;
+        assert!(Unpublished.can_transition(Mempool));
+        assert!(Mempool.can_transition(Confirmed(10)));
+        assert!(Confirmed(10).can_transition(Reorged));
+        assert!(Reorged.can_transition(Confirmed(11)));
+        assert!(Confirmed(10).can_transition(Final));
+        assert!(!Confirmed(10).can_transition(Confirmed(11)));
+        assert!(!Confirmed(10).can_transition(Mempool));
+        assert!(!Final.can_transition(Reorged));
+        assert!(Final.can_transition(Final));
+
+        let mut status = Final;
+        assert_eq!(
+            status.transition(Unpublished),
+            Err(InvalidTransition {
+                from: Final,
+                to: Unpublished
+            })
+        );
+        assert_eq!(status, Final);
+    }
+
+    #[test]
+    fn update() {
+        let chain = Chain {
+            tip: Cell::new(100),
+            anchor: Cell::new(AnchorObservation::Absent),
+        };
+        let mut status = AnchorStatus::default();
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Unpublished));
+
+        chain.anchor.set(AnchorObservation::Mempool);
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Mempool));
+
+        chain.tip.set(101);
+        chain.anchor.set(AnchorObservation::Mined(101));
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Confirmed(101)));
+
+        chain.anchor.set(AnchorObservation::Mempool);
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Reorged));
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Mempool));
+
+        chain.tip.set(102);
+        chain.anchor.set(AnchorObservation::Mined(102));
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Confirmed(102)));
+
+        chain.tip.set(107);
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Final));
+
+        chain.anchor.set(AnchorObservation::Absent);
+        assert_eq!(status.update(&chain, &0, 6), Ok(AnchorStatus::Final));
+
+        let data = status.to_strict_serialized::<16>().unwrap();
+        assert_eq!(AnchorStatus::from_strict_serialized::<16>(data).unwrap(), status);
+    }
+}
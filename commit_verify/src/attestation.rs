@@ -0,0 +1,164 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BIP-340 signatures over commitment ids, allowing oracles and issuers to
+//! sign client-side-validated data in a standard domain-separated way.
+
+use std::borrow::Borrow;
+
+use amplify::num::u256;
+use secp256k1::constants::CURVE_ORDER;
+use secp256k1::{schnorr, Keypair, Message, Scalar, Secp256k1, SecretKey, XOnlyPublicKey};
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::{CommitmentId, ErrorCode, ErrorCodes};
+
+/// Tag of the BIP-340 challenge hash.
+const BIP340_CHALLENGE_TAG: &[u8] = b"BIP0340/challenge";
+
+/// Computes BIP-340 challenge `e = H(R || P || m) mod n` for the schemes
+/// constructing signatures from their parts, like adaptor signatures.
+pub(crate) fn bip340_challenge(
+    r: &XOnlyPublicKey,
+    signer: &XOnlyPublicKey,
+    msg: &Message,
+) -> Scalar {
+    let mut engine = Sha256::from_tag(BIP340_CHALLENGE_TAG);
+    engine.input_raw(&r.serialize());
+    engine.input_raw(&signer.serialize());
+    engine.input_raw(msg.as_ref());
+    let mut e = u256::from_be_bytes(engine.finish());
+    // Any 256-bit value is less than twice the order, so a single subtraction
+    // is sufficient
+    let order = u256::from_be_bytes(CURVE_ORDER);
+    if e >= order {
+        e -= order;
+    }
+    Scalar::from_be_bytes(e.to_be_bytes()).expect("reduced modulo curve order")
+}
+
+/// the attestation signature is not valid for the provided data.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct InvalidAttestation;
+
+impl ErrorCode for InvalidAttestation {
+    fn code(&self) -> &'static str { "attestation.invalid" }
+}
+
+impl ErrorCodes for InvalidAttestation {
+    const CODES: &'static [&'static str] = &["attestation.invalid"];
+}
+
+/// BIP-340 signature over a commitment id of some data.
+///
+/// The signed message is a tagged hash committing both to the commitment id
+/// and to the [`CommitmentId::TAG`] of the data type, so the same signature
+/// can't be re-used for the data of a different type.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Attestation {
+    /// Public key of the signer.
+    pub signer: XOnlyPublicKey,
+    /// Signature.
+    pub sig: schnorr::Signature,
+}
+
+impl Attestation {
+    /// Tag of the hash producing the signed message.
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0009:attest:v01#A";
+
+    /// Computes the message which is signed by the attestation for the data.
+    pub fn message<T: CommitmentId>(data: &T) -> Message
+    where T::Id: Borrow<[u8]> {
+        let id = data.commitment_id();
+        let mut engine = Sha256::from_tag(Self::TAG);
+        engine.input_raw(&T::TAG);
+        engine.input_raw(id.borrow());
+        Message::from_digest(engine.finish())
+    }
+
+    /// Signs the commitment id of the data with the secret key.
+    pub fn sign<T: CommitmentId>(secret_key: &SecretKey, data: &T) -> Self
+    where T::Id: Borrow<[u8]> {
+        let secp = Secp256k1::signing_only();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        let sig = secp.sign_schnorr_no_aux_rand(&Self::message(data), &keypair);
+        Attestation {
+            signer: keypair.x_only_public_key().0,
+            sig,
+        }
+    }
+
+    /// Verifies that the attestation signs the commitment id of the data.
+    pub fn verify<T: CommitmentId>(&self, data: &T) -> Result<(), InvalidAttestation>
+    where T::Id: Borrow<[u8]> {
+        let secp = Secp256k1::verification_only();
+        secp.verify_schnorr(&self.sig, &Self::message(data), &self.signer)
+            .map_err(|_| InvalidAttestation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkle::MerkleNode;
+    use crate::mpc::Commitment;
+    use crate::CommitEncode;
+
+    #[derive(Clone, Debug)]
+    struct Data(u64);
+
+    impl CommitEncode for Data {
+        fn commit_encode(&self, e: &mut impl std::io::Write) { self.0.commit_encode(e) }
+    }
+
+    impl CommitmentId for Data {
+        const TAG: [u8; 32] = *b"urn:lnpbp:test:attestation#2023A";
+        type Id = MerkleNode;
+    }
+
+    #[derive(Clone, Debug)]
+    struct Other(u64);
+
+    impl CommitEncode for Other {
+        fn commit_encode(&self, e: &mut impl std::io::Write) { self.0.commit_encode(e) }
+    }
+
+    impl CommitmentId for Other {
+        const TAG: [u8; 32] = *b"urn:lnpbp:test:attestation#2023B";
+        type Id = Commitment;
+    }
+
+    #[test]
+    fn attestation() {
+        let secret_key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let attestation = Attestation::sign(&secret_key, &Data(7));
+        assert_eq!(attestation.verify(&Data(7)), Ok(()));
+        assert_eq!(attestation.verify(&Data(8)), Err(InvalidAttestation));
+        assert_eq!(attestation.verify(&Other(7)), Err(InvalidAttestation));
+
+        let other_key = SecretKey::from_slice(&[0x43; 32]).unwrap();
+        let mut forged = Attestation::sign(&other_key, &Data(7));
+        forged.signer = attestation.signer;
+        assert_eq!(forged.verify(&Data(7)), Err(InvalidAttestation));
+    }
+}
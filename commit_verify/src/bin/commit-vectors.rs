@@ -0,0 +1,344 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generator of JSON test vectors for the commitment schemes of the library,
+//! to be consumed by the implementations in other languages.
+//!
+//! All inputs are deterministic, such that the output changes only if a
+//! commitment scheme changes. Byte strings are hex-encoded; proofs are given
+//! in their strict serialization or as lists of hashes.
+
+use amplify::confinement::{Confined, U16, U32};
+use amplify::hex::ToHex;
+use amplify::num::u5;
+use commit_verify::blob::{BlobCommitment, BlobProof};
+use commit_verify::merkle::MerkleNode;
+use commit_verify::mnemonic::mnemonic;
+use commit_verify::mpc::{
+    Commitment, CommitmentLog, MerkleBlock, MerkleTree, Message, MultiSource, ProtocolId,
+};
+use commit_verify::pedersen::{Generators, PedersenCommitment, PedersenOpening, PedersenProtocol};
+use commit_verify::structured::{FieldValue, StructuredCommit};
+use commit_verify::{CommitVerify, CommitmentId, CommitmentProtocol};
+use serde_json::{json, Value};
+use strict_encoding::StrictSerialize;
+
+const VECTORS_VERSION: u8 = 2;
+const MERKLE_TAG: [u8; 16] = *b"urn:lnpbp:test:v";
+
+struct VectorProtocol;
+impl CommitmentProtocol for VectorProtocol {}
+impl PedersenProtocol for VectorProtocol {
+    const TAG: [u8; 32] = *b"urn:lnpbp:test:vectors:pedersen#";
+}
+
+struct Fields(Vec<FieldValue>);
+impl StructuredCommit for Fields {
+    const MERKLE_TAG: [u8; 16] = MERKLE_TAG;
+
+    fn fields(&self) -> Vec<FieldValue> { self.0.clone() }
+}
+
+fn hashes<'a>(nodes: impl IntoIterator<Item = &'a MerkleNode>) -> Vec<String> {
+    nodes.into_iter().map(MerkleNode::to_hex).collect()
+}
+
+fn mpc_vectors() -> Vec<Value> {
+    [1u8, 3, 8, 17]
+        .into_iter()
+        .map(|count| {
+            let messages =
+                (0..count).map(|no| (ProtocolId::from([no; 32]), Message::from([0x80 | no; 32])));
+            let mut source =
+                MultiSource::try_from_iter(messages).expect("small number of messages");
+            source.min_depth = u5::ZERO;
+            source.static_entropy = Some(0xFEED_0000 + count as u64);
+            let tree = MerkleTree::try_commit(&source).expect("small number of messages");
+            let block = MerkleBlock::from(&tree);
+            let messages = source
+                .messages
+                .iter()
+                .map(|(protocol_id, message)| {
+                    let proof = block
+                        .to_merkle_proof(*protocol_id)
+                        .expect("protocol is in the tree");
+                    json!({
+                        "protocolId": protocol_id.to_hex(),
+                        "message": message.to_hex(),
+                        "proof": proof.to_strict_serialized::<U16>().expect("small proof").to_hex(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "entropy": source.static_entropy,
+                "messages": messages,
+                "depth": tree.depth().to_u8(),
+                "cofactor": block.cofactor(),
+                "commitment": tree.commitment_id().to_hex(),
+                "block": block.to_strict_serialized::<U32>().expect("small block").to_hex(),
+            })
+        })
+        .collect()
+}
+
+fn merkle_vectors() -> Vec<Value> {
+    [1u8, 2, 3, 7, 8]
+        .into_iter()
+        .map(|count| {
+            let leaves = (0..count)
+                .map(|no| MerkleNode::from([no; 32]))
+                .collect::<Vec<_>>();
+            let leaves = Confined::<_, 0, { u16::MAX as usize }>::try_from(leaves)
+                .expect("small number of leaves");
+            let root = MerkleNode::merklize(MERKLE_TAG, &leaves);
+            json!({
+                "tag": MERKLE_TAG.to_hex(),
+                "leaves": leaves.iter().map(MerkleNode::to_hex).collect::<Vec<_>>(),
+                "root": root.to_hex(),
+            })
+        })
+        .collect()
+}
+
+fn blob_vectors() -> Vec<Value> {
+    [(0usize, 4u32), (1, 4), (9, 4), (16, 4), (1000, 64)]
+        .into_iter()
+        .map(|(len, chunk_size)| {
+            let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+            let commitment = BlobCommitment::with_chunk_size(&data, chunk_size);
+            let range = if len > 2 {
+                Some((1, len as u64 / 2))
+            } else {
+                None
+            };
+            let proof = range.map(|(offset, len)| {
+                let proof = BlobProof::prove_range(&data, chunk_size, offset, len)
+                    .expect("range inside the data");
+                json!({
+                    "offset": offset,
+                    "len": len,
+                    "proof": proof.to_strict_serialized::<U32>().expect("small proof").to_hex(),
+                })
+            });
+            json!({
+                "data": data.to_hex(),
+                "chunkSize": chunk_size,
+                "commitment": commitment.to_strict_serialized::<U16>().expect("small").to_hex(),
+                "id": commitment.commitment_id().to_hex(),
+                "rangeProof": proof,
+            })
+        })
+        .collect()
+}
+
+fn protocol_name_vectors() -> Vec<Value> {
+    ["RGB", "  rgb  contracts ", "LNPBP\tStandards"]
+        .into_iter()
+        .map(|name| {
+            let protocol_id = ProtocolId::named(name).expect("valid name");
+            json!({
+                "name": name,
+                "normalized": ProtocolId::normalize_name(name).expect("valid name"),
+                "protocolId": protocol_id.to_hex(),
+            })
+        })
+        .collect()
+}
+
+fn placement_vectors() -> Vec<Value> {
+    [1u8, 5, 12, 40]
+        .into_iter()
+        .map(|count| {
+            let messages = (0..count).map(|no| {
+                let protocol_id =
+                    ProtocolId::named(&format!("placement {no}")).expect("valid name");
+                (protocol_id, Message::from([no; 32]))
+            });
+            let mut source =
+                MultiSource::try_from_iter(messages).expect("small number of messages");
+            source.static_entropy = Some(count as u64);
+            let tree = MerkleTree::try_commit(&source).expect("small number of messages");
+            let positions = source
+                .messages
+                .keys()
+                .map(|protocol_id| {
+                    json!({
+                        "protocolId": protocol_id.to_hex(),
+                        "pos": tree.protocol_id_pos(*protocol_id),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "width": tree.width(),
+                "cofactor": MerkleBlock::from(&tree).cofactor(),
+                "positions": positions,
+            })
+        })
+        .collect()
+}
+
+fn field_vectors() -> Vec<Value> {
+    [1u8, 2, 3, 5, 8]
+        .into_iter()
+        .map(|count| {
+            let fields = (0..count)
+                .map(|no| FieldValue::with(&format!("field.{no}"), &(no as u64 * 100)))
+                .collect::<Vec<_>>();
+            let structure = Fields(fields);
+            let proofs = structure
+                .fields()
+                .iter()
+                .map(|field| {
+                    let proof = structure
+                        .prove_field(&field.path)
+                        .expect("structure has few fields");
+                    json!({
+                        "path": field.path.as_str(),
+                        "value": field.value.to_hex(),
+                        "leaf": field.to_merkle_node().to_hex(),
+                        "pos": proof.pos(),
+                        "width": proof.width(),
+                        "proof": hashes(proof.path()),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "tag": MERKLE_TAG.to_hex(),
+                "fields": proofs,
+                "root": structure
+                    .structured_root()
+                    .expect("vector structures have few fields")
+                    .to_hex(),
+            })
+        })
+        .collect()
+}
+
+fn log_vectors() -> Vec<Value> {
+    [1u8, 2, 3, 5, 8]
+        .into_iter()
+        .map(|size| {
+            let mut log = CommitmentLog::new();
+            for no in 0..size {
+                log.append(Commitment::from([no; 32]));
+            }
+            let size = size as u64;
+            let inclusion = (0..size)
+                .map(|index| {
+                    let proof = log.prove(index).expect("index inside the log");
+                    json!({
+                        "index": index,
+                        "proof": hashes(proof.path()),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let consistency = (1..size)
+                .map(|old_size| {
+                    let proof = log
+                        .prove_consistency(old_size, size)
+                        .expect("sizes inside the log");
+                    json!({
+                        "oldSize": old_size,
+                        "oldRoot": log.root_at(old_size).expect("non-empty log").to_hex(),
+                        "proof": hashes(proof.path()),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "commitments": (0..size as u8).map(|no| [no; 32].to_hex()).collect::<Vec<_>>(),
+                "size": size,
+                "root": log.root().expect("non-empty log").to_hex(),
+                "inclusion": inclusion,
+                "consistency": consistency,
+            })
+        })
+        .collect()
+}
+
+fn pedersen_vectors() -> Value {
+    let generators = Generators::of::<VectorProtocol>();
+    let commitments = [(0u64, [0x01u8; 32]), (1, [0x02; 32]), (u64::MAX, [0xFE; 32])]
+        .into_iter()
+        .map(|(value, blinding)| {
+            let opening = PedersenOpening::with_value(value, blinding);
+            let commitment =
+                <PedersenCommitment as CommitVerify<_, VectorProtocol>>::commit(&opening);
+            json!({
+                "value": value,
+                "blinding": blinding.to_hex(),
+                "commitment": commitment.to_byte_array().to_hex(),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "tag": VectorProtocol::TAG.to_hex(),
+        "valueGenerator": generators.value.compress().to_bytes().to_hex(),
+        "blindingGenerator": generators.blinding.compress().to_bytes().to_hex(),
+        "commitments": commitments,
+    })
+}
+
+fn mnemonic_vectors() -> Vec<Value> {
+    [
+        [0x00u8; 32],
+        [0xFF; 32],
+        ProtocolId::named("RGB")
+            .expect("valid name")
+            .to_byte_array(),
+    ]
+    .into_iter()
+    .map(|id| {
+        json!({
+            "id": id.to_hex(),
+            "mnemonic": mnemonic(&id.into()),
+        })
+    })
+    .collect()
+}
+
+fn vectors() -> Value {
+    json!({
+        "version": VECTORS_VERSION,
+        "mpc": mpc_vectors(),
+        "merkle": merkle_vectors(),
+        "blob": blob_vectors(),
+        "protocolNames": protocol_name_vectors(),
+        "placement": placement_vectors(),
+        "fields": field_vectors(),
+        "log": log_vectors(),
+        "pedersen": pedersen_vectors(),
+        "mnemonic": mnemonic_vectors(),
+    })
+}
+
+fn main() {
+    println!("{}", serde_json::to_string_pretty(&vectors()).expect("valid JSON"));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        assert_eq!(vectors(), vectors());
+    }
+}
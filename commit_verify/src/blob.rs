@@ -0,0 +1,700 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Commitments to large byte blobs (files, media etc), which do not fit
+//! confined collection sizes.
+//!
+//! The blob is split into fixed-size chunks, which are folded into a merkle
+//! tree of the same shape as defined in [RFC 6962] (the left subtree always
+//! being the largest perfect binary tree). This allows both streaming
+//! computation of the commitment with [`BlobHasher`], which doesn't require the
+//! whole blob to be kept in memory, and proofs of inclusion of the individual
+//! chunks with [`ChunkProof`].
+//!
+//! [RFC 6962]: https://www.rfc-editor.org/rfc/rfc6962#section-2.1
+
+use std::io;
+use std::num::NonZeroU32;
+
+use amplify::confinement::{Confined, LargeVec, TinyVec, U32};
+use amplify::Bytes32;
+#[cfg(feature = "async")]
+use futures_util::{Stream, StreamExt};
+use sha2::Sha256;
+use strict_encoding::{
+    DecodeError, StrictDecode, StrictDeserialize, StrictEncode, StrictSerialize, StrictType,
+    TypeName, TypedRead, TypedWrite,
+};
+
+use crate::digest::DigestExt;
+use crate::mpc::Message;
+use crate::rfc6962::{audit_path, root_from_path, split_point, tree_root};
+use crate::{CommitmentId, ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+pub const BLOB_LEAF_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0009:blobleaf#23A";
+pub const BLOB_NODE_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0009:blobnode#23A";
+
+/// Default size of a blob chunk (64 kiB).
+pub const BLOB_DEFAULT_CHUNK_SIZE: u32 = 1 << 16;
+
+/// Computes hash of a single blob chunk.
+pub fn blob_leaf(chunk: &[u8]) -> Bytes32 {
+    let mut engine = Sha256::from_tag(BLOB_LEAF_TAG);
+    engine.input_with_len::<U32>(chunk);
+    engine.finish().into()
+}
+
+/// Computes hash of a blob merkle tree node from its two children.
+pub fn blob_node(left: Bytes32, right: Bytes32) -> Bytes32 {
+    let mut engine = Sha256::from_tag(BLOB_NODE_TAG);
+    engine.input_raw(left.as_slice());
+    engine.input_raw(right.as_slice());
+    engine.finish().into()
+}
+
+/// Splits the blob into chunks and computes their hashes.
+pub(crate) fn blob_leaves(data: &[u8], chunk_size: NonZeroU32) -> Vec<Bytes32> {
+    if data.is_empty() {
+        return vec![blob_leaf(&[])];
+    }
+    data.chunks(chunk_size.get() as usize)
+        .map(blob_leaf)
+        .collect()
+}
+
+/// Computes merkle root over the leaves using RFC 6962 tree shape.
+pub(crate) fn blob_root(leaves: &[Bytes32]) -> Bytes32 {
+    debug_assert!(!leaves.is_empty(), "blob always has at least one leaf");
+    tree_root(leaves, blob_node)
+}
+
+/// Size of the blob chunks, which is never zero.
+///
+/// The size is strict-encoded in exactly the same way as `u32`, so it doesn't
+/// introduce a new type into the strict type library, but zero sizes are
+/// rejected during the decoding.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", try_from = "u32", into = "u32")
+)]
+struct ChunkSize(NonZeroU32);
+
+impl Default for ChunkSize {
+    fn default() -> Self {
+        ChunkSize(NonZeroU32::new(BLOB_DEFAULT_CHUNK_SIZE).expect("non-zero constant"))
+    }
+}
+
+impl TryFrom<u32> for ChunkSize {
+    type Error = BlobProofError;
+
+    fn try_from(chunk_size: u32) -> Result<Self, Self::Error> {
+        NonZeroU32::new(chunk_size)
+            .map(ChunkSize)
+            .ok_or(BlobProofError::ZeroChunkSize)
+    }
+}
+
+impl From<ChunkSize> for u32 {
+    fn from(chunk_size: ChunkSize) -> Self { chunk_size.0.get() }
+}
+
+impl StrictType for ChunkSize {
+    const STRICT_LIB_NAME: &'static str = <u32 as StrictType>::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { None }
+}
+
+impl StrictEncode for ChunkSize {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.0.get().strict_encode(writer)
+    }
+}
+
+impl StrictDecode for ChunkSize {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        ChunkSize::try_from(u32::strict_decode(reader)?)
+            .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
+    }
+}
+
+/// Commitment to a byte blob of an arbitrary length.
+#[derive(Getters, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[derive(CommitEncode)]
+#[commit_encode(crate = crate, strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BlobCommitment {
+    /// Length of the blob, in bytes.
+    #[getter(as_copy)]
+    len: u64,
+
+    /// Size of the chunks used for the merklization.
+    #[getter(skip)]
+    chunk_size: ChunkSize,
+
+    /// Merkle root of the chunks.
+    #[getter(as_copy)]
+    root: Bytes32,
+}
+
+impl StrictSerialize for BlobCommitment {}
+impl StrictDeserialize for BlobCommitment {}
+
+impl CommitmentId for BlobCommitment {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0009:blob:v01#23A";
+    type Id = Message;
+}
+
+impl BlobCommitment {
+    /// Commits to the blob using [`BLOB_DEFAULT_CHUNK_SIZE`].
+    pub fn commit(data: &[u8]) -> Self { Self::with_chunk_size(data, BLOB_DEFAULT_CHUNK_SIZE) }
+
+    /// Commits to the blob using a custom chunk size.
+    ///
+    /// # Panics
+    ///
+    /// If the chunk size is zero.
+    pub fn with_chunk_size(data: &[u8], chunk_size: u32) -> Self {
+        let mut hasher = BlobHasher::new(chunk_size);
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    /// Returns size of the chunks used for the merklization, which is never
+    /// zero.
+    pub fn chunk_size(&self) -> u32 { self.chunk_size.into() }
+
+    /// Returns number of chunks in the blob. Empty blob consists of a single
+    /// empty chunk.
+    pub fn chunk_count(&self) -> u64 {
+        if self.len == 0 {
+            return 1;
+        }
+        (self.len - 1) / self.chunk_size() as u64 + 1
+    }
+
+    /// Returns expected length of the chunk with the given index.
+    pub fn chunk_len(&self, index: u64) -> Option<u32> {
+        let count = self.chunk_count();
+        if index >= count {
+            return None;
+        }
+        if index + 1 < count {
+            return Some(self.chunk_size());
+        }
+        Some((self.len - index * self.chunk_size() as u64) as u32)
+    }
+
+    /// Produces message, which can be used in LNPBP-4 commitments.
+    pub fn to_message(&self) -> Message { self.commitment_id() }
+
+    /// Checks that the commitment matches the provided data.
+    pub fn verify(&self, data: &[u8]) -> bool {
+        Self::with_chunk_size(data, self.chunk_size()) == *self
+    }
+}
+
+/// Incremental (streaming) computation of [`BlobCommitment`].
+///
+/// Keeps in memory only a single chunk and a logarithmic number of merkle
+/// nodes.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BlobHasher {
+    chunk_size: ChunkSize,
+    len: u64,
+    chunk: Vec<u8>,
+    stack: Vec<(u8, Bytes32)>,
+}
+
+impl Default for BlobHasher {
+    fn default() -> Self { BlobHasher::new(BLOB_DEFAULT_CHUNK_SIZE) }
+}
+
+impl BlobHasher {
+    /// Constructs hasher with the given chunk size.
+    ///
+    /// # Panics
+    ///
+    /// If the chunk size is zero.
+    pub fn new(chunk_size: u32) -> Self {
+        let chunk_size = ChunkSize::try_from(chunk_size).expect("blob chunk size must be non-zero");
+        BlobHasher {
+            chunk_size,
+            len: 0,
+            chunk: Vec::with_capacity(u32::from(chunk_size) as usize),
+            stack: vec![],
+        }
+    }
+
+    /// Returns number of bytes processed so far.
+    pub fn len(&self) -> u64 { self.len }
+
+    /// Detects whether no data were processed so far.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Adds more data to the blob.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        let chunk_size = u32::from(self.chunk_size) as usize;
+        while !data.is_empty() {
+            let rest = chunk_size - self.chunk.len();
+            let (head, tail) = data.split_at(rest.min(data.len()));
+            self.chunk.extend_from_slice(head);
+            data = tail;
+            if self.chunk.len() == chunk_size {
+                let leaf = blob_leaf(&self.chunk);
+                self.chunk.clear();
+                self.push_leaf(leaf);
+            }
+        }
+    }
+
+    fn push_leaf(&mut self, leaf: Bytes32) {
+        let mut node = (0u8, leaf);
+        while let Some((height, left)) = self.stack.last().copied() {
+            if height != node.0 {
+                break;
+            }
+            self.stack.pop();
+            node = (height + 1, blob_node(left, node.1));
+        }
+        self.stack.push(node);
+    }
+
+    /// Completes computation of the commitment.
+    pub fn finish(mut self) -> BlobCommitment {
+        if !self.chunk.is_empty() || self.len == 0 {
+            let leaf = blob_leaf(&self.chunk);
+            self.push_leaf(leaf);
+        }
+        let (_, mut root) = self.stack.pop().expect("at least one leaf is present");
+        while let Some((_, left)) = self.stack.pop() {
+            root = blob_node(left, root);
+        }
+        BlobCommitment {
+            len: self.len,
+            chunk_size: self.chunk_size,
+            root,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl BlobHasher {
+    /// Consumes all data from the async `stream`.
+    ///
+    /// The stream is polled for the next item only after the previous one is
+    /// hashed, so the producer is backpressured by the hashing speed and no
+    /// more than a single item and a single chunk are kept in memory.
+    pub async fn update_stream<S>(&mut self, stream: S)
+    where
+        S: Stream,
+        S::Item: AsRef<[u8]>,
+    {
+        futures_util::pin_mut!(stream);
+        while let Some(data) = stream.next().await {
+            self.update(data.as_ref());
+        }
+    }
+}
+
+/// Commits to a blob arriving as an async `stream` of byte buffers (like
+/// `bytes::Bytes`) using [`BLOB_DEFAULT_CHUNK_SIZE`], producing message which
+/// can be used in LNPBP-4 commitments.
+///
+/// See [`BlobHasher::update_stream`] for the details.
+#[cfg(feature = "async")]
+pub async fn commit_stream<S>(stream: S) -> Message
+where
+    S: Stream,
+    S::Item: AsRef<[u8]>,
+{
+    let mut hasher = BlobHasher::default();
+    hasher.update_stream(stream).await;
+    hasher.finish().to_message()
+}
+
+impl io::Write for BlobHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Proof of inclusion of a single chunk into [`BlobCommitment`].
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ChunkProof {
+    /// Index of the chunk.
+    #[getter(as_copy)]
+    index: u64,
+
+    /// Merkle path from the chunk to the root, starting from the bottom.
+    path: TinyVec<Bytes32>,
+}
+
+impl ChunkProof {
+    /// Constructs proof for the chunk with the given `index` of the blob
+    /// `data`. Returns `None` if the index is out of the blob bounds or the
+    /// chunk size is zero.
+    pub fn with(data: &[u8], chunk_size: u32, index: u64) -> Option<Self> {
+        let leaves = blob_leaves(data, NonZeroU32::new(chunk_size)?);
+        if index >= leaves.len() as u64 {
+            return None;
+        }
+        let mut path = vec![];
+        audit_path(index as usize, &leaves, blob_node, &mut path);
+        Some(ChunkProof {
+            index,
+            path: Confined::try_from(path).expect("tree depth never exceeds 64"),
+        })
+    }
+
+    /// Verifies that the `chunk` is a part of the blob with the given
+    /// `commitment`.
+    pub fn verify(&self, commitment: &BlobCommitment, chunk: &[u8]) -> bool {
+        if commitment.chunk_len(self.index) != Some(chunk.len() as u32) {
+            return false;
+        }
+        let leaf = blob_leaf(chunk);
+        root_from_path(self.index, commitment.chunk_count(), leaf, &self.path, blob_node) ==
+            Some(commitment.root)
+    }
+}
+
+/// Errors constructing or verifying [`BlobProof`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum BlobProofError {
+    /// proven byte range must not be empty.
+    EmptyRange,
+
+    /// byte range starting at {0} with length {1} is out of the blob bounds.
+    OutOfBounds(u64, u64),
+
+    /// byte range is too large to be proven with a single proof.
+    TooLarge,
+
+    /// chunk data in the proof do not match the proven byte range.
+    DataMismatch,
+
+    /// blob proof doesn't match the blob commitment.
+    RootMismatch,
+
+    /// blob chunk size must not be zero.
+    ZeroChunkSize,
+}
+
+impl ErrorCode for BlobProofError {
+    fn code(&self) -> &'static str {
+        match self {
+            BlobProofError::EmptyRange => "blob.empty-range",
+            BlobProofError::OutOfBounds(..) => "blob.out-of-bounds",
+            BlobProofError::TooLarge => "blob.too-large",
+            BlobProofError::DataMismatch => "blob.data-mismatch",
+            BlobProofError::RootMismatch => "blob.root-mismatch",
+            BlobProofError::ZeroChunkSize => "blob.zero-chunk-size",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            BlobProofError::OutOfBounds(start, len) => {
+                vec![("start", start.to_string()), ("len", len.to_string())]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl ErrorCodes for BlobProofError {
+    const CODES: &'static [&'static str] = &[
+        "blob.empty-range",
+        "blob.out-of-bounds",
+        "blob.too-large",
+        "blob.data-mismatch",
+        "blob.root-mismatch",
+        "blob.zero-chunk-size",
+    ];
+}
+
+/// Proof that a byte range is a part of the blob with a given
+/// [`BlobCommitment`].
+///
+/// The proof contains full data of all chunks overlapping with the range and
+/// the roots of all merkle subtrees which don't overlap with it.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BlobProof {
+    /// Offset of the proven byte range.
+    #[getter(as_copy)]
+    offset: u64,
+
+    /// Length of the proven byte range.
+    #[getter(as_copy)]
+    len: u64,
+
+    /// Data of the chunks overlapping with the proven range.
+    chunks: LargeVec<u8>,
+
+    /// Roots of the subtrees not overlapping with the range, in left-to-right
+    /// order.
+    path: TinyVec<Bytes32>,
+}
+
+impl StrictSerialize for BlobProof {}
+impl StrictDeserialize for BlobProof {}
+
+impl BlobProof {
+    /// Constructs proof for `len` bytes of the blob `data` starting at
+    /// `offset`.
+    pub fn prove_range(
+        data: &[u8],
+        chunk_size: u32,
+        offset: u64,
+        len: u64,
+    ) -> Result<Self, BlobProofError> {
+        let chunk_size = ChunkSize::try_from(chunk_size)?.0;
+        let (first, last) = chunk_range(data.len() as u64, chunk_size, offset, len)?;
+        let leaves = blob_leaves(data, chunk_size);
+        let mut path = vec![];
+        range_path(&leaves, 0, first, last + 1, &mut path);
+
+        let start = (first * chunk_size.get() as u64) as usize;
+        let end = ((last + 1) * chunk_size.get() as u64).min(data.len() as u64) as usize;
+        Ok(BlobProof {
+            offset,
+            len,
+            chunks: Confined::try_from(data[start..end].to_vec())
+                .map_err(|_| BlobProofError::TooLarge)?,
+            path: Confined::try_from(path).expect("tree depth never exceeds 64"),
+        })
+    }
+
+    /// Verifies the proof against the blob `commitment`, returning the
+    /// attested bytes of the range.
+    pub fn verify(&self, commitment: &BlobCommitment) -> Result<&[u8], BlobProofError> {
+        let chunk_size = commitment.chunk_size.0;
+        let (first, last) = chunk_range(commitment.len, chunk_size, self.offset, self.len)?;
+        let start = first * chunk_size.get() as u64;
+        let end = ((last + 1) * chunk_size.get() as u64).min(commitment.len);
+        if self.chunks.len() as u64 != end - start {
+            return Err(BlobProofError::DataMismatch);
+        }
+
+        let mut leaves = self.chunks.chunks(chunk_size.get() as usize).map(blob_leaf);
+        let mut path = self.path.iter().copied();
+        let root = range_root(0, commitment.chunk_count(), first, last + 1, &mut leaves, &mut path);
+        if root != Some(commitment.root) || leaves.next().is_some() || path.next().is_some() {
+            return Err(BlobProofError::RootMismatch);
+        }
+
+        let from = (self.offset - start) as usize;
+        Ok(&self.chunks[from..from + self.len as usize])
+    }
+}
+
+/// Returns indexes of the first and the last chunks overlapping with the byte
+/// range.
+fn chunk_range(
+    blob_len: u64,
+    chunk_size: NonZeroU32,
+    offset: u64,
+    len: u64,
+) -> Result<(u64, u64), BlobProofError> {
+    let chunk_size = chunk_size.get();
+    if len == 0 {
+        return Err(BlobProofError::EmptyRange);
+    }
+    match offset.checked_add(len) {
+        Some(end) if end <= blob_len => {
+            Ok((offset / chunk_size as u64, (end - 1) / chunk_size as u64))
+        }
+        _ => Err(BlobProofError::OutOfBounds(offset, len)),
+    }
+}
+
+fn range_path(leaves: &[Bytes32], start: u64, lo: u64, hi: u64, path: &mut Vec<Bytes32>) {
+    let n = leaves.len() as u64;
+    if start + n <= lo || start >= hi {
+        path.push(blob_root(leaves));
+        return;
+    }
+    if n == 1 {
+        return;
+    }
+    let k = split_point(n);
+    range_path(&leaves[..k as usize], start, lo, hi, path);
+    range_path(&leaves[k as usize..], start + k, lo, hi, path);
+}
+
+fn range_root(
+    start: u64,
+    count: u64,
+    lo: u64,
+    hi: u64,
+    leaves: &mut impl Iterator<Item = Bytes32>,
+    path: &mut impl Iterator<Item = Bytes32>,
+) -> Option<Bytes32> {
+    if start + count <= lo || start >= hi {
+        return path.next();
+    }
+    if count == 1 {
+        return leaves.next();
+    }
+    let k = split_point(count);
+    let left = range_root(start, k, lo, hi, leaves, path)?;
+    let right = range_root(start + k, count - k, lo, hi, leaves, path)?;
+    Some(blob_node(left, right))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use amplify::confinement::U16;
+
+    use super::*;
+
+    fn data(len: usize) -> Vec<u8> { (0..len).map(|i| (i % 251) as u8).collect() }
+
+    #[test]
+    fn streaming_matches_batch() {
+        for len in [0usize, 1, 15, 16, 17, 64, 100, 1000] {
+            let data = data(len);
+            let chunks = if data.is_empty() {
+                vec![blob_leaf(&[])]
+            } else {
+                data.chunks(16).map(blob_leaf).collect()
+            };
+            let commitment = BlobCommitment::with_chunk_size(&data, 16);
+            assert_eq!(commitment.root, blob_root(&chunks));
+            assert_eq!(commitment.chunk_count(), chunks.len() as u64);
+
+            let mut hasher = BlobHasher::new(16);
+            for piece in data.chunks(7) {
+                hasher.write_all(piece).unwrap();
+            }
+            assert_eq!(hasher.finish(), commitment);
+            assert!(commitment.verify(&data));
+        }
+    }
+
+    #[test]
+    fn chunk_proofs() {
+        for len in [0usize, 10, 16, 33, 100, 257] {
+            let data = data(len);
+            let commitment = BlobCommitment::with_chunk_size(&data, 16);
+            for index in 0..commitment.chunk_count() {
+                let proof = ChunkProof::with(&data, 16, index).unwrap();
+                let start = index as usize * 16;
+                let chunk = &data[start..(start + 16).min(len)];
+                assert!(proof.verify(&commitment, chunk), "len {len}, chunk {index}");
+                if !chunk.is_empty() {
+                    let mut other = chunk.to_vec();
+                    other[0] ^= 0xFF;
+                    assert!(!proof.verify(&commitment, &other));
+                }
+            }
+            assert!(ChunkProof::with(&data, 16, commitment.chunk_count()).is_none());
+        }
+    }
+
+    #[test]
+    fn message() {
+        let commitment = BlobCommitment::commit(b"some file");
+        assert_ne!(commitment.to_message(), BlobCommitment::commit(b"other file").to_message());
+        assert_ne!(
+            commitment.to_message(),
+            BlobCommitment::with_chunk_size(b"some file", 4).to_message()
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn stream() {
+        let data = data(200_000);
+        let stream = futures_util::stream::iter(data.chunks(1000).map(<[u8]>::to_vec));
+        let message = futures_executor::block_on(commit_stream(stream));
+        assert_eq!(message, BlobCommitment::commit(&data).to_message());
+    }
+
+    #[test]
+    fn range_proofs() {
+        let data = data(5000);
+        let commitment = BlobCommitment::with_chunk_size(&data, 256);
+        for (offset, len) in [(1024, 1024), (0, 1), (0, 5000), (100, 300), (4999, 1), (255, 2)] {
+            let proof = BlobProof::prove_range(&data, 256, offset, len).unwrap();
+            let attested = proof.verify(&commitment).unwrap();
+            assert_eq!(attested, &data[offset as usize..(offset + len) as usize]);
+        }
+
+        let mut proof = BlobProof::prove_range(&data, 256, 1024, 1024).unwrap();
+        let mut chunks = proof.chunks.to_vec();
+        chunks[10] ^= 0xFF;
+        proof.chunks = Confined::try_from(chunks).unwrap();
+        assert_eq!(proof.verify(&commitment), Err(BlobProofError::RootMismatch));
+
+        let other = BlobCommitment::with_chunk_size(&data[..4999], 256);
+        let proof = BlobProof::prove_range(&data, 256, 1024, 1024).unwrap();
+        assert_eq!(proof.verify(&other), Err(BlobProofError::RootMismatch));
+
+        assert_eq!(BlobProof::prove_range(&data, 256, 10, 0), Err(BlobProofError::EmptyRange));
+        assert_eq!(
+            BlobProof::prove_range(&data, 256, 4990, 11),
+            Err(BlobProofError::OutOfBounds(4990, 11))
+        );
+        assert_eq!(BlobProof::prove_range(&data, 0, 0, 1), Err(BlobProofError::ZeroChunkSize));
+    }
+
+    #[test]
+    fn zero_chunk_size() {
+        assert_eq!(ChunkProof::with(b"data", 0, 0), None);
+
+        let commitment = BlobCommitment::commit(b"data");
+        let mut bytes = commitment.to_strict_serialized::<U16>().unwrap().release();
+        // chunk size follows the 8-byte blob length
+        bytes[8..12].copy_from_slice(&[0; 4]);
+        let bytes = Confined::try_from(bytes).unwrap();
+        assert!(BlobCommitment::from_strict_serialized::<U16>(bytes).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Negotiation of the proof formats between peers.
+//!
+//! Peers exchanging proofs may run different versions of the library, or
+//! versions compiled with different features. Before the exchange each peer
+//! announces its [`Capabilities`], and both use the common subset returned by
+//! [`Capabilities::negotiate`].
+
+use std::ops::{BitAnd, BitOr};
+
+use amplify::num::u5;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+/// Bit flags of the commitment schemes and proof encodings supported by a
+/// peer.
+#[derive(Wrapper, Copy, Clone, PartialEq, Eq, Hash, Debug, Default, From)]
+#[wrapper(Deref)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct CapabilityFlags(u32);
+
+impl CapabilityFlags {
+    /// LNPBP-4 multi-protocol commitments.
+    pub const MPC: Self = CapabilityFlags(1 << 0);
+    /// LNPBP-81 merkle trees over blobs.
+    pub const BLOB: Self = CapabilityFlags(1 << 1);
+    /// Inclusion proofs into append-only commitment logs.
+    pub const LOG: Self = CapabilityFlags(1 << 2);
+    /// BIP-340 attestations over commitment ids.
+    pub const ATTESTATION: Self = CapabilityFlags(1 << 3);
+    /// Pedersen commitments over ristretto255.
+    pub const PEDERSEN: Self = CapabilityFlags(1 << 4);
+
+    /// Strict encoding of the proofs.
+    pub const STRICT: Self = CapabilityFlags(1 << 16);
+    /// Zstd compression of strict-encoded proofs.
+    pub const ZSTD: Self = CapabilityFlags(1 << 17);
+    /// JSON encoding of the proofs with serde.
+    pub const JSON: Self = CapabilityFlags(1 << 18);
+
+    /// Mask of the flags defining commitment schemes.
+    pub const SCHEMES: Self = CapabilityFlags(0x0000_FFFF);
+    /// Mask of the flags defining proof encodings.
+    pub const ENCODINGS: Self = CapabilityFlags(0xFFFF_0000);
+
+    /// Constructs flags without any capability set.
+    pub const fn empty() -> Self { CapabilityFlags(0) }
+
+    /// Returns raw bits of the flags.
+    pub const fn bits(self) -> u32 { self.0 }
+
+    /// Detects whether all the flags from `other` are set.
+    pub const fn contains(self, other: Self) -> bool { self.0 & other.0 == other.0 }
+
+    /// Detects whether any of the flags from `other` is set.
+    pub const fn intersects(self, other: Self) -> bool { self.0 & other.0 != 0 }
+
+    /// Detects whether no flags are set.
+    pub const fn is_empty(self) -> bool { self.0 == 0 }
+}
+
+impl BitOr for CapabilityFlags {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self { CapabilityFlags(self.0 | rhs.0) }
+}
+
+impl BitAnd for CapabilityFlags {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self { CapabilityFlags(self.0 & rhs.0) }
+}
+
+/// Errors negotiating [`Capabilities`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum NegotiationError {
+    /// peers don't share any commitment scheme.
+    NoCommonScheme,
+
+    /// peers don't share any proof encoding.
+    NoCommonEncoding,
+}
+
+impl ErrorCode for NegotiationError {
+    fn code(&self) -> &'static str {
+        match self {
+            NegotiationError::NoCommonScheme => "capabilities.no-common-scheme",
+            NegotiationError::NoCommonEncoding => "capabilities.no-common-encoding",
+        }
+    }
+}
+
+impl ErrorCodes for NegotiationError {
+    const CODES: &'static [&'static str] =
+        &["capabilities.no-common-scheme", "capabilities.no-common-encoding"];
+}
+
+/// Capabilities of a peer exchanging proofs.
+#[derive(Getters, Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Capabilities {
+    /// Supported commitment schemes and proof encodings.
+    #[getter(as_copy)]
+    flags: CapabilityFlags,
+
+    /// Maximal depth of LNPBP-4 trees accepted by the peer.
+    #[getter(as_copy)]
+    max_depth: u5,
+}
+
+impl StrictSerialize for Capabilities {}
+impl StrictDeserialize for Capabilities {}
+
+impl Capabilities {
+    /// Constructs capabilities from the flags and the maximal depth of LNPBP-4
+    /// trees.
+    pub fn with(flags: CapabilityFlags, max_depth: u5) -> Self { Capabilities { flags, max_depth } }
+
+    /// Returns capabilities of this library, as defined by its compile-time
+    /// features, accepting LNPBP-4 trees of any depth.
+    pub fn local() -> Self {
+        let mut flags = CapabilityFlags::MPC |
+            CapabilityFlags::BLOB |
+            CapabilityFlags::LOG |
+            CapabilityFlags::STRICT;
+        if cfg!(feature = "secp256k1") {
+            flags = flags | CapabilityFlags::ATTESTATION;
+        }
+        if cfg!(feature = "ristretto") {
+            flags = flags | CapabilityFlags::PEDERSEN;
+        }
+        if cfg!(feature = "zstd") {
+            flags = flags | CapabilityFlags::ZSTD;
+        }
+        if cfg!(feature = "serde") {
+            flags = flags | CapabilityFlags::JSON;
+        }
+        Capabilities::with(flags, u5::MAX)
+    }
+
+    /// Detects whether the peer supports all the `flags`.
+    pub fn supports(&self, flags: CapabilityFlags) -> bool { self.flags.contains(flags) }
+
+    /// Computes the capabilities shared with the `remote` peer: the common
+    /// commitment schemes and proof encodings and the smaller of the maximal
+    /// tree depths.
+    ///
+    /// # Errors
+    ///
+    /// If the peers have no common commitment scheme or proof encoding.
+    pub fn negotiate(&self, remote: &Capabilities) -> Result<Capabilities, NegotiationError> {
+        let flags = self.flags & remote.flags;
+        if !flags.intersects(CapabilityFlags::SCHEMES) {
+            return Err(NegotiationError::NoCommonScheme);
+        }
+        if !flags.intersects(CapabilityFlags::ENCODINGS) {
+            return Err(NegotiationError::NoCommonEncoding);
+        }
+        Ok(Capabilities::with(flags, self.max_depth.min(remote.max_depth)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn negotiate() {
+        let local = Capabilities::local();
+        assert!(local.supports(CapabilityFlags::MPC | CapabilityFlags::STRICT));
+
+        let remote = Capabilities::with(
+            CapabilityFlags::MPC | CapabilityFlags::PEDERSEN | CapabilityFlags::STRICT,
+            u5::with(16),
+        );
+        let common = local.negotiate(&remote).unwrap();
+        assert_eq!(common, remote.negotiate(&local).unwrap());
+        assert_eq!(common.max_depth(), u5::with(16));
+        assert!(common.supports(CapabilityFlags::MPC | CapabilityFlags::STRICT));
+        assert!(!common.supports(CapabilityFlags::BLOB));
+
+        let zstd_only = Capabilities::with(CapabilityFlags::MPC | CapabilityFlags::ZSTD, u5::MAX);
+        let strict_only =
+            Capabilities::with(CapabilityFlags::MPC | CapabilityFlags::STRICT, u5::MAX);
+        assert_eq!(zstd_only.negotiate(&strict_only), Err(NegotiationError::NoCommonEncoding));
+        let blob_only =
+            Capabilities::with(CapabilityFlags::BLOB | CapabilityFlags::STRICT, u5::MAX);
+        assert_eq!(blob_only.negotiate(&strict_only), Err(NegotiationError::NoCommonScheme));
+
+        let data = common.to_strict_serialized::<16>().unwrap();
+        assert_eq!(Capabilities::from_strict_serialized::<16>(data).unwrap(), common);
+    }
+}
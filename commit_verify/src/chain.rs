@@ -0,0 +1,149 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Binding of commitments to a specific chain (network).
+//!
+//! The same client-side-validated data committed on two networks (like
+//! bitcoin mainnet and testnet) produce the same commitment ids, allowing to
+//! replay a proof made for one network on the other one. [`ChainContext`]
+//! mixes a chain hash (genesis block hash or a network magic number) into the
+//! outer commitment id, such that the ids differ by construction.
+//!
+//! Anchors are bound with [`ChainCommitmentId::chain_commitment_id`]; seals
+//! and other data without their own commitment id are bound with
+//! [`ChainContext::bind`] over their [`CommitEncode`] representation.
+
+use amplify::Bytes32;
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::{CommitEncode, CommitmentId, LIB_NAME_COMMIT_VERIFY};
+
+/// Chain (network) in which the commitments are published, identified by a
+/// 32-byte chain hash.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ChainContext(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl ChainContext {
+    /// Tag of the hash binding a commit-encoded value to the chain with
+    /// [`ChainContext::bind`].
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:chain:bind:val:v01#23A";
+    /// Tag of the hash binding a commitment id to the chain with
+    /// [`ChainContext::bind_id`].
+    pub const ID_TAG: [u8; 32] = *b"urn:lnpbp:chain:bind:cid:v01#23A";
+    /// Tag of the hash deriving chain context from a network magic number.
+    pub const MAGIC_TAG: [u8; 32] = *b"urn:lnpbp:chain:netmagic:v01#23A";
+
+    /// Constructs context from the genesis block hash, in its consensus byte
+    /// order (i.e. not reversed, as it is displayed by bitcoin software).
+    pub fn from_genesis(genesis_hash: [u8; 32]) -> Self { ChainContext(genesis_hash.into()) }
+
+    /// Constructs context from the network magic number, for the networks
+    /// which have no genesis block hash.
+    pub fn from_magic(magic: u32) -> Self {
+        let mut engine = Sha256::from_tag(Self::MAGIC_TAG);
+        engine.input_raw(&magic.to_le_bytes());
+        ChainContext(engine.finish().into())
+    }
+
+    /// Computes id of the `value` commitment bound to the chain.
+    pub fn bind(&self, value: &impl CommitEncode) -> ChainBoundId {
+        let mut engine = Sha256::from_tag(Self::TAG);
+        engine.input_raw(self.0.as_slice());
+        value.commit_encode(&mut engine);
+        engine.finish().into()
+    }
+
+    /// Computes id of the `value` commitment bound to the chain, mixing in the
+    /// tag of the commitment id type, such that values of different types with
+    /// the same encoding produce different ids.
+    pub fn bind_id<T: CommitmentId>(&self, value: &T) -> ChainBoundId {
+        let mut engine = Sha256::from_tag(Self::ID_TAG);
+        engine.input_raw(self.0.as_slice());
+        engine.input_raw(&T::TAG);
+        value.commit_encode(&mut engine);
+        engine.finish().into()
+    }
+}
+
+/// Commitment id bound to a specific chain with [`ChainContext`].
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ChainBoundId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+/// Commitment ids bound to a specific chain.
+pub trait ChainCommitmentId: CommitmentId {
+    /// Computes commitment id bound to the `chain`, which differs from the
+    /// commitment id of the same data in other chains.
+    fn chain_commitment_id(&self, chain: &ChainContext) -> ChainBoundId { chain.bind_id(self) }
+}
+
+impl<T: CommitmentId> ChainCommitmentId for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::MerkleBlock;
+
+    #[test]
+    fn chain_binding() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+
+        let mainnet = ChainContext::from_genesis([1u8; 32]);
+        let testnet = ChainContext::from_genesis([2u8; 32]);
+        let custom = ChainContext::from_magic(0xDAB5BFFA);
+        assert_ne!(custom, ChainContext::from_magic(0xD9B4BEF9));
+
+        let id = tree.chain_commitment_id(&mainnet);
+        assert_eq!(id, block.chain_commitment_id(&mainnet));
+        assert_ne!(id, tree.chain_commitment_id(&testnet));
+        assert_ne!(id, tree.chain_commitment_id(&custom));
+        assert_ne!(id.to_byte_array(), tree.commitment_id().to_byte_array());
+        assert_ne!(id, mainnet.bind(&tree));
+        assert_eq!(mainnet.bind(&tree), mainnet.bind(&block));
+    }
+}
@@ -26,7 +26,7 @@ use sha2::Sha256;
 use strict_encoding::{StrictEncode, StrictWriter};
 
 use crate::digest::DigestExt;
-use crate::CommitmentProtocol;
+use crate::{CommitmentProtocol, ErrorCode, ErrorCodes};
 
 /// Error during commitment verification
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
@@ -38,6 +38,19 @@ pub enum VerifyError {
     InvalidMessage,
 }
 
+impl ErrorCode for VerifyError {
+    fn code(&self) -> &'static str {
+        match self {
+            VerifyError::InvalidCommitment => "commit.mismatch",
+            VerifyError::InvalidMessage => "commit.invalid-message",
+        }
+    }
+}
+
+impl ErrorCodes for VerifyError {
+    const CODES: &'static [&'static str] = &["commit.mismatch", "commit.invalid-message"];
+}
+
 /// Trait for commit-verify scheme.
 pub trait CommitVerify<Msg, Protocol: CommitmentProtocol>
 where Self: Eq + Sized
@@ -82,16 +95,34 @@ where Self: Eq + Sized
     }
 }
 
+/// Object-safe verification interface for the proofs carrying all data
+/// required for their verification against a 32-byte commitment.
+///
+/// Allows plugin systems to verify proofs as `dyn VerifiableProof` without
+/// compile-time knowledge of their types. Proofs requiring external data, like
+/// [`crate::mpc::MerkleProof`] requiring the message, must be converted into a
+/// self-contained form first (for instance with
+/// [`crate::mpc::MerkleBlock::with`]).
+pub trait VerifiableProof {
+    /// Verifies the proof against the `commitment`.
+    fn verify_dyn(&self, commitment: &[u8; 32]) -> Result<(), VerifyError>;
+}
+
 /// Commitment protocol which writes strict-encoded data into a hasher.
 pub struct StrictEncodedProtocol;
 
 impl CommitmentProtocol for StrictEncodedProtocol {}
 
+impl StrictEncodedProtocol {
+    /// Tag of the hash committing to the strict-encoded data.
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0007:strict:v01#A";
+}
+
 impl<T> CommitVerify<T, StrictEncodedProtocol> for Bytes32
 where T: StrictEncode
 {
     fn commit(msg: &T) -> Self {
-        let mut engine = Sha256::from_tag(*b"urn:lnpbp:lnpbp0007:strict:v01#A");
+        let mut engine = Sha256::from_tag(StrictEncodedProtocol::TAG);
         let w = StrictWriter::with(u32::MAX as usize, &mut engine);
         msg.strict_encode(w).ok();
         engine.finish().into()
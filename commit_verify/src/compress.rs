@@ -0,0 +1,226 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transparent compression of strict-serialized proofs (merkle blocks,
+//! disclosures etc) for archival storage.
+//!
+//! Archived data start with a byte specifying their [`Format`]. Plain data
+//! are followed by the strict-serialized value; compressed data are followed
+//! by the 32-bit little-endian size of the strict-serialized value and a zstd
+//! frame. Thus, archives may contain both compressed and uncompressed proofs.
+//!
+//! Delta encoding of merkle blocks against other blocks of the same tree is
+//! not provided: decoding such data would require the base block, while each
+//! archived proof must be decodable on its own. New encodings may be added as
+//! new [`Format`] variants without breaking the existing archives.
+
+use std::io;
+
+use amplify::confinement::{Confined, U32};
+use strict_encoding::{DeserializeError, SerializeError, StrictDeserialize, StrictSerialize};
+
+use crate::{ErrorCode, ErrorCodes};
+
+/// Format of the archived data, encoded as the first byte of the data.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+#[repr(u8)]
+pub enum Format {
+    /// Plain strict-serialized data.
+    Plain = 0x00,
+    /// Strict-serialized data compressed with zstd.
+    Zstd = 0x01,
+}
+
+impl TryFrom<u8> for Format {
+    type Error = CompressionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            x if x == Format::Plain as u8 => Ok(Format::Plain),
+            x if x == Format::Zstd as u8 => Ok(Format::Zstd),
+            unknown => Err(CompressionError::UnknownFormat(unknown)),
+        }
+    }
+}
+
+/// Errors compressing or decompressing data.
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum CompressionError {
+    /// unable to decompress the data: {0}
+    #[from]
+    Io(io::Error),
+
+    #[from]
+    #[display(inner)]
+    Serialize(SerializeError),
+
+    #[from]
+    #[display(inner)]
+    Deserialize(DeserializeError),
+
+    /// archived data are truncated.
+    Truncated,
+
+    /// unknown archived data format {0:#04x}.
+    UnknownFormat(u8),
+
+    /// uncompressed data size of {0} bytes exceeds the limit of {1} bytes.
+    TooLarge(usize, u32),
+
+    /// size of the decompressed data doesn't match the declared size.
+    SizeMismatch,
+}
+
+impl ErrorCode for CompressionError {
+    fn code(&self) -> &'static str {
+        match self {
+            CompressionError::Io(_) => "compress.io",
+            CompressionError::Serialize(_) => "compress.serialize",
+            CompressionError::Deserialize(_) => "compress.deserialize",
+            CompressionError::Truncated => "compress.truncated",
+            CompressionError::UnknownFormat(_) => "compress.unknown-format",
+            CompressionError::TooLarge(..) => "compress.too-large",
+            CompressionError::SizeMismatch => "compress.size-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            CompressionError::Io(err) => vec![("kind", format!("{:?}", err.kind()))],
+            CompressionError::UnknownFormat(format) => vec![("format", format.to_string())],
+            CompressionError::TooLarge(size, limit) => {
+                vec![("size", size.to_string()), ("limit", limit.to_string())]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl ErrorCodes for CompressionError {
+    const CODES: &'static [&'static str] = &[
+        "compress.io",
+        "compress.serialize",
+        "compress.deserialize",
+        "compress.truncated",
+        "compress.unknown-format",
+        "compress.too-large",
+        "compress.size-mismatch",
+    ];
+}
+
+/// Strict-serializes and compresses the `value`.
+pub fn compress<T: StrictSerialize>(value: &T) -> Result<Vec<u8>, CompressionError> {
+    archive(value, Format::Zstd)
+}
+
+/// Strict-serializes the `value` and archives it in the given `format`.
+pub fn archive<T: StrictSerialize>(value: &T, format: Format) -> Result<Vec<u8>, CompressionError> {
+    let data = value.to_strict_serialized::<U32>()?;
+    let mut res = vec![format as u8];
+    match format {
+        Format::Plain => res.extend_from_slice(&data),
+        Format::Zstd => {
+            let compressed = zstd::bulk::compress(&data, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+            res.reserve(compressed.len() + 4);
+            res.extend((data.len() as u32).to_le_bytes());
+            res.extend(compressed);
+        }
+    }
+    Ok(res)
+}
+
+/// Decompresses and strict-deserializes the `data` archived with [`archive`]
+/// or [`compress`].
+///
+/// The size of the uncompressed data must not exceed `limit` bytes.
+pub fn decompress<T: StrictDeserialize>(data: &[u8], limit: u32) -> Result<T, CompressionError> {
+    let (format, data) = data.split_first().ok_or(CompressionError::Truncated)?;
+    let data = match Format::try_from(*format)? {
+        Format::Plain => {
+            if data.len() > limit as usize {
+                return Err(CompressionError::TooLarge(data.len(), limit));
+            }
+            data.to_vec()
+        }
+        Format::Zstd => {
+            if data.len() < 4 {
+                return Err(CompressionError::Truncated);
+            }
+            let (size, frame) = data.split_at(4);
+            let size = u32::from_le_bytes([size[0], size[1], size[2], size[3]]) as usize;
+            if size > limit as usize {
+                return Err(CompressionError::TooLarge(size, limit));
+            }
+            let data = zstd::bulk::decompress(frame, size)?;
+            if data.len() != size {
+                return Err(CompressionError::SizeMismatch);
+            }
+            data
+        }
+    };
+    let len = data.len();
+    let data = Confined::try_from(data).map_err(|_| CompressionError::TooLarge(len, limit))?;
+    Ok(T::from_strict_serialized::<U32>(data)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::MerkleBlock;
+
+    #[test]
+    fn roundtrip() {
+        let tree = make_random_tree(&make_random_messages(16));
+        let block = MerkleBlock::from(&tree);
+        let size = block.to_strict_serialized::<U32>().unwrap().len();
+        let plain = archive(&block, Format::Plain).unwrap();
+        let compressed = compress(&block).unwrap();
+        assert_eq!(plain[0], Format::Plain as u8);
+        assert_eq!(compressed[0], Format::Zstd as u8);
+
+        assert_eq!(decompress::<MerkleBlock>(&compressed, u32::MAX).unwrap(), block);
+        assert_eq!(decompress::<MerkleBlock>(&plain, u32::MAX).unwrap(), block);
+
+        let limit = size as u32 - 1;
+        for data in [&plain, &compressed] {
+            assert!(matches!(
+                decompress::<MerkleBlock>(data, limit),
+                Err(CompressionError::TooLarge(s, l)) if s == size && l == limit
+            ));
+        }
+        assert!(matches!(
+            decompress::<MerkleBlock>(&compressed[..3], u32::MAX),
+            Err(CompressionError::Truncated)
+        ));
+        assert!(matches!(
+            decompress::<MerkleBlock>(&[], u32::MAX),
+            Err(CompressionError::Truncated)
+        ));
+        assert!(matches!(
+            decompress::<MerkleBlock>(&[0x02], u32::MAX),
+            Err(CompressionError::UnknownFormat(0x02))
+        ));
+        assert!(decompress::<MerkleBlock>(&compressed[..compressed.len() - 4], u32::MAX).is_err());
+    }
+}
@@ -21,7 +21,7 @@
 
 //! Convolved commitments (convolve-commit-verify scheme).
 
-use crate::{CommitEncode, CommitmentProtocol, VerifyEq};
+use crate::{CommitEncode, CommitmentProtocol, ErrorCode, ErrorCodes, VerifyEq};
 
 /// Error during commitment verification
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
@@ -42,6 +42,21 @@ pub enum ConvolveVerifyError {
     InvalidProof,
 }
 
+impl ErrorCode for ConvolveVerifyError {
+    fn code(&self) -> &'static str {
+        match self {
+            ConvolveVerifyError::CommitmentMismatch => "convolve.mismatch",
+            ConvolveVerifyError::ImpossibleMessage => "convolve.impossible-message",
+            ConvolveVerifyError::InvalidProof => "convolve.invalid-proof",
+        }
+    }
+}
+
+impl ErrorCodes for ConvolveVerifyError {
+    const CODES: &'static [&'static str] =
+        &["convolve.mismatch", "convolve.impossible-message", "convolve.invalid-proof"];
+}
+
 /// Proof type used by [`ConvolveCommit`] protocol.
 pub trait ConvolveCommitProof<Msg, Source, Protocol>
 where
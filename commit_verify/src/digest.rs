@@ -54,7 +54,10 @@ impl DigestExt for Sha256 {
 
     fn input_raw(&mut self, data: &[u8]) { self.update(data); }
 
-    fn finish(self) -> [u8; 32] { self.finalize().into() }
+    fn finish(self) -> [u8; 32] {
+        crate::meter::count_hash();
+        self.finalize().into()
+    }
 }
 
 impl DigestExt<20> for Ripemd160 {
@@ -71,5 +74,8 @@ impl DigestExt<20> for Ripemd160 {
 
     fn input_raw(&mut self, data: &[u8]) { self.update(data); }
 
-    fn finish(self) -> [u8; 20] { self.finalize().into() }
+    fn finish(self) -> [u8; 20] {
+        crate::meter::count_hash();
+        self.finalize().into()
+    }
 }
@@ -21,7 +21,7 @@
 
 //! Embedded commitments (commit-embed-verify scheme).
 
-use crate::{CommitEncode, CommitmentProtocol};
+use crate::{CommitEncode, CommitmentProtocol, ErrorCode, ErrorCodes};
 
 /// Error during commitment verification
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -45,6 +45,33 @@ pub enum EmbedVerifyError<E: std::error::Error> {
     ProofMismatch,
 }
 
+impl<E: ErrorCode> ErrorCode for EmbedVerifyError<E> {
+    fn code(&self) -> &'static str {
+        match self {
+            EmbedVerifyError::CommitmentMismatch => "embed.mismatch",
+            EmbedVerifyError::InvalidMessage(_) => "embed.invalid-message",
+            EmbedVerifyError::InvalidProof => "embed.invalid-proof",
+            EmbedVerifyError::ProofMismatch => "embed.proof-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            EmbedVerifyError::InvalidMessage(err) => {
+                let mut params = vec![("cause", err.code().to_owned())];
+                params.extend(err.params());
+                params
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl<E: ErrorCode> ErrorCodes for EmbedVerifyError<E> {
+    const CODES: &'static [&'static str] =
+        &["embed.mismatch", "embed.invalid-message", "embed.invalid-proof", "embed.proof-mismatch"];
+}
+
 /// Trait for equivalence verification. Implemented for all types implementing
 /// `Eq`. For non-`Eq` types this trait provides way to implement custom
 /// equivalence verification used during commitment verification procedure.
@@ -59,6 +59,9 @@
 //!   [`super::merkle::MerkleLeaves`]. It merkelizes data provided by this trait
 //!   and serializes merkle root into the hasher. [`CommitmentId`] should be
 //!   implemented for types which has external identifiers
+//! - [`strategies::Structured`] can apply to types implementing
+//!   [`crate::structured::StructuredCommit`]. It serializes merkle root of the
+//!   structure fields into the hasher.
 //!
 //! [LNPBP-9]: https://github.com/LNP-BP/LNPBPs/blob/master/lnpbp-0009.md
 
@@ -101,6 +104,7 @@ pub trait CommitStrategy {
     /// - [`strategies::ConcealStrict`]
     /// - [`strategies::Id`]
     /// - [`strategies::Merklize`]
+    /// - [`strategies::Structured`]
     type Strategy;
 }
 
@@ -116,6 +120,7 @@ pub mod strategies {
 
     use super::*;
     use crate::merkle::{MerkleLeaves, MerkleNode};
+    use crate::structured::StructuredCommit;
 
     /// Used only internally for blank implementation on reference types.
     #[doc(hidden)]
@@ -165,6 +170,16 @@ pub mod strategies {
     /// Can apply only to types implementing [`MerkleLeaves`] trait.
     pub enum Merklize<const MERKLE_ROOT_TAG: u128> {}
 
+    /// Commits to the value by serializing merkle root of its fields, as
+    /// provided by [`StructuredCommit::structured_root`], into the hasher.
+    ///
+    /// Can apply only to types implementing [`StructuredCommit`] trait.
+    ///
+    /// # Panics
+    ///
+    /// Commitment panics if the structure has more than `u16::MAX` fields.
+    pub enum Structured {}
+
     impl<'a, T> CommitEncode for Holder<&'a T, IntoU8>
     where T: Copy + Into<u8>
     {
@@ -227,6 +242,20 @@ pub mod strategies {
         }
     }
 
+    impl<'a, T> CommitEncode for Holder<&'a T, Structured>
+    where T: StructuredCommit
+    {
+        fn commit_encode(&self, e: &mut impl io::Write) {
+            self.as_type()
+                .structured_root()
+                .expect(
+                    "structures committed with the structured strategy must not exceed 65535 \
+                     fields",
+                )
+                .commit_encode(e);
+        }
+    }
+
     impl<T> CommitEncode for T
     where
         T: CommitStrategy,
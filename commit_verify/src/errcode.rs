@@ -0,0 +1,279 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stable error codes for localization of the error messages.
+//!
+//! `Display` implementations of the library errors provide English messages.
+//! Applications presenting errors to the users in other languages may use
+//! [`ErrorCode::code`] as a key into their message catalog, substituting
+//! [`ErrorCode::params`] into the translated message.
+//!
+//! Errors wrapping other errors of this library and displaying them as-is
+//! report the code and parameters of the wrapped error. Other error variants
+//! caused by a wrapped error (including errors of the resolvers provided by
+//! the users) report its code as the `cause` parameter, followed by the
+//! wrapped error parameters.
+//!
+//! Error codes are stable: once assigned to an error variant, a code is never
+//! changed or re-used for a different variant. Each error type lists all the
+//! codes it may report in [`ErrorCodes::CODES`]; the codes defined by this
+//! library are:
+//!
+//! | Code                                     | Error                                        |
+//! |------------------------------------------|----------------------------------------------|
+//! | `commit.mismatch`                        | [`crate::VerifyError`]                       |
+//! | `commit.invalid-message`                 | [`crate::VerifyError`]                       |
+//! | `convolve.mismatch`                      | [`crate::ConvolveVerifyError`]               |
+//! | `convolve.impossible-message`            | [`crate::ConvolveVerifyError`]               |
+//! | `convolve.invalid-proof`                 | [`crate::ConvolveVerifyError`]               |
+//! | `embed.mismatch`                         | [`crate::EmbedVerifyError`]                  |
+//! | `embed.invalid-message`                  | [`crate::EmbedVerifyError`]                  |
+//! | `embed.invalid-proof`                    | [`crate::EmbedVerifyError`]                  |
+//! | `embed.proof-mismatch`                   | [`crate::EmbedVerifyError`]                  |
+//! | `mpc.empty`                              | [`crate::mpc::Error`]                        |
+//! | `mpc.too-many-messages`                  | [`crate::mpc::Error`]                        |
+//! | `mpc.cant-fit`                           | [`crate::mpc::Error`]                        |
+//! | `mpc.leaf-not-known`                     | [`crate::mpc::LeafNotKnown`]                 |
+//! | `mpc.invalid-proof`                      | [`crate::mpc::InvalidProof`]                 |
+//! | `mpc.unrelated-blocks`                   | [`crate::mpc::MergeError`]                   |
+//! | `mpc.non-canonical.cofactor`             | [`crate::mpc::NonCanonical`]                 |
+//! | `mpc.non-canonical.concealed`            | [`crate::mpc::NonCanonical`]                 |
+//! | `mpc.tree.count-mismatch`                | [`crate::mpc::TreeValidationError`]          |
+//! | `mpc.tree.misplaced`                     | [`crate::mpc::TreeValidationError`]          |
+//! | `mpc.tree.message-mismatch`              | [`crate::mpc::TreeValidationError`]          |
+//! | `mpc.name.empty`                         | [`crate::mpc::ProtocolNameError`]            |
+//! | `mpc.name.too-long`                      | [`crate::mpc::ProtocolNameError`]            |
+//! | `mpc.name.invalid-char`                  | [`crate::mpc::ProtocolNameError`]            |
+//! | `mpc.precommitment.no-entropy`           | [`crate::mpc::PrecommitmentError`]           |
+//! | `mpc.precommitment.concealed`            | [`crate::mpc::PrecommitmentError`]           |
+//! | `mpc.precommitment.mismatch`             | [`crate::mpc::PrecommitmentError`]           |
+//! | `mpc.precommitment.misplaced`            | [`crate::mpc::PrecommitmentError`]           |
+//! | `mpc.precommitment.duplicate`            | [`crate::mpc::PrecommitmentError`]           |
+//! | `mpc.resumable.checkpoint-mismatch`      | [`crate::mpc::ResumableError`]               |
+//! | `mpc.resumable.invalid-cross-section`    | [`crate::mpc::ResumableError`]               |
+//! | `mpc.resumable.commitment-mismatch`      | [`crate::mpc::ResumableError`]               |
+//! | `mpc.history.invalid-proof`              | [`crate::mpc::HistoryError`]                 |
+//! | `mpc.history.commitment-mismatch`        | [`crate::mpc::HistoryError`]                 |
+//! | `mpc.history.length-mismatch`            | [`crate::mpc::HistoryError`]                 |
+//! | `mpc.history.too-large`                  | [`crate::mpc::HistoryAppendError`]           |
+//! | `mpc.registry.name-too-long`             | [`crate::mpc::MessageTypeError`]             |
+//! | `mpc.registry.unregistered`              | [`crate::mpc::MessageTypeError`]             |
+//! | `mpc.registry.mismatch`                  | [`crate::mpc::MessageTypeError`]             |
+//! | `mpc.registry.commitment-mismatch`       | [`crate::mpc::MessageTypeError`]             |
+//! | `mpc.registry.type-mismatch`             | [`crate::mpc::MessageTypeError`]             |
+//! | `mpc.aggregate.empty`                    | [`crate::mpc::AggregationError`]             |
+//! | `mpc.aggregate.duplicate-producer`       | [`crate::mpc::AggregationError`]             |
+//! | `mpc.reference.mismatch`                 | [`crate::mpc::DifferentialMismatch`]         |
+//! | `mpc.fixture.header`                     | [`crate::mpc::FixtureError`]                 |
+//! | `mpc.fixture.line`                       | [`crate::mpc::FixtureError`]                 |
+//! | `mpc.fixture.missing`                    | [`crate::mpc::FixtureError`]                 |
+//! | `mpc.fixture.too-many-messages`          | [`crate::mpc::FixtureError`]                 |
+//! | `mpc.fixture.invalid`                    | [`crate::mpc::FixtureError`]                 |
+//! | `mpc.delta.not-present`                  | [`crate::mpc::DeltaError`]                   |
+//! | `mpc.delta.already-present`              | [`crate::mpc::DeltaError`]                   |
+//! | `mpc.checkpoint.empty`                   | [`crate::mpc::CheckpointError`]              |
+//! | `mpc.checkpoint.no-genesis`              | [`crate::mpc::CheckpointError`]              |
+//! | `mpc.checkpoint.broken-link`             | [`crate::mpc::CheckpointError`]              |
+//! | `mpc.checkpoint.invalid-sequence`        | [`crate::mpc::CheckpointError`]              |
+//! | `mpc.checkpoint.anchor-mismatch`         | [`crate::mpc::CheckpointError`]              |
+//! | `mpc.blinded.duplicate`                  | [`crate::mpc::BlindedError`]                 |
+//! | `mpc.blinded.leaf-not-found`             | [`crate::mpc::BlindedError`]                 |
+//! | `mpc.blinded.leaf-mismatch`              | [`crate::mpc::BlindedError`]                 |
+//! | `mpc.policy.too-many-messages`           | [`crate::mpc::PolicyError`]                  |
+//! | `mpc.policy.forbidden`                   | [`crate::mpc::PolicyError`]                  |
+//! | `mpc.policy.missing`                     | [`crate::mpc::PolicyError`]                  |
+//! | `mpc.path.invalid-hex`                   | [`crate::mpc::MerklePathError`]              |
+//! | `mpc.path.invalid-length`                | [`crate::mpc::MerklePathError`]              |
+//! | `mpc.path.too-long`                      | [`crate::mpc::MerklePathError`]              |
+//! | `mpc.path.depth-mismatch`                | [`crate::mpc::MerklePathError`]              |
+//! | `mpc.tombstone.reason-too-long`          | [`crate::mpc::TombstoneError`]               |
+//! | `mpc.tombstone.too-many`                 | [`crate::mpc::TombstoneError`]               |
+//! | `mpc.seals.absent`                       | [`crate::mpc::ClosedSealsError`]             |
+//! | `mpc.seals.mismatch`                     | [`crate::mpc::ClosedSealsError`]             |
+//! | `mpc.seals.commitment-mismatch`          | [`crate::mpc::ClosedSealsError`]             |
+//! | `mpc.seals.omitted`                      | [`crate::mpc::ClosedSealsError`]             |
+//! | `blob.empty-range`                       | [`crate::blob::BlobProofError`]              |
+//! | `blob.out-of-bounds`                     | [`crate::blob::BlobProofError`]              |
+//! | `blob.too-large`                         | [`crate::blob::BlobProofError`]              |
+//! | `blob.data-mismatch`                     | [`crate::blob::BlobProofError`]              |
+//! | `blob.root-mismatch`                     | [`crate::blob::BlobProofError`]              |
+//! | `blob.zero-chunk-size`                   | [`crate::blob::BlobProofError`]              |
+//! | `structured.unknown-field`               | [`crate::structured::FieldProofError`]       |
+//! | `structured.too-many-fields`             | [`crate::structured::FieldProofError`]       |
+//! | `anchor.invalid-transition`              | [`crate::anchor::InvalidTransition`]         |
+//! | `anchor.resolver`                        | [`crate::anchor::AnchorUpdateError`]         |
+//! | `capabilities.no-common-scheme`          | [`crate::capabilities::NegotiationError`]    |
+//! | `capabilities.no-common-encoding`        | [`crate::capabilities::NegotiationError`]    |
+//! | `store.io`                               | `store::FileStoreError`                      |
+//! | `store.serialize`                        | `store::FileStoreError`                      |
+//! | `store.deserialize`                      | `store::FileStoreError`                      |
+//! | `compress.io`                            | `compress::CompressionError`                 |
+//! | `compress.serialize`                     | `compress::CompressionError`                 |
+//! | `compress.deserialize`                   | `compress::CompressionError`                 |
+//! | `compress.truncated`                     | `compress::CompressionError`                 |
+//! | `compress.unknown-format`                | `compress::CompressionError`                 |
+//! | `compress.too-large`                     | `compress::CompressionError`                 |
+//! | `compress.size-mismatch`                 | `compress::CompressionError`                 |
+//! | `attestation.invalid`                    | `attestation::InvalidAttestation`            |
+//! | `escrow.invalid-adaptor`                 | `escrow::EscrowError`                        |
+//! | `escrow.secret-mismatch`                 | `escrow::EscrowError`                        |
+//! | `escrow.signature-mismatch`              | `escrow::EscrowError`                        |
+
+/// Error with a stable code and programmatically accessible parameters.
+pub trait ErrorCode: std::error::Error {
+    /// Stable code of the error variant.
+    fn code(&self) -> &'static str;
+
+    /// Named parameters of the error, in the order of their appearance in the
+    /// English message.
+    fn params(&self) -> Vec<(&'static str, String)> { vec![] }
+}
+
+/// Error type with a fixed set of stable codes.
+///
+/// Lists the codes the error may report, such that the catalog of the error
+/// codes is built from the error types themselves.
+pub trait ErrorCodes: ErrorCode {
+    /// All codes which may be returned by [`ErrorCode::code`], including the
+    /// codes of the wrapped errors reported as-is.
+    const CODES: &'static [&'static str];
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::anchor::{AnchorUpdateError, InvalidTransition};
+    #[cfg(feature = "secp256k1")]
+    use crate::attestation::InvalidAttestation;
+    use crate::blob::BlobProofError;
+    use crate::capabilities::NegotiationError;
+    #[cfg(feature = "zstd")]
+    use crate::compress::CompressionError;
+    #[cfg(feature = "secp256k1")]
+    use crate::escrow::EscrowError;
+    use crate::mpc::{
+        self, AggregationError, BlindedError, CheckpointError, ClosedSealsError, DeltaError,
+        DifferentialMismatch, FixtureError, HistoryAppendError, HistoryError, InvalidProof,
+        LeafNotKnown, MergeError, MerklePathError, MessageTypeError, NonCanonical, PolicyError,
+        PrecommitmentError, ProtocolNameError, ResumableError, TombstoneError, TreeValidationError,
+    };
+    #[cfg(feature = "fs")]
+    use crate::store::FileStoreError;
+    use crate::structured::FieldProofError;
+    use crate::{ConvolveVerifyError, EmbedVerifyError, VerifyError};
+
+    type Codes = &'static [&'static str];
+
+    /// Lists error types with the codes of the errors they wrap and report
+    /// as-is.
+    macro_rules! catalog {
+        ($($(#[$attr:meta])* $ty:ty $(=> [$($wrapped:ty),+])?),+ $(,)?) => {
+            vec![$($(#[$attr])* (
+                stringify!($ty),
+                <$ty as ErrorCodes>::CODES,
+                vec![$($(<$wrapped as ErrorCodes>::CODES),+)?],
+            )),+]
+        };
+    }
+
+    fn catalog() -> Vec<(&'static str, Codes, Vec<Codes>)> {
+        catalog![
+            VerifyError,
+            ConvolveVerifyError,
+            EmbedVerifyError<VerifyError>,
+            mpc::Error,
+            LeafNotKnown,
+            InvalidProof,
+            MergeError => [InvalidProof],
+            NonCanonical,
+            TreeValidationError,
+            ProtocolNameError,
+            PrecommitmentError,
+            ResumableError,
+            HistoryError,
+            HistoryAppendError => [LeafNotKnown],
+            MessageTypeError => [LeafNotKnown, InvalidProof],
+            AggregationError => [mpc::Error],
+            DifferentialMismatch,
+            FixtureError,
+            DeltaError => [mpc::Error],
+            CheckpointError => [InvalidProof],
+            BlindedError => [mpc::Error],
+            PolicyError => [mpc::Error],
+            MerklePathError,
+            TombstoneError => [LeafNotKnown],
+            ClosedSealsError => [InvalidProof],
+            BlobProofError,
+            FieldProofError,
+            InvalidTransition,
+            AnchorUpdateError<VerifyError> => [InvalidTransition],
+            NegotiationError,
+            #[cfg(feature = "fs")]
+            FileStoreError => [MergeError],
+            #[cfg(feature = "zstd")]
+            CompressionError,
+            #[cfg(feature = "secp256k1")]
+            InvalidAttestation,
+            #[cfg(feature = "secp256k1")]
+            EscrowError,
+        ]
+    }
+
+    #[test]
+    fn wrapped_codes() {
+        for (name, codes, wrapped) in catalog() {
+            for code in wrapped.concat() {
+                assert!(
+                    codes.contains(&code),
+                    "{name} doesn't list code {code} of a wrapped error"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unique_codes() {
+        let mut own = BTreeSet::new();
+        for (name, codes, wrapped) in catalog() {
+            let wrapped = wrapped.concat();
+            let unique = codes.iter().collect::<BTreeSet<_>>();
+            assert_eq!(unique.len(), codes.len(), "{name} lists some code twice");
+            for code in codes.iter().filter(|code| !wrapped.contains(*code)) {
+                assert!(own.insert(*code), "code {code} of {name} is used by another error");
+            }
+        }
+    }
+
+    #[test]
+    fn params() {
+        let err = TreeValidationError::CountMismatch(2, 1);
+        assert_eq!(err.code(), "mpc.tree.count-mismatch");
+        assert!(TreeValidationError::CODES.contains(&err.code()));
+        assert_eq!(err.params(), vec![("messages", 2.to_string()), ("placed", 1.to_string())]);
+        assert!(VerifyError::InvalidCommitment.params().is_empty());
+
+        let err = AnchorUpdateError::<VerifyError>::Resolver(VerifyError::InvalidCommitment);
+        assert_eq!(err.code(), "anchor.resolver");
+        assert_eq!(err.params(), vec![("cause", "commit.mismatch".to_owned())]);
+    }
+}
@@ -0,0 +1,254 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Commitment escrow with BIP-340 adaptor signatures over commitment ids.
+//!
+//! Adaptor signature is created against an adaptor point, which discrete
+//! logarithm is a secret held by a counterparty. Completing the adaptor
+//! signature into a valid [`Attestation`] requires the secret, and publishing
+//! the completed signature (for instance, as a part of a seal closing) reveals
+//! the secret to anybody holding the adaptor signature. This is the pattern
+//! used by client-side-validated atomic swaps.
+
+use secp256k1::{
+    schnorr, Keypair, Message, Parity, PublicKey, Scalar, Secp256k1, SecretKey, XOnlyPublicKey,
+};
+use sha2::Sha256;
+
+use crate::attestation::{bip340_challenge, Attestation};
+use crate::digest::DigestExt;
+use crate::{CommitmentId, ErrorCode, ErrorCodes};
+
+/// Errors processing [`AdaptorSignature`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum EscrowError {
+    /// the adaptor signature is not valid for the provided data.
+    InvalidAdaptor,
+
+    /// the secret doesn't match the adaptor point.
+    SecretMismatch,
+
+    /// the signature is not a completion of the adaptor signature.
+    SignatureMismatch,
+}
+
+impl ErrorCode for EscrowError {
+    fn code(&self) -> &'static str {
+        match self {
+            EscrowError::InvalidAdaptor => "escrow.invalid-adaptor",
+            EscrowError::SecretMismatch => "escrow.secret-mismatch",
+            EscrowError::SignatureMismatch => "escrow.signature-mismatch",
+        }
+    }
+}
+
+impl ErrorCodes for EscrowError {
+    const CODES: &'static [&'static str] =
+        &["escrow.invalid-adaptor", "escrow.secret-mismatch", "escrow.signature-mismatch"];
+}
+
+/// BIP-340 adaptor signature (pre-signature) over a commitment id of some data,
+/// which can be completed into an [`Attestation`] only with the knowledge of
+/// the discrete logarithm of the adaptor point.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct AdaptorSignature {
+    /// Public key of the signer.
+    pub signer: XOnlyPublicKey,
+    /// Adaptor point, which discrete logarithm is the escrowed secret.
+    pub adaptor: PublicKey,
+    /// Public nonce, not including the adaptor point.
+    pub nonce: PublicKey,
+    /// Pre-signature scalar.
+    pub presig: SecretKey,
+}
+
+impl AdaptorSignature {
+    /// Tag of the hash producing the signing nonce.
+    pub const NONCE_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0009:adaptr:v01#A";
+
+    /// Creates adaptor signature over the commitment id of the data, which
+    /// will be completable with the discrete logarithm of the `adaptor`
+    /// point.
+    pub fn sign<T: CommitmentId>(secret_key: &SecretKey, adaptor: PublicKey, data: &T) -> Self {
+        Self::sign_message(secret_key, adaptor, &Attestation::message(data))
+    }
+
+    fn sign_message(secret_key: &SecretKey, adaptor: PublicKey, msg: &Message) -> Self {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        let (signer, parity) = keypair.x_only_public_key();
+        let secret_key = match parity {
+            Parity::Even => *secret_key,
+            Parity::Odd => secret_key.negate(),
+        };
+
+        let mut engine = Sha256::from_tag(Self::NONCE_TAG);
+        engine.input_raw(&secret_key.secret_bytes());
+        engine.input_raw(&adaptor.serialize());
+        engine.input_raw(msg.as_ref());
+        let k = SecretKey::from_slice(&engine.finish()).expect("negligible probability");
+        let nonce = PublicKey::from_secret_key(&secp, &k);
+
+        let (r, parity) = final_nonce(nonce, adaptor).expect("negligible probability");
+        let k = match parity {
+            Parity::Even => k,
+            Parity::Odd => k.negate(),
+        };
+        let e = bip340_challenge(&r, &signer, msg);
+        let presig = secret_key
+            .mul_tweak(&e)
+            .and_then(|ex| ex.add_tweak(&Scalar::from(k)))
+            .expect("negligible probability");
+
+        AdaptorSignature {
+            signer,
+            adaptor,
+            nonce,
+            presig,
+        }
+    }
+
+    /// Verifies that the adaptor signature is made over the commitment id of
+    /// the data and, being completed, will produce a valid [`Attestation`].
+    pub fn verify<T: CommitmentId>(&self, data: &T) -> Result<(), EscrowError> {
+        self.verify_message(&Attestation::message(data))
+    }
+
+    fn verify_message(&self, msg: &Message) -> Result<(), EscrowError> {
+        let secp = Secp256k1::new();
+        let (r, parity) =
+            final_nonce(self.nonce, self.adaptor).map_err(|_| EscrowError::InvalidAdaptor)?;
+        let nonce = match parity {
+            Parity::Even => self.nonce,
+            Parity::Odd => self.nonce.negate(&secp),
+        };
+        let e = bip340_challenge(&r, &self.signer, msg);
+        let expected = PublicKey::from_x_only_public_key(self.signer, Parity::Even)
+            .mul_tweak(&secp, &e)
+            .and_then(|ep| ep.combine(&nonce))
+            .map_err(|_| EscrowError::InvalidAdaptor)?;
+        if PublicKey::from_secret_key(&secp, &self.presig) != expected {
+            return Err(EscrowError::InvalidAdaptor);
+        }
+        Ok(())
+    }
+
+    /// Completes the adaptor signature with the escrowed `secret` into a
+    /// valid [`Attestation`].
+    pub fn complete(&self, secret: &SecretKey) -> Result<Attestation, EscrowError> {
+        let secp = Secp256k1::signing_only();
+        if PublicKey::from_secret_key(&secp, secret) != self.adaptor {
+            return Err(EscrowError::SecretMismatch);
+        }
+        let (r, parity) =
+            final_nonce(self.nonce, self.adaptor).map_err(|_| EscrowError::InvalidAdaptor)?;
+        let secret = match parity {
+            Parity::Even => *secret,
+            Parity::Odd => secret.negate(),
+        };
+        let s = self
+            .presig
+            .add_tweak(&Scalar::from(secret))
+            .map_err(|_| EscrowError::InvalidAdaptor)?;
+
+        let mut sig = [0u8; 64];
+        sig[..32].copy_from_slice(&r.serialize());
+        sig[32..].copy_from_slice(&s.secret_bytes());
+        Ok(Attestation {
+            signer: self.signer,
+            sig: schnorr::Signature::from_slice(&sig).expect("64-byte signature"),
+        })
+    }
+
+    /// Extracts the escrowed secret from the completed signature.
+    pub fn extract(&self, sig: &schnorr::Signature) -> Result<SecretKey, EscrowError> {
+        let secp = Secp256k1::signing_only();
+        let (r, parity) =
+            final_nonce(self.nonce, self.adaptor).map_err(|_| EscrowError::InvalidAdaptor)?;
+        let sig = sig.serialize();
+        if sig[..32] != r.serialize() {
+            return Err(EscrowError::SignatureMismatch);
+        }
+        let s = SecretKey::from_slice(&sig[32..]).map_err(|_| EscrowError::SignatureMismatch)?;
+        let secret = match parity {
+            Parity::Even => s.add_tweak(&Scalar::from(self.presig.negate())),
+            Parity::Odd => self.presig.add_tweak(&Scalar::from(s.negate())),
+        }
+        .map_err(|_| EscrowError::SignatureMismatch)?;
+        if PublicKey::from_secret_key(&secp, &secret) != self.adaptor {
+            return Err(EscrowError::SignatureMismatch);
+        }
+        Ok(secret)
+    }
+}
+
+/// Computes x-only final nonce `R = R' + T` and the parity of its y
+/// coordinate.
+fn final_nonce(
+    nonce: PublicKey,
+    adaptor: PublicKey,
+) -> Result<(XOnlyPublicKey, Parity), secp256k1::Error> {
+    nonce.combine(&adaptor).map(|r| r.x_only_public_key())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkle::MerkleNode;
+    use crate::CommitEncode;
+
+    #[derive(Clone, Debug)]
+    struct Swap(u64);
+
+    impl CommitEncode for Swap {
+        fn commit_encode(&self, e: &mut impl std::io::Write) { self.0.commit_encode(e) }
+    }
+
+    impl CommitmentId for Swap {
+        const TAG: [u8; 32] = *b"urn:lnpbp:test:escrow:swap#2023A";
+        type Id = MerkleNode;
+    }
+
+    #[test]
+    fn escrow() {
+        let secp = Secp256k1::signing_only();
+        for no in 1u8..=16 {
+            let signer_key = SecretKey::from_slice(&[no; 32]).unwrap();
+            let secret = SecretKey::from_slice(&[0x80 | no; 32]).unwrap();
+            let adaptor = PublicKey::from_secret_key(&secp, &secret);
+
+            let presig = AdaptorSignature::sign(&signer_key, adaptor, &Swap(no as u64));
+            assert_eq!(presig.verify(&Swap(no as u64)), Ok(()));
+            assert_eq!(presig.verify(&Swap(0)), Err(EscrowError::InvalidAdaptor));
+
+            let other = SecretKey::from_slice(&[0x42; 32]).unwrap();
+            assert_eq!(presig.complete(&other), Err(EscrowError::SecretMismatch));
+
+            let attestation = presig.complete(&secret).unwrap();
+            assert_eq!(attestation.verify(&Swap(no as u64)), Ok(()));
+            assert_eq!(presig.extract(&attestation.sig), Ok(secret));
+
+            let unrelated = Attestation::sign(&signer_key, &Swap(no as u64));
+            assert_eq!(presig.extract(&unrelated.sig), Err(EscrowError::SignatureMismatch));
+        }
+    }
+}
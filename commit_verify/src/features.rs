@@ -0,0 +1,80 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime introspection of the compile-time features of the library.
+
+/// Compile-time features the library was built with.
+///
+/// New fields are added as the library gains new features, so the structure
+/// can't be constructed outside of the library.
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Features {
+    /// Random entropy generation for LNPBP-4 trees (`rand` feature).
+    pub rand: bool,
+    /// Serde serialization of the library types (`serde` feature).
+    pub serde: bool,
+    /// Strict type library of the commitment types (`stl` feature).
+    pub stl: bool,
+    /// Derive macros for commit encoding (`derive` feature).
+    pub derive: bool,
+    /// File system support (`fs` feature).
+    pub fs: bool,
+    /// BIP-340 attestations and adaptor signatures (`secp256k1` feature).
+    pub secp256k1: bool,
+    /// Pedersen commitments over ristretto255 (`ristretto` feature).
+    pub ristretto: bool,
+    /// Commitments to async streams (`async` feature).
+    pub asynchronous: bool,
+    /// Compression of serialized proofs (`zstd` feature).
+    pub zstd: bool,
+    /// Mnemonic checksums of commitment ids (`mnemonic` feature).
+    pub mnemonic: bool,
+    /// Property-based test utilities for downstream crates (`test-utils`
+    /// feature).
+    pub test_utils: bool,
+    /// Generator of the commitment test vectors (`vector-gen` feature).
+    pub vector_gen: bool,
+}
+
+/// Returns compile-time features the library was built with, allowing plugins
+/// and FFI consumers to adapt their behavior to the available functionality.
+pub const fn features() -> Features {
+    Features {
+        rand: cfg!(feature = "rand"),
+        serde: cfg!(feature = "serde"),
+        stl: cfg!(feature = "stl"),
+        derive: cfg!(feature = "derive"),
+        fs: cfg!(feature = "fs"),
+        secp256k1: cfg!(feature = "secp256k1"),
+        ristretto: cfg!(feature = "ristretto"),
+        asynchronous: cfg!(feature = "async"),
+        zstd: cfg!(feature = "zstd"),
+        mnemonic: cfg!(feature = "mnemonic"),
+        test_utils: cfg!(feature = "test-utils"),
+        vector_gen: cfg!(feature = "vector-gen"),
+    }
+}
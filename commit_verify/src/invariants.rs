@@ -0,0 +1,213 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Property-based tests for the core invariants of LNPBP-4 commitments.
+//!
+//! The module provides [`proptest`] strategies generating arbitrary message
+//! sets, and checks of the invariants which must hold for any of them:
+//! - each message is placed into its own tree leaf, at the position defined by
+//!   its protocol id ([`check_placement`]);
+//! - commitment doesn't depend on the order in which the messages are provided
+//!   ([`check_root_stability`]);
+//! - concealing any part of the tree keeps its commitment id
+//!   ([`check_conceal`]).
+//!
+//! Forks and alternative implementations can import the whole suite into
+//! their tests with [`mpc_invariant_tests!`] macro:
+//!
+//! ```ignore
+//! commit_verify::mpc_invariant_tests!();
+//! ```
+
+use std::collections::BTreeSet;
+
+use amplify::confinement::Confined;
+use amplify::num::u5;
+pub use proptest;
+use proptest::collection::btree_map;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use crate::mpc::{Leaf, MerkleBlock, MerkleTree, Message, MultiSource, ProtocolId};
+use crate::{CommitmentId, Conceal, TryCommitVerify};
+
+/// Maximal number of messages generated by [`arb_source`].
+pub const MAX_ARB_MESSAGES: usize = 32;
+
+/// Maximal minimal depth of the trees generated by [`arb_source`].
+pub const MAX_ARB_MIN_DEPTH: u8 = 8;
+
+/// Strategy generating arbitrary protocol ids.
+pub fn arb_protocol_id() -> impl Strategy<Value = ProtocolId> {
+    any::<[u8; 32]>().prop_map(ProtocolId::from)
+}
+
+/// Strategy generating arbitrary messages.
+pub fn arb_message() -> impl Strategy<Value = Message> { any::<[u8; 32]>().prop_map(Message::from) }
+
+/// Strategy generating arbitrary sources of LNPBP-4 commitments, with up to
+/// [`MAX_ARB_MESSAGES`] messages, minimal depth from 1 up to
+/// [`MAX_ARB_MIN_DEPTH`] and a static entropy.
+///
+/// Minimal depth is never zero, since a tree of zero depth without messages
+/// can't be constructed (see [`crate::mpc::Error::Empty`]).
+pub fn arb_source() -> impl Strategy<Value = MultiSource> {
+    (
+        btree_map(arb_protocol_id(), arb_message(), 0..=MAX_ARB_MESSAGES),
+        1..=MAX_ARB_MIN_DEPTH,
+        any::<u64>(),
+    )
+        .prop_map(|(messages, min_depth, entropy)| MultiSource {
+            min_depth: u5::with(min_depth),
+            messages: Confined::try_from(messages).expect("message number is below the limit"),
+            static_entropy: Some(entropy),
+        })
+}
+
+/// Strategy generating arbitrary sources like [`arb_source`], together with
+/// their messages in a random order.
+pub fn arb_shuffled_source() -> impl Strategy<Value = (MultiSource, Vec<(ProtocolId, Message)>)> {
+    arb_source().prop_flat_map(|source| {
+        let messages = source
+            .messages
+            .iter()
+            .map(|(protocol_id, message)| (*protocol_id, *message))
+            .collect::<Vec<_>>();
+        (Just(source), Just(messages).prop_shuffle())
+    })
+}
+
+/// Checks that each message of the `source` is placed into its own leaf of the
+/// tree, at the position defined by its protocol id.
+pub fn check_placement(source: &MultiSource) -> Result<(), TestCaseError> {
+    let tree =
+        MerkleTree::try_commit(source).map_err(|err| TestCaseError::fail(err.to_string()))?;
+    prop_assert!(tree.depth() >= source.min_depth);
+    prop_assert_eq!(tree.validate(), Ok(()));
+
+    let mut placed = BTreeSet::new();
+    for pos in 0..tree.width() {
+        if let Some(Leaf::Inhabited { protocol, message }) = tree.leaf(pos) {
+            prop_assert!(placed.insert(protocol), "protocol {} is placed twice", protocol);
+            prop_assert_eq!(source.messages.get(&protocol), Some(&message));
+        }
+    }
+    prop_assert_eq!(placed.len(), source.messages.len());
+    Ok(())
+}
+
+/// Checks that the commitment to the `source` messages doesn't depend on the
+/// order in which they are provided, by constructing trees from the
+/// `shuffled` messages, inserted one by one and collected at once, as well as
+/// in the reversed order.
+pub fn check_root_stability(
+    source: &MultiSource,
+    shuffled: &[(ProtocolId, Message)],
+) -> Result<(), TestCaseError> {
+    let source_with = |messages: &mut dyn Iterator<Item = (ProtocolId, Message)>| {
+        let mut permuted = MultiSource {
+            min_depth: source.min_depth,
+            messages: none!(),
+            static_entropy: source.static_entropy,
+        };
+        for (protocol_id, message) in messages {
+            permuted
+                .messages
+                .insert(protocol_id, message)
+                .map_err(|err| TestCaseError::fail(err.to_string()))?;
+        }
+        Ok::<_, TestCaseError>(permuted)
+    };
+    let inserted = source_with(&mut shuffled.iter().copied())?;
+    let reversed = source_with(&mut shuffled.iter().rev().copied())?;
+    let collected = MultiSource {
+        min_depth: source.min_depth,
+        messages: Confined::try_from_iter(shuffled.iter().copied())
+            .map_err(|err| TestCaseError::fail(err.to_string()))?,
+        static_entropy: source.static_entropy,
+    };
+
+    let tree =
+        MerkleTree::try_commit(source).map_err(|err| TestCaseError::fail(err.to_string()))?;
+    for permuted in [inserted, reversed, collected] {
+        let other = MerkleTree::try_commit(&permuted)
+            .map_err(|err| TestCaseError::fail(err.to_string()))?;
+        prop_assert_eq!(tree.root(), other.root());
+        prop_assert_eq!(tree.commitment_id(), other.commitment_id());
+    }
+    Ok(())
+}
+
+/// Checks that the tree, the block constructed from it and the blocks
+/// revealing a single message all have the same commitment id, and that the
+/// proofs extracted from them convolve into it.
+pub fn check_conceal(source: &MultiSource) -> Result<(), TestCaseError> {
+    let tree =
+        MerkleTree::try_commit(source).map_err(|err| TestCaseError::fail(err.to_string()))?;
+    let commitment = tree.commitment_id();
+    let block = MerkleBlock::from(&tree);
+    prop_assert_eq!(block.commitment_id(), commitment);
+    prop_assert_eq!(block.conceal(), tree.conceal());
+
+    for (protocol_id, message) in &source.messages {
+        let mut partial = block.clone();
+        partial
+            .conceal_except([*protocol_id])
+            .map_err(|err| TestCaseError::fail(err.to_string()))?;
+        prop_assert_eq!(partial.commitment_id(), commitment);
+
+        let proof = partial
+            .to_merkle_proof(*protocol_id)
+            .map_err(|err| TestCaseError::fail(err.to_string()))?;
+        prop_assert_eq!(proof.convolve(*protocol_id, *message), Ok(commitment));
+    }
+    Ok(())
+}
+
+/// Generates property tests for all LNPBP-4 invariants.
+#[macro_export]
+macro_rules! mpc_invariant_tests {
+    () => {
+        $crate::invariants::proptest::proptest! {
+            #[test]
+            fn mpc_placement(source in $crate::invariants::arb_source()) {
+                $crate::invariants::check_placement(&source)?;
+            }
+
+            #[test]
+            fn mpc_root_stability(
+                (source, shuffled) in $crate::invariants::arb_shuffled_source()
+            ) {
+                $crate::invariants::check_root_stability(&source, &shuffled)?;
+            }
+
+            #[test]
+            fn mpc_conceal(source in $crate::invariants::arb_source()) {
+                $crate::invariants::check_conceal(&source)?;
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    crate::mpc_invariant_tests!();
+}
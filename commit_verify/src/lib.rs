@@ -29,6 +29,7 @@
     dead_code,
     // TODO: uncomment missing_docs
 )]
+#![forbid(unsafe_code)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
 #[macro_use]
@@ -43,27 +44,55 @@ extern crate serde_crate as serde;
 extern crate core;
 
 #[cfg(feature = "derive")]
-pub use commit_encoding_derive::CommitEncode;
+pub use commit_encoding_derive::{CommitEncode, CommitmentId};
 
 mod commit;
 mod conceal;
 mod convolve;
 mod embed;
 mod encode;
+mod errcode;
+mod features;
 mod id;
+mod rfc6962;
 #[cfg(feature = "stl")]
 pub mod stl;
 
+pub mod anchor;
+#[cfg(feature = "secp256k1")]
+pub mod attestation;
+pub mod blob;
+pub mod capabilities;
+pub mod chain;
+#[cfg(feature = "zstd")]
+pub mod compress;
+#[cfg(feature = "secp256k1")]
+pub mod escrow;
+#[cfg(feature = "test-utils")]
+pub mod invariants;
 pub mod merkle;
+pub mod meter;
+#[cfg(feature = "mnemonic")]
+pub mod mnemonic;
 pub mod mpc;
+#[cfg(feature = "ristretto")]
+pub mod pedersen;
+pub mod publication;
+pub mod stability;
+pub mod store;
+pub mod structured;
 mod digest;
 
-pub use commit::{CommitVerify, StrictEncodedProtocol, TryCommitVerify, VerifyError};
+pub use commit::{
+    CommitVerify, StrictEncodedProtocol, TryCommitVerify, VerifiableProof, VerifyError,
+};
 pub use conceal::Conceal;
 pub use convolve::{ConvolveCommit, ConvolveCommitProof, ConvolveVerifyError};
 pub use digest::{Digest, DigestExt, Ripemd160, Sha256};
 pub use embed::{EmbedCommitProof, EmbedCommitVerify, EmbedVerifyError, VerifyEq};
 pub use encode::{strategies, CommitEncode, CommitStrategy};
+pub use errcode::{ErrorCode, ErrorCodes};
+pub use features::{features, Features};
 pub use id::CommitmentId;
 
 pub const LIB_NAME_COMMIT_VERIFY: &str = "CommitVerify";
@@ -0,0 +1,106 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instrumentation counting hash invocations.
+//!
+//! In debug builds each hash computed by the library through
+//! [`crate::DigestExt::finish`] increments a thread-local counter, allowing
+//! performance work and constant-work analysis to measure how much hashing a
+//! given operation performs. In release builds the counting is compiled out
+//! and [`HashMeter`] always reports zero.
+//!
+//! ```ignore
+//! let (commitment, hashes) = HashMeter::measure(|| proof.convolve(protocol_id, message));
+//! ```
+
+use std::cell::Cell;
+
+thread_local! {
+    static HASH_COUNT: Cell<u64> = Cell::new(0);
+}
+
+/// Registers single hash invocation.
+#[inline]
+pub(crate) fn count_hash() {
+    #[cfg(debug_assertions)]
+    HASH_COUNT.with(|count| count.set(count.get() + 1));
+}
+
+fn hash_count() -> u64 { HASH_COUNT.with(Cell::get) }
+
+/// Meter of hash invocations made by the current thread since the meter
+/// creation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct HashMeter {
+    start: u64,
+}
+
+impl HashMeter {
+    /// Detects whether the hash invocations are counted in the current build.
+    pub const ENABLED: bool = cfg!(debug_assertions);
+
+    /// Starts measuring hash invocations.
+    pub fn start() -> Self {
+        HashMeter {
+            start: hash_count(),
+        }
+    }
+
+    /// Returns number of hash invocations made by the current thread since
+    /// the meter start.
+    pub fn count(&self) -> u64 { hash_count() - self.start }
+
+    /// Runs the operation `f`, returning its result and the number of hash
+    /// invocations it has made.
+    pub fn measure<R>(f: impl FnOnce() -> R) -> (R, u64) {
+        let meter = HashMeter::start();
+        let res = f();
+        (res, meter.count())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::MerkleBlock;
+    use crate::CommitmentId;
+
+    #[test]
+    fn hash_meter() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let (pid, msg) = msgs.into_iter().next().unwrap();
+        let proof = MerkleBlock::from(&tree).to_merkle_proof(pid).unwrap();
+
+        let (commitment, hashes) = HashMeter::measure(|| proof.convolve_in_place(pid, msg));
+        assert_eq!(commitment, Ok(tree.commitment_id()));
+        let (_, root_hashes) = HashMeter::measure(|| tree.root());
+        if HashMeter::ENABLED {
+            // Leaf, path nodes and the final commitment
+            assert_eq!(hashes, proof.depth() as u64 + 2);
+            assert!(root_hashes >= tree.width() as u64);
+        } else {
+            assert_eq!(hashes, 0);
+            assert_eq!(root_hashes, 0);
+        }
+    }
+}
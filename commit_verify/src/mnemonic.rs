@@ -0,0 +1,113 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mnemonic checksums of commitment ids.
+//!
+//! Comparing 64 hex digits over the phone or in a screenshot is error-prone.
+//! Each 32-byte id may be accompanied by three words derived from its
+//! checksum, which is enough to detect accidental mistakes, but not to
+//! protect against a deliberate attack.
+//!
+//! ```ignore
+//! use commit_verify::mnemonic::IdMnemonic;
+//!
+//! // Plain id
+//! println!("{}", commitment.display_mnemonic());
+//! // Id with the mnemonic suffix, like `...a1f3#pizza-arena-nikita`
+//! println!("{:#}", commitment.display_mnemonic());
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use amplify::{Bytes32, Wrapper};
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+
+pub const MNEMONIC_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0009:mnemonic#23A";
+
+/// Computes three mnemonic words for the `id` checksum, separated by dashes.
+pub fn mnemonic(id: &Bytes32) -> String {
+    let mut engine = Sha256::from_tag(MNEMONIC_TAG);
+    engine.input_raw(id.as_slice());
+    let checksum = engine.finish();
+    ::mnemonic::to_string(&checksum[..4])
+}
+
+/// Mnemonic checksums for the types wrapping 32-byte ids.
+pub trait IdMnemonic: Wrapper<Inner = Bytes32> + Display {
+    /// Computes three mnemonic words for the id checksum, separated by dashes.
+    fn mnemonic(&self) -> String { mnemonic(self.as_inner()) }
+
+    /// Returns displayable wrapper, which adds the mnemonic suffix to the id
+    /// when the alternate formatter flag (`{:#}`) is used.
+    fn display_mnemonic(&self) -> DisplayMnemonic<'_, Self>
+    where Self: Sized {
+        DisplayMnemonic(self)
+    }
+}
+
+impl<T> IdMnemonic for T where T: Wrapper<Inner = Bytes32> + Display {}
+
+/// Displays id, adding mnemonic suffix with the alternate formatter flag
+/// (`{:#}`).
+#[derive(Copy, Clone, Debug)]
+pub struct DisplayMnemonic<'id, T: IdMnemonic>(&'id T);
+
+impl<'id, T: IdMnemonic> Display for DisplayMnemonic<'id, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        if f.alternate() {
+            write!(f, "#{}", self.0.mnemonic())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::{Commitment, ProtocolId};
+
+    #[test]
+    fn mnemonic_suffix() {
+        let commitment = Commitment::from([0xA5; 32]);
+        let words = commitment.mnemonic();
+        assert_eq!(words.split('-').count(), 3);
+        assert_eq!(words, "crown-marina-kayak");
+        assert_eq!(Commitment::from([0x00; 32]).mnemonic(), "sensor-geneva-provide");
+        assert_eq!(words, mnemonic(&Bytes32::from([0xA5; 32])));
+        assert_ne!(words, Commitment::from([0xA6; 32]).mnemonic());
+        assert_eq!(words, ProtocolId::from([0xA5; 32]).mnemonic());
+
+        assert_eq!(commitment.display_mnemonic().to_string(), commitment.to_string());
+        assert_eq!(format!("{:#}", commitment.display_mnemonic()), format!("{commitment}#{words}"));
+    }
+
+    #[test]
+    fn mnemonic_alternate_full_id() {
+        let commitment = Commitment::from([0xA5; 32]);
+        assert_eq!(
+            format!("{:#}", commitment.display_mnemonic()),
+            "a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5#crown-marina-kayak"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Aggregation of independent LNPBP-4 commitments from different producers
+//! into a single commitment.
+//!
+//! Aggregation is a secondary-level LNPBP-4 tree, where each producer
+//! commitment is placed as a message under the producer id. This allows an
+//! aggregation service to put commitments of unrelated parties into a single
+//! proof-of-publication (for instance, a single `OP_RETURN` output), providing
+//! each of the producers with an [`AggregatedProof`].
+
+use sha2::Sha256;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::digest::DigestExt;
+use crate::mpc::{
+    self, Commitment, InvalidProof, LeafNotKnown, MerkleBlock, MerkleProof, MerkleTree, Message,
+    MessageMap, MultiSource, ProtocolId,
+};
+use crate::{CommitmentId, ErrorCode, ErrorCodes, TryCommitVerify, LIB_NAME_COMMIT_VERIFY};
+
+/// Errors aggregating LNPBP-4 commitments.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum AggregationError {
+    /// no commitments were provided for the aggregation.
+    Empty,
+
+    /// producer {0} provided more than one commitment for the aggregation.
+    DuplicateProducer(ProtocolId),
+
+    #[from]
+    #[display(inner)]
+    Tree(mpc::Error),
+}
+
+impl ErrorCode for AggregationError {
+    fn code(&self) -> &'static str {
+        match self {
+            AggregationError::Empty => "mpc.aggregate.empty",
+            AggregationError::DuplicateProducer(_) => "mpc.aggregate.duplicate-producer",
+            AggregationError::Tree(err) => err.code(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            AggregationError::Empty => vec![],
+            AggregationError::DuplicateProducer(protocol_id) => {
+                vec![("protocol_id", protocol_id.to_string())]
+            }
+            AggregationError::Tree(err) => err.params(),
+        }
+    }
+}
+
+impl ErrorCodes for AggregationError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.aggregate.empty",
+        "mpc.aggregate.duplicate-producer",
+        "mpc.empty",
+        "mpc.too-many-messages",
+        "mpc.cant-fit",
+    ];
+}
+
+/// Converts producer LNPBP-4 commitment into a message of the aggregation
+/// tree by hashing it with [`Aggregation::TAG`], such that the aggregation
+/// message can't be confused with a protocol message of the same value.
+pub fn producer_message(commitment: Commitment) -> Message {
+    let mut engine = Sha256::from_tag(Aggregation::TAG);
+    engine.input_raw(&commitment.to_byte_array());
+    Message::from(engine.finish())
+}
+
+/// Secondary-level LNPBP-4 tree aggregating commitments of the independent
+/// producers.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Aggregation {
+    tree: MerkleTree,
+}
+
+impl Aggregation {
+    /// Tag of the hash converting producer commitments into messages of the
+    /// aggregation tree.
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:aggr:v01#23A";
+
+    /// Aggregates commitments, each given under its producer id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the crate is compiled without `rand` feature enabled and the
+    /// `static_entropy` is not provided.
+    pub fn aggregate(
+        commitments: impl IntoIterator<Item = (ProtocolId, Commitment)>,
+        static_entropy: Option<u64>,
+    ) -> Result<Self, AggregationError> {
+        let mut messages = MessageMap::default();
+        for (producer, commitment) in commitments {
+            let count = messages.len() + 1;
+            if messages
+                .insert(producer, producer_message(commitment))
+                .map_err(|_| mpc::Error::TooManyMessages(count))?
+                .is_some()
+            {
+                return Err(AggregationError::DuplicateProducer(producer));
+            }
+        }
+        if messages.is_empty() {
+            return Err(AggregationError::Empty);
+        }
+        let source = MultiSource {
+            messages,
+            static_entropy,
+            ..default!()
+        };
+        let tree = MerkleTree::try_commit(&source)?;
+        Ok(Aggregation { tree })
+    }
+
+    /// Aggregates commitments like [`Aggregation::aggregate`], taking the
+    /// entropy from the provided `rng`.
+    #[cfg(feature = "rand")]
+    pub fn aggregate_with_rng(
+        commitments: impl IntoIterator<Item = (ProtocolId, Commitment)>,
+        rng: &mut (impl rand::RngCore + rand::CryptoRng),
+    ) -> Result<Self, AggregationError> {
+        Self::aggregate(commitments, Some(rng.next_u64()))
+    }
+
+    /// Returns the secondary-level tree.
+    pub fn tree(&self) -> &MerkleTree { &self.tree }
+
+    /// Returns the aggregated commitment, which has to be published.
+    pub fn commitment(&self) -> Commitment { self.tree.commitment_id() }
+
+    /// Constructs proof for the producer, extending its own merkle proof for
+    /// some message (`inner`) with the proof of the producer commitment
+    /// inside the aggregation.
+    pub fn prove(
+        &self,
+        producer: ProtocolId,
+        inner: MerkleProof,
+    ) -> Result<AggregatedProof, LeafNotKnown> {
+        let outer = MerkleBlock::from(&self.tree).into_merkle_proof(producer)?;
+        Ok(AggregatedProof {
+            producer,
+            inner,
+            outer,
+        })
+    }
+}
+
+/// Proof of a message being committed under some protocol into a producer
+/// LNPBP-4 commitment, which is aggregated with other producer commitments.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AggregatedProof {
+    /// Producer id.
+    #[getter(as_copy)]
+    producer: ProtocolId,
+
+    /// Proof of the message inside the producer commitment.
+    inner: MerkleProof,
+
+    /// Proof of the producer commitment inside the aggregation.
+    outer: MerkleProof,
+}
+
+impl StrictSerialize for AggregatedProof {}
+impl StrictDeserialize for AggregatedProof {}
+
+impl AggregatedProof {
+    /// Convolves the proof with the `message` under the given `protocol_id`,
+    /// producing the producer and the aggregated commitments.
+    pub fn convolve(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+    ) -> Result<(Commitment, Commitment), InvalidProof> {
+        let commitment = self.inner.convolve(protocol_id, message)?;
+        let aggregated = self
+            .outer
+            .convolve(self.producer, producer_message(commitment))?;
+        Ok((commitment, aggregated))
+    }
+
+    /// Verifies that the `message` under the given `protocol_id` is committed
+    /// into the aggregated commitment.
+    pub fn verify(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        aggregated: Commitment,
+    ) -> bool {
+        matches!(
+            self.convolve(protocol_id, message),
+            Ok((_, commitment)) if commitment == aggregated
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+
+    #[test]
+    fn aggregation() {
+        let producers = make_random_messages(5);
+        let trees = producers
+            .keys()
+            .map(|producer| (*producer, make_random_tree(&make_random_messages(7))))
+            .collect::<Vec<_>>();
+        let commitments = trees
+            .iter()
+            .map(|(producer, tree)| (*producer, tree.commitment_id()));
+        let aggregation = Aggregation::aggregate(commitments, Some(0x2023)).unwrap();
+        let aggregated = aggregation.commitment();
+
+        for (producer, tree) in &trees {
+            let block = MerkleBlock::from(tree);
+            for (protocol_id, message) in tree.messages.iter() {
+                let inner = block.to_merkle_proof(*protocol_id).unwrap();
+                let proof = aggregation.prove(*producer, inner).unwrap();
+                assert_eq!(
+                    proof.convolve(*protocol_id, *message),
+                    Ok((tree.commitment_id(), aggregated))
+                );
+                assert!(proof.verify(*protocol_id, *message, aggregated));
+                assert!(!proof.verify(*protocol_id, Message::from([0u8; 32]), aggregated));
+                assert!(!proof.verify(*protocol_id, *message, tree.commitment_id()));
+                assert_ne!(
+                    producer_message(tree.commitment_id()).to_byte_array(),
+                    tree.commitment_id().to_byte_array()
+                );
+            }
+        }
+
+        let (producer, tree) = &trees[0];
+        let duplicate = [(*producer, tree.commitment_id()), (*producer, tree.commitment_id())];
+        assert_eq!(
+            Aggregation::aggregate(duplicate, Some(0)),
+            Err(AggregationError::DuplicateProducer(*producer))
+        );
+        assert_eq!(Aggregation::aggregate(None, None), Err(AggregationError::Empty));
+        assert!(aggregation
+            .prove(ProtocolId::from([0u8; 32]), default!())
+            .is_err());
+    }
+}
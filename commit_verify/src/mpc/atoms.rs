@@ -19,20 +19,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{btree_map, BTreeMap};
 use std::io::Write;
 
-use amplify::confinement::MediumOrdMap;
-use amplify::num::u5;
+use amplify::confinement::{self, Confined, U8};
+use amplify::num::{u24, u5};
 use amplify::{Bytes32, FromSliceError, Wrapper};
+use sha2::Sha256;
 
+use crate::digest::DigestExt;
 use crate::id::CommitmentId;
 use crate::merkle::MerkleNode;
-use crate::{strategies, CommitEncode, CommitStrategy};
+use crate::mpc::limits::MESSAGE_MAP_LIMIT;
+use crate::{strategies, CommitEncode, CommitStrategy, ErrorCode, ErrorCodes};
 
 pub const MPC_MINIMAL_DEPTH: u5 = u5::with(3);
 
 /// Map from protocol ids to commitment messages.
-pub type MessageMap = MediumOrdMap<ProtocolId, Message>;
+///
+/// Both strict and commit encodings of the map are strictly ordered by the
+/// protocol id (lexicographic order of its bytes) and can't contain duplicated
+/// keys; this is guaranteed by the underlying `BTreeMap`. Strict decoding
+/// rejects serialized data not following this order, so a decoded map can't
+/// silently produce a root different from the one of the encoded map.
+pub type MessageMap = Confined<BTreeMap<ProtocolId, Message>, 0, MESSAGE_MAP_LIMIT>;
 
 /// Source data for creation of multi-message commitments according to [LNPBP-4]
 /// procedure.
@@ -58,9 +68,94 @@ impl CommitStrategy for ProtocolId {
 }
 
 impl ProtocolId {
+    /// Tag of the hash deriving protocol ids from the protocol names with
+    /// [`ProtocolId::named`].
+    pub const NAME_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:name:v01#23A";
+
     pub fn copy_from_slice(slice: &[u8]) -> Result<Self, FromSliceError> {
         Bytes32::copy_from_slice(slice).map(Self)
     }
+
+    /// Normalizes human-readable protocol name, such that the same name
+    /// written in a different case or with different whitespace produces the
+    /// same protocol id.
+    ///
+    /// The normalization trims leading and trailing whitespace, collapses
+    /// internal whitespace into a single space and lowercases the name. Names
+    /// are restricted to printable ASCII characters: this makes unicode NFC
+    /// normalization a no-op and excludes confusable (homoglyph) characters.
+    pub fn normalize_name(name: &str) -> Result<String, ProtocolNameError> {
+        let mut normalized = String::with_capacity(name.len());
+        for (pos, ch) in name.chars().enumerate() {
+            if ch.is_ascii_whitespace() {
+                if !normalized.is_empty() && !normalized.ends_with(' ') {
+                    normalized.push(' ');
+                }
+            } else if ch.is_ascii_graphic() {
+                normalized.push(ch.to_ascii_lowercase());
+            } else {
+                return Err(ProtocolNameError::InvalidChar(ch, pos));
+            }
+        }
+        if normalized.ends_with(' ') {
+            normalized.pop();
+        }
+        if normalized.is_empty() {
+            return Err(ProtocolNameError::Empty);
+        }
+        if normalized.len() > u8::MAX as usize {
+            return Err(ProtocolNameError::TooLong);
+        }
+        Ok(normalized)
+    }
+
+    /// Constructs protocol id from a human-readable protocol name, normalized
+    /// with [`ProtocolId::normalize_name`].
+    pub fn named(name: &str) -> Result<Self, ProtocolNameError> {
+        let name = Self::normalize_name(name)?;
+        let mut engine = Sha256::from_tag(Self::NAME_TAG);
+        engine.input_with_len::<U8>(name.as_bytes());
+        Ok(engine.finish().into())
+    }
+}
+
+/// Errors constructing [`ProtocolId`] from a human-readable name.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ProtocolNameError {
+    /// protocol name is empty.
+    Empty,
+
+    /// normalized protocol name exceeds 255 bytes.
+    TooLong,
+
+    /// protocol name contains character {0:?} at position {1}, which is not
+    /// a printable ASCII character.
+    InvalidChar(char, usize),
+}
+
+impl ErrorCode for ProtocolNameError {
+    fn code(&self) -> &'static str {
+        match self {
+            ProtocolNameError::Empty => "mpc.name.empty",
+            ProtocolNameError::TooLong => "mpc.name.too-long",
+            ProtocolNameError::InvalidChar(..) => "mpc.name.invalid-char",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ProtocolNameError::Empty | ProtocolNameError::TooLong => vec![],
+            ProtocolNameError::InvalidChar(ch, pos) => {
+                vec![("char", format!("{ch:?}")), ("position", pos.to_string())]
+            }
+        }
+    }
+}
+
+impl ErrorCodes for ProtocolNameError {
+    const CODES: &'static [&'static str] =
+        &["mpc.name.empty", "mpc.name.too-long", "mpc.name.invalid-char"];
 }
 
 /// Original message participating in multi-message commitment.
@@ -89,24 +184,43 @@ impl Message {
     }
 }
 
+/// Leaf of the [LNPBP-4] merkle tree.
+///
+/// Leaf hash is a tagged hash with [`Leaf::TAG`](CommitmentId::TAG) of the
+/// leaf commit-encoding: `0x10 || protocol_id || message` for the inhabited
+/// leaves and `0x11 || entropy || pos` (little-endian) for the placeholders.
+///
+/// [LNPBP-4]: https://github.com/LNP-BP/LNPBPs/blob/master/lnpbp-0004.md
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, From)]
 pub enum Leaf {
+    /// Leaf holding a message under some protocol.
     Inhabited {
+        /// Protocol id.
         protocol: ProtocolId,
+        /// Message committed under the protocol.
         message: Message,
     },
+    /// Placeholder leaf filled with the tree entropy.
     Entropy {
+        /// Tree entropy.
         entropy: u64,
+        /// Position of the leaf in the tree.
         pos: u32,
     },
 }
 
 impl Leaf {
+    /// Constructs placeholder leaf at the position `pos` of the tree with the
+    /// given `entropy`.
     pub fn entropy(entropy: u64, pos: u32) -> Self { Self::Entropy { entropy, pos } }
 
+    /// Constructs leaf holding `message` under the `protocol`.
     pub fn inhabited(protocol: ProtocolId, message: Message) -> Self {
         Self::Inhabited { protocol, message }
     }
+
+    /// Computes leaf hash, which is used as a merkle node by the tree.
+    pub fn to_merkle_node(&self) -> MerkleNode { self.commitment_id() }
 }
 
 impl CommitEncode for Leaf {
@@ -159,6 +273,97 @@ impl CommitStrategy for Commitment {
 }
 
 impl Commitment {
+    /// Tag of the hash producing extended commitments with
+    /// [`Commitment::extend`].
+    pub const EXTENDED_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:extd:v01#23A";
+
+    pub fn copy_from_slice(slice: &[u8]) -> Result<Self, FromSliceError> {
+        Bytes32::copy_from_slice(slice).map(Self)
+    }
+
+    /// Constructs commitment from the merkle `root` of a tree or a block, such
+    /// that it matches their commitment ids.
+    pub fn from_root(root: MerkleNode) -> Commitment {
+        let mut engine = Sha256::from_tag(super::MerkleBlock::TAG);
+        root.commit_encode(&mut engine);
+        engine.finish().into()
+    }
+
+    /// Produces extended commitment, which additionally commits to the tree
+    /// `depth`, `cofactor` and the number of the committed messages.
+    ///
+    /// Extended commitment is an opt-in mode closing the possibility of the
+    /// same set of messages being committed with different tree parameters
+    /// without this being detectable from the final commitment value.
+    pub fn extend(self, depth: u5, cofactor: u16, count: u32) -> Commitment {
+        let mut engine = Sha256::from_tag(Self::EXTENDED_TAG);
+        self.commit_encode(&mut engine);
+        depth.to_u8().commit_encode(&mut engine);
+        cofactor.commit_encode(&mut engine);
+        count.commit_encode(&mut engine);
+        engine.finish().into()
+    }
+}
+
+/// Pre-commitment to a set of messages, created before the entropy and the
+/// final structure of the LNPBP-4 tree are known.
+///
+/// Allows commit-reveal protocols where parties must lock in the messages
+/// before seeing the anchor. Produced by [`MultiSource::precommitment`] and
+/// checked against the final tree with [`super::MerkleTree::precommitment`]
+/// or [`super::MerkleBlock::verify_precommitment`].
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = crate::LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct Precommitment(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitStrategy for Precommitment {
+    type Strategy = strategies::Strict;
+}
+
+impl Precommitment {
+    /// Tag of the hash computing pre-commitment to the set of messages with
+    /// [`Precommitment::with`].
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:msgs:v01#23A";
+    /// Tag of the hash deriving placeholder entropy from the pre-commitment
+    /// with [`Precommitment::fill_entropy`].
+    pub const FILL_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:fill:v01#23A";
+
+    /// Computes pre-commitment to the set of messages.
+    pub fn with(messages: &MessageMap) -> Self {
+        let mut engine = Sha256::from_tag(Self::TAG);
+        u24::with(messages.len() as u32).commit_encode(&mut engine);
+        for (protocol, message) in messages.iter() {
+            protocol.commit_encode(&mut engine);
+            message.commit_encode(&mut engine);
+        }
+        engine.finish().into()
+    }
+
+    /// Derives entropy for the placeholder leaves from the pre-commitment,
+    /// making the tree fully determined by the set of messages.
+    ///
+    /// NB: Placeholders filled with such entropy do not hide the number of
+    /// messages in the tree, nor do they prevent brute-forcing protocols and
+    /// messages in the tree from the known candidates.
+    pub fn fill_entropy(&self) -> u64 {
+        let mut engine = Sha256::from_tag(Self::FILL_TAG);
+        self.commit_encode(&mut engine);
+        let mut entropy = [0u8; 8];
+        entropy.copy_from_slice(&engine.finish()[..8]);
+        u64::from_le_bytes(entropy)
+    }
+
     pub fn copy_from_slice(slice: &[u8]) -> Result<Self, FromSliceError> {
         Bytes32::copy_from_slice(slice).map(Self)
     }
@@ -193,4 +398,130 @@ impl MultiSource {
             ..default!()
         }
     }
+
+    /// Constructs source for a tree without secret entropy, where the
+    /// placeholder leaves are filled with entropy derived from the `messages`
+    /// themselves (see [`Precommitment::fill_entropy`]).
+    ///
+    /// Such trees are fully deterministic and reproducible from the messages,
+    /// which is required by some audit-log use cases, at the price of the
+    /// privacy of the placeholders.
+    pub fn deterministic(messages: MessageMap) -> Self {
+        let static_entropy = Some(Precommitment::with(&messages).fill_entropy());
+        MultiSource {
+            messages,
+            static_entropy,
+            ..default!()
+        }
+    }
+
+    /// Produces binding pre-commitment to the set of messages, which does not
+    /// depend on the entropy and the structure of the final LNPBP-4 tree.
+    #[inline]
+    pub fn precommitment(&self) -> Precommitment { Precommitment::with(&self.messages) }
+
+    /// Constructs source with default parameters from the `messages`,
+    /// failing if their number exceeds [`MessageMap`] limit.
+    pub fn try_from_iter(
+        messages: impl IntoIterator<Item = (ProtocolId, Message)>,
+    ) -> Result<Self, confinement::Error> {
+        let mut source = MultiSource::default();
+        source.try_extend(messages)?;
+        Ok(source)
+    }
+
+    /// Adds `messages` to the source, replacing messages under the same
+    /// protocol ids, and failing if the resulting number of messages exceeds
+    /// [`MessageMap`] limit.
+    pub fn try_extend(
+        &mut self,
+        messages: impl IntoIterator<Item = (ProtocolId, Message)>,
+    ) -> Result<(), confinement::Error> {
+        for (protocol_id, message) in messages {
+            self.messages.insert(protocol_id, message)?;
+        }
+        Ok(())
+    }
+}
+
+/// # Panics
+///
+/// Panics if the number of messages exceeds [`MessageMap`] limit; use
+/// [`MultiSource::try_from_iter`] for the fallible version.
+impl FromIterator<(ProtocolId, Message)> for MultiSource {
+    fn from_iter<T: IntoIterator<Item = (ProtocolId, Message)>>(iter: T) -> Self {
+        MultiSource::try_from_iter(iter).expect("number of messages exceeds message map limit")
+    }
+}
+
+/// # Panics
+///
+/// Panics if the number of messages exceeds [`MessageMap`] limit; use
+/// [`MultiSource::try_extend`] for the fallible version.
+impl Extend<(ProtocolId, Message)> for MultiSource {
+    fn extend<T: IntoIterator<Item = (ProtocolId, Message)>>(&mut self, iter: T) {
+        self.try_extend(iter)
+            .expect("number of messages exceeds message map limit")
+    }
+}
+
+impl IntoIterator for MultiSource {
+    type Item = (ProtocolId, Message);
+    type IntoIter = btree_map::IntoIter<ProtocolId, Message>;
+
+    fn into_iter(self) -> Self::IntoIter { self.messages.into_iter() }
+}
+
+impl<'a> IntoIterator for &'a MultiSource {
+    type Item = (&'a ProtocolId, &'a Message);
+    type IntoIter = btree_map::Iter<'a, ProtocolId, Message>;
+
+    fn into_iter(self) -> Self::IntoIter { self.messages.iter() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::make_random_messages;
+
+    #[test]
+    fn protocol_name() {
+        let id = ProtocolId::named("RGB Contracts").unwrap();
+        assert_eq!(ProtocolId::named("  rgb \t contracts\n").unwrap(), id);
+        assert_eq!(ProtocolId::named("rgb  CONTRACTS").unwrap(), id);
+        assert_ne!(ProtocolId::named("rgbcontracts").unwrap(), id);
+        assert_eq!(ProtocolId::normalize_name(" A\tB  c ").unwrap(), "a b c");
+
+        assert_eq!(ProtocolId::named(""), Err(ProtocolNameError::Empty));
+        assert_eq!(ProtocolId::named(" \t\n"), Err(ProtocolNameError::Empty));
+        assert_eq!(ProtocolId::named(&"a".repeat(256)), Err(ProtocolNameError::TooLong));
+        assert!(ProtocolId::named(&format!("  {}  ", "a".repeat(255))).is_ok());
+        // Cyrillic "а" is confusable with latin "a"
+        assert_eq!(
+            ProtocolId::named("rgb\u{0430}"),
+            Err(ProtocolNameError::InvalidChar('\u{0430}', 3))
+        );
+        assert_eq!(
+            ProtocolId::named("e\u{0301}"),
+            Err(ProtocolNameError::InvalidChar('\u{0301}', 1))
+        );
+        assert_eq!(ProtocolId::named("a\u{0}b"), Err(ProtocolNameError::InvalidChar('\0', 1)));
+    }
+
+    #[test]
+    fn source_iter() {
+        let msgs = make_random_messages(9);
+        let source = msgs.clone().into_iter().collect::<MultiSource>();
+        assert_eq!(source.messages.len(), 9);
+        assert_eq!(source.min_depth, MPC_MINIMAL_DEPTH);
+        assert_eq!(source.clone().into_iter().collect::<BTreeMap<_, _>>(), msgs);
+        assert!((&source).into_iter().eq(msgs.iter()));
+
+        let mut extended = source.clone();
+        let other = make_random_messages(3);
+        extended.extend(other.clone());
+        assert_eq!(extended.messages.len(), 12);
+        extended.extend(other);
+        assert_eq!(extended.messages.len(), 12);
+    }
 }
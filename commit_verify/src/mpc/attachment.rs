@@ -0,0 +1,191 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Auxiliary data (range proofs, signatures etc) bound to a committed message.
+//!
+//! Attachments do not enter the 32-byte message itself; instead the message
+//! and its attachments are committed with an outer tagged hash, which is used
+//! as the message in the LNPBP-4 tree.
+
+use amplify::confinement::{self, MediumVec, SmallVec};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::mpc::{Commitment, InvalidProof, MerkleProof, Message, ProtocolId};
+use crate::{CommitmentId, LIB_NAME_COMMIT_VERIFY};
+
+/// Auxiliary data bound to a committed message.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Attachment {
+    /// Protocol-specific type of the attachment, allowing verifiers to
+    /// distinguish, for instance, range proofs from signatures.
+    pub kind: u16,
+    /// Attachment data.
+    pub data: MediumVec<u8>,
+}
+
+impl Attachment {
+    /// Constructs attachment of a given kind.
+    pub fn with(kind: u16, data: impl IntoIterator<Item = u8>) -> Result<Self, confinement::Error> {
+        Ok(Attachment {
+            kind,
+            data: MediumVec::try_from_iter(data)?,
+        })
+    }
+}
+
+/// Hook verifying attachments against the message they are bound to.
+pub trait VerifyAttachment {
+    /// Error returned by the verification.
+    type Error: std::error::Error;
+
+    /// Verifies `attachment` in the context of the `message` it is bound to.
+    fn verify_attachment(
+        &self,
+        message: Message,
+        attachment: &Attachment,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Message with a set of bound attachments.
+///
+/// The value committed into LNPBP-4 tree is the [`CommitmentId`] of this
+/// structure, and not the original message.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[derive(CommitEncode)]
+#[commit_encode(crate = crate, strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AttachedMessage {
+    /// Original message.
+    #[getter(as_copy)]
+    message: Message,
+
+    /// Attachments bound to the message.
+    attachments: SmallVec<Attachment>,
+}
+
+impl StrictSerialize for AttachedMessage {}
+impl StrictDeserialize for AttachedMessage {}
+
+impl CommitmentId for AttachedMessage {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:atch:v01#23A";
+    type Id = Message;
+}
+
+impl AttachedMessage {
+    /// Constructs message without attachments.
+    pub fn new(message: Message) -> Self {
+        AttachedMessage {
+            message,
+            attachments: empty!(),
+        }
+    }
+
+    /// Binds an attachment to the message.
+    pub fn attach(&mut self, attachment: Attachment) -> Result<(), confinement::Error> {
+        self.attachments.push(attachment)
+    }
+
+    /// Convolves the `proof` with the outer hash of the message and its
+    /// attachments under the given `protocol_id`.
+    pub fn convolve(
+        &self,
+        proof: &MerkleProof,
+        protocol_id: ProtocolId,
+    ) -> Result<Commitment, InvalidProof> {
+        proof.convolve(protocol_id, self.commitment_id())
+    }
+
+    /// Runs `verifier` on all attachments, returning the first error.
+    pub fn verify<V: VerifyAttachment>(&self, verifier: &V) -> Result<(), V::Error> {
+        for attachment in &self.attachments {
+            verifier.verify_attachment(self.message, attachment)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::tree::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::MerkleBlock;
+
+    /// attachment data must start with the message bytes.
+    #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+    #[display(doc_comments)]
+    struct PrefixError;
+
+    struct PrefixVerifier;
+
+    impl VerifyAttachment for PrefixVerifier {
+        type Error = PrefixError;
+
+        fn verify_attachment(
+            &self,
+            message: Message,
+            attachment: &Attachment,
+        ) -> Result<(), Self::Error> {
+            if !attachment.data.starts_with(&message[..]) {
+                return Err(PrefixError);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn attached_message() {
+        let mut msgs = make_random_messages(4);
+        let (pid, msg) = msgs.pop_first().unwrap();
+
+        let mut attached = AttachedMessage::new(msg);
+        let plain_id = attached.commitment_id();
+        attached
+            .attach(Attachment::with(1, msg.iter().copied().chain([0xFF])).unwrap())
+            .unwrap();
+        assert_ne!(attached.commitment_id(), plain_id);
+        assert_ne!(attached.commitment_id(), msg);
+        assert_eq!(attached.verify(&PrefixVerifier), Ok(()));
+
+        msgs.insert(pid, attached.commitment_id());
+        let tree = make_random_tree(&msgs);
+        let proof = MerkleBlock::from(&tree).to_merkle_proof(pid).unwrap();
+        assert_eq!(attached.convolve(&proof, pid), Ok(tree.commitment_id()));
+
+        attached
+            .attach(Attachment::with(2, [0u8; 4]).unwrap())
+            .unwrap();
+        assert_ne!(attached.convolve(&proof, pid), Ok(tree.commitment_id()));
+        assert_eq!(attached.verify(&PrefixVerifier), Err(PrefixError));
+    }
+}
@@ -0,0 +1,307 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Construction of LNPBP-4 trees by a coordinator from blinded leaves.
+//!
+//! In shared-anchor services participants may not trust the coordinator with
+//! their messages. Each participant submits just a [`BlindedLeaf`]: its
+//! protocol id, which defines the leaf position, and the leaf hash, which
+//! commits to the message. The coordinator builds [`MerkleBlock`] with all
+//! participant leaves concealed; each participant later reveals its own leaf
+//! in the block with [`MerkleBlock::reveal_leaf`] and extracts a complete
+//! [`super::MerkleProof`] from it.
+
+use amplify::confinement::LargeVec;
+use amplify::num::u5;
+use amplify::Wrapper;
+#[cfg(feature = "rand")]
+use rand::{CryptoRng, RngCore};
+
+use crate::merkle::MerkleNode;
+use crate::mpc::{self, Leaf, MerkleBlock, MerkleTree, Message, MultiSource, ProtocolId, TreeNode};
+use crate::{CommitmentId, ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+/// Errors constructing or revealing blinded LNPBP-4 trees.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum BlindedError {
+    /// protocol {0} has submitted more than one leaf.
+    Duplicate(ProtocolId),
+
+    /// block doesn't contain concealed leaf for protocol {0}.
+    LeafNotFound(ProtocolId),
+
+    /// message under protocol {0} doesn't match the concealed leaf.
+    LeafMismatch(ProtocolId),
+
+    #[from]
+    #[display(inner)]
+    Tree(mpc::Error),
+}
+
+impl ErrorCode for BlindedError {
+    fn code(&self) -> &'static str {
+        match self {
+            BlindedError::Duplicate(_) => "mpc.blinded.duplicate",
+            BlindedError::LeafNotFound(_) => "mpc.blinded.leaf-not-found",
+            BlindedError::LeafMismatch(_) => "mpc.blinded.leaf-mismatch",
+            BlindedError::Tree(err) => err.code(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            BlindedError::Duplicate(protocol_id) |
+            BlindedError::LeafNotFound(protocol_id) |
+            BlindedError::LeafMismatch(protocol_id) => {
+                vec![("protocol_id", protocol_id.to_string())]
+            }
+            BlindedError::Tree(err) => err.params(),
+        }
+    }
+}
+
+impl ErrorCodes for BlindedError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.blinded.duplicate",
+        "mpc.blinded.leaf-not-found",
+        "mpc.blinded.leaf-mismatch",
+        "mpc.empty",
+        "mpc.too-many-messages",
+        "mpc.cant-fit",
+    ];
+}
+
+/// Leaf of LNPBP-4 tree submitted to a coordinator without revealing the
+/// message.
+#[derive(Getters, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BlindedLeaf {
+    /// Protocol defining the leaf position in the tree.
+    #[getter(as_copy)]
+    protocol_id: ProtocolId,
+
+    /// Hash of the leaf committing to the message.
+    #[getter(as_copy)]
+    leaf: MerkleNode,
+}
+
+impl BlindedLeaf {
+    /// Constructs blinded leaf for the `message` under `protocol_id`.
+    pub fn new(protocol_id: ProtocolId, message: Message) -> Self {
+        BlindedLeaf {
+            protocol_id,
+            leaf: Leaf::inhabited(protocol_id, message).commitment_id(),
+        }
+    }
+
+    /// Checks whether the leaf commits to the `message`.
+    pub fn verify(&self, message: Message) -> bool {
+        Leaf::inhabited(self.protocol_id, message).commitment_id() == self.leaf
+    }
+}
+
+impl MerkleBlock {
+    /// Constructs merkle block from the blinded leaves, keeping all of them
+    /// concealed. The placement of the leaves is the same as of
+    /// [`MerkleTree::try_commit`] for the source with the same protocols,
+    /// `min_depth` and `entropy`, such that the resulting commitment is the
+    /// same as the one of the tree built from the original messages.
+    pub fn try_commit_blinded(
+        leaves: impl IntoIterator<Item = BlindedLeaf>,
+        min_depth: u5,
+        entropy: u64,
+    ) -> Result<Self, BlindedError> {
+        let mut source = MultiSource {
+            min_depth,
+            static_entropy: Some(entropy),
+            ..default!()
+        };
+        // Placement depends only on the protocol ids, so we put the leaf
+        // hashes in place of the messages and replace the leaves afterwards.
+        for blinded in leaves {
+            let message = Message::from(blinded.leaf.into_inner());
+            if source
+                .messages
+                .insert(blinded.protocol_id, message)
+                .map_err(|_| mpc::Error::TooManyMessages(source.messages.len() + 1))?
+                .is_some()
+            {
+                return Err(BlindedError::Duplicate(blinded.protocol_id));
+            }
+        }
+        let tree = MerkleTree::try_commit(&source)?;
+
+        let block = MerkleBlock::from(&tree);
+        let cross_section = block.cross_section().iter().map(|node| match *node {
+            TreeNode::CommitmentLeaf { message, .. } => TreeNode::ConcealedNode {
+                depth: block.depth(),
+                hash: MerkleNode::from(message.into_inner()),
+            },
+            node => node,
+        });
+        let cross_section =
+            LargeVec::try_from_iter(cross_section).expect("tree width guarantees are broken");
+        Ok(MerkleBlock::with_cross_section(
+            block.depth(),
+            block.cofactor(),
+            block.entropy(),
+            cross_section,
+        ))
+    }
+
+    /// Constructs merkle block from the blinded leaves like
+    /// [`MerkleBlock::try_commit_blinded`], taking the entropy from the
+    /// provided `rng`.
+    #[cfg(feature = "rand")]
+    pub fn try_commit_blinded_with_rng(
+        leaves: impl IntoIterator<Item = BlindedLeaf>,
+        min_depth: u5,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<Self, BlindedError> {
+        Self::try_commit_blinded(leaves, min_depth, rng.next_u64())
+    }
+
+    /// Reveals concealed leaf of the block with the `message` under the
+    /// `protocol_id`, after which a merkle proof for the protocol can be
+    /// extracted from the block. The block commitment doesn't change.
+    pub fn reveal_leaf(
+        &mut self,
+        protocol_id: ProtocolId,
+        message: Message,
+    ) -> Result<(), BlindedError> {
+        let pos = self.protocol_id_pos(protocol_id);
+        let depth = self.depth();
+        let mut offset = 0u32;
+        let index = self
+            .cross_section()
+            .iter()
+            .position(|node| {
+                let node_depth = node.depth_or(depth);
+                let found = offset == pos && node_depth == depth;
+                offset += 1 << (depth.to_u8() - node_depth.to_u8());
+                found
+            })
+            .ok_or(BlindedError::LeafNotFound(protocol_id))?;
+        match self.cross_section()[index] {
+            TreeNode::CommitmentLeaf { .. } => return Err(BlindedError::LeafNotFound(protocol_id)),
+            TreeNode::ConcealedNode { hash, .. }
+                if hash != Leaf::inhabited(protocol_id, message).commitment_id() =>
+            {
+                return Err(BlindedError::LeafMismatch(protocol_id));
+            }
+            TreeNode::ConcealedNode { .. } => {}
+        }
+
+        let mut cross_section = self.cross_section().to_vec();
+        cross_section[index] = TreeNode::CommitmentLeaf {
+            protocol_id,
+            message,
+        };
+        *self = MerkleBlock::with_cross_section(
+            depth,
+            self.cofactor(),
+            self.entropy(),
+            LargeVec::try_from(cross_section).expect("tree width guarantees are broken"),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+
+    use super::*;
+    use crate::mpc::test_helpers::make_random_messages;
+
+    #[test]
+    fn blinded_tree() {
+        let msgs = make_random_messages(11);
+        let entropy = 0xB11D;
+        let leaves = msgs
+            .iter()
+            .map(|(protocol_id, message)| BlindedLeaf::new(*protocol_id, *message));
+        let block = MerkleBlock::try_commit_blinded(leaves, u5::ZERO, entropy).unwrap();
+        assert!(block.to_known_message_map().is_empty());
+
+        let source = MultiSource {
+            min_depth: u5::ZERO,
+            messages: Confined::try_from(msgs.clone()).unwrap(),
+            static_entropy: Some(entropy),
+        };
+        let tree = MerkleTree::try_commit(&source).unwrap();
+        let commitment = tree.commitment_id();
+        assert_eq!(block.commitment_id(), commitment);
+
+        for (protocol_id, message) in &msgs {
+            let mut block = block.clone();
+            assert_eq!(
+                block.reveal_leaf(*protocol_id, Message::from([0xFF; 32])),
+                Err(BlindedError::LeafMismatch(*protocol_id))
+            );
+            block.reveal_leaf(*protocol_id, *message).unwrap();
+            assert_eq!(block.commitment_id(), commitment);
+            assert_eq!(
+                block.reveal_leaf(*protocol_id, *message),
+                Err(BlindedError::LeafNotFound(*protocol_id))
+            );
+            let proof = block.to_merkle_proof(*protocol_id).unwrap();
+            assert_eq!(proof.convolve(*protocol_id, *message), Ok(commitment));
+        }
+
+        let leaf = BlindedLeaf::new(ProtocolId::from([1u8; 32]), Message::from([2u8; 32]));
+        assert!(leaf.verify(Message::from([2u8; 32])));
+        assert_eq!(
+            MerkleBlock::try_commit_blinded([leaf, leaf], u5::ZERO, entropy),
+            Err(BlindedError::Duplicate(leaf.protocol_id()))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn blinded_tree_with_rng() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let msgs = make_random_messages(5);
+        let leaves = msgs
+            .iter()
+            .map(|(protocol_id, message)| BlindedLeaf::new(*protocol_id, *message))
+            .collect::<Vec<_>>();
+        let block1 = MerkleBlock::try_commit_blinded_with_rng(
+            leaves.clone(),
+            u5::ZERO,
+            &mut StdRng::seed_from_u64(1),
+        )
+        .unwrap();
+        let block2 =
+            MerkleBlock::try_commit_blinded(leaves, u5::ZERO, StdRng::seed_from_u64(1).next_u64())
+                .unwrap();
+        assert_eq!(block1, block2);
+    }
+}
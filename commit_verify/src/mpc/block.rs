@@ -24,24 +24,31 @@
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
 
-use amplify::confinement::{Confined, LargeVec};
+use amplify::confinement::{Confined, LargeVec, TinyVec};
 use amplify::num::u5;
+use amplify::Bytes32;
+use sha2::Sha256;
 use strict_encoding::{StrictDeserialize, StrictEncode, StrictSerialize};
 
+use crate::digest::DigestExt;
 use crate::id::CommitmentId;
 use crate::merkle::{MerkleBuoy, MerkleNode};
 use crate::mpc::atoms::Leaf;
 use crate::mpc::tree::protocol_id_pos;
 use crate::mpc::{
-    Commitment, MerkleTree, Message, MessageMap, Proof, ProtocolId, MERKLE_LNPBP4_TAG,
+    Commitment, MerklePath, MerkleTree, Message, MessageMap, Precommitment, Proof, ProtocolId,
+    MERKLE_LNPBP4_TAG,
+};
+use crate::{
+    CommitEncode, Conceal, ErrorCode, ErrorCodes, VerifiableProof, VerifyError,
+    LIB_NAME_COMMIT_VERIFY,
 };
-use crate::{Conceal, LIB_NAME_COMMIT_VERIFY};
 
 /// commitment under protocol id {0} is absent from the known part of a given
 /// LNPBP-4 Merkle block.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
 #[display(doc_comments)]
-pub struct LeafNotKnown(ProtocolId);
+pub struct LeafNotKnown(pub(super) ProtocolId);
 
 /// the provided merkle proof protocol id {protocol_id} position {actual}
 /// doesn't match the expected position {expected} within the tree of width
@@ -75,6 +82,300 @@ pub enum MergeError {
     },
 }
 
+impl ErrorCode for LeafNotKnown {
+    fn code(&self) -> &'static str { "mpc.leaf-not-known" }
+
+    fn params(&self) -> Vec<(&'static str, String)> { vec![("protocol_id", self.0.to_string())] }
+}
+
+impl ErrorCodes for LeafNotKnown {
+    const CODES: &'static [&'static str] = &["mpc.leaf-not-known"];
+}
+
+impl ErrorCode for InvalidProof {
+    fn code(&self) -> &'static str { "mpc.invalid-proof" }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("protocol_id", self.protocol_id.to_string()),
+            ("actual", self.actual.to_string()),
+            ("expected", self.expected.to_string()),
+            ("width", self.width.to_string()),
+        ]
+    }
+}
+
+impl ErrorCodes for InvalidProof {
+    const CODES: &'static [&'static str] = &["mpc.invalid-proof"];
+}
+
+impl ErrorCode for MergeError {
+    fn code(&self) -> &'static str {
+        match self {
+            MergeError::InvalidProof(err) => err.code(),
+            MergeError::UnrelatedBlocks { .. } => "mpc.unrelated-blocks",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            MergeError::InvalidProof(err) => err.params(),
+            MergeError::UnrelatedBlocks {
+                base_root,
+                merged_root,
+            } => {
+                vec![("base_root", base_root.to_string()), ("merged_root", merged_root.to_string())]
+            }
+        }
+    }
+}
+
+impl ErrorCodes for MergeError {
+    const CODES: &'static [&'static str] = &["mpc.unrelated-blocks", "mpc.invalid-proof"];
+}
+
+/// Non-canonical encodings of LNPBP-4 blocks and proofs, which have the same
+/// commitment id as their canonical versions.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum NonCanonical {
+    /// cofactor {actual} is not the smallest cofactor placing the revealed
+    /// leaves at their positions and must be encoded as {canonical}.
+    Cofactor { actual: u16, canonical: u16 },
+
+    /// block contains {0} concealed nodes which must be aggregated with their
+    /// concealed siblings.
+    RedundantConcealed(usize),
+}
+
+impl ErrorCode for NonCanonical {
+    fn code(&self) -> &'static str {
+        match self {
+            NonCanonical::Cofactor { .. } => "mpc.non-canonical.cofactor",
+            NonCanonical::RedundantConcealed(_) => "mpc.non-canonical.concealed",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            NonCanonical::Cofactor { actual, canonical } => {
+                vec![("actual", actual.to_string()), ("canonical", canonical.to_string())]
+            }
+            NonCanonical::RedundantConcealed(count) => vec![("count", count.to_string())],
+        }
+    }
+}
+
+impl ErrorCodes for NonCanonical {
+    const CODES: &'static [&'static str] =
+        &["mpc.non-canonical.cofactor", "mpc.non-canonical.concealed"];
+}
+
+/// Enumerates all cofactors placing each of the revealed `leaves` at its
+/// position within a tree of the given `width`, in ascending order. Cofactors
+/// are not committed to, so all of them produce encodings with the same
+/// commitment id.
+///
+/// Cofactors starting from `width - 1` reduce the placement modulus to one and
+/// place all leaves at the same position, so only the cofactors below that are
+/// checked individually.
+fn equivalent_cofactors(
+    leaves: &[(u32, ProtocolId)],
+    width: u32,
+) -> impl Iterator<Item = u16> + '_ {
+    let saturated = u16::try_from(width.saturating_sub(1)).unwrap_or(u16::MAX);
+    let placed = move |cofactor: &u16| {
+        leaves
+            .iter()
+            .all(|(pos, protocol_id)| protocol_id_pos(*protocol_id, *cofactor, width) == *pos)
+    };
+    let tail = placed(&saturated).then_some(saturated..=u16::MAX);
+    (0..saturated)
+        .filter(placed)
+        .chain(tail.into_iter().flatten())
+}
+
+/// Returns the smallest cofactor equivalent to the `cofactor` (see
+/// [`equivalent_cofactors`]), or the `cofactor` itself if it doesn't place the
+/// `leaves` at their positions.
+fn canonical_cofactor(cofactor: u16, leaves: &[(u32, ProtocolId)], width: u32) -> u16 {
+    equivalent_cofactors(leaves, width)
+        .take_while(|equivalent| *equivalent <= cofactor)
+        .next()
+        .unwrap_or(cofactor)
+}
+
+/// Errors checking LNPBP-4 Merkle block against a [`Precommitment`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum PrecommitmentError {
+    /// the block doesn't contain information about the entropy used for the
+    /// placeholders, so the complete set of messages can't be determined.
+    NoEntropy,
+
+    /// the block contains concealed tree node or leaf at position {0}, so the
+    /// complete set of messages can't be determined.
+    ConcealedLeaf(u32),
+
+    /// the set of messages revealed in the block doesn't match the
+    /// pre-commitment.
+    Mismatch,
+
+    /// message for the protocol {protocol_id} is revealed at position {actual}
+    /// instead of {expected}.
+    Misplaced {
+        protocol_id: ProtocolId,
+        expected: u32,
+        actual: u32,
+    },
+
+    /// the block reveals more than one message for the protocol {0}.
+    DuplicateProtocol(ProtocolId),
+}
+
+impl ErrorCode for PrecommitmentError {
+    fn code(&self) -> &'static str {
+        match self {
+            PrecommitmentError::NoEntropy => "mpc.precommitment.no-entropy",
+            PrecommitmentError::ConcealedLeaf(_) => "mpc.precommitment.concealed",
+            PrecommitmentError::Mismatch => "mpc.precommitment.mismatch",
+            PrecommitmentError::Misplaced { .. } => "mpc.precommitment.misplaced",
+            PrecommitmentError::DuplicateProtocol(_) => "mpc.precommitment.duplicate",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            PrecommitmentError::ConcealedLeaf(pos) => vec![("pos", pos.to_string())],
+            PrecommitmentError::Misplaced {
+                protocol_id,
+                expected,
+                actual,
+            } => vec![
+                ("protocol_id", protocol_id.to_string()),
+                ("actual", actual.to_string()),
+                ("expected", expected.to_string()),
+            ],
+            PrecommitmentError::DuplicateProtocol(protocol_id) => {
+                vec![("protocol_id", protocol_id.to_string())]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl ErrorCodes for PrecommitmentError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.precommitment.no-entropy",
+        "mpc.precommitment.concealed",
+        "mpc.precommitment.mismatch",
+        "mpc.precommitment.misplaced",
+        "mpc.precommitment.duplicate",
+    ];
+}
+
+/// Errors of the resumable LNPBP-4 Merkle block verification.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ResumableError {
+    /// the verification checkpoint doesn't belong to the verified Merkle block
+    /// or the expected commitment.
+    CheckpointMismatch,
+
+    /// the Merkle block has invalid cross-section structure at position {0}.
+    InvalidCrossSection(u32),
+
+    /// the Merkle block doesn't match the expected commitment.
+    CommitmentMismatch,
+}
+
+impl ErrorCode for ResumableError {
+    fn code(&self) -> &'static str {
+        match self {
+            ResumableError::CheckpointMismatch => "mpc.resumable.checkpoint-mismatch",
+            ResumableError::InvalidCrossSection(_) => "mpc.resumable.invalid-cross-section",
+            ResumableError::CommitmentMismatch => "mpc.resumable.commitment-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ResumableError::InvalidCrossSection(pos) => vec![("pos", pos.to_string())],
+            _ => vec![],
+        }
+    }
+}
+
+impl ErrorCodes for ResumableError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.resumable.checkpoint-mismatch",
+        "mpc.resumable.invalid-cross-section",
+        "mpc.resumable.commitment-mismatch",
+    ];
+}
+
+/// Serializable state of interrupted Merkle block verification, allowing to
+/// resume it with [`MerkleBlock::verify_resumable`].
+///
+/// The checkpoint is tied to the processed part of the block cross-section by
+/// its digest, which is checked on resume. The digest is not keyed, so it
+/// detects checkpoints of other blocks, but not forged ones: the checkpoints
+/// must be kept by the verifier itself.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct VerifyCheckpoint {
+    /// Commitment id the block is verified against.
+    #[getter(as_copy)]
+    commitment: Commitment,
+
+    /// Depth of the verified block.
+    #[getter(as_copy)]
+    depth: u5,
+
+    /// Number of the nodes in the verified block cross-section.
+    #[getter(as_copy)]
+    len: u32,
+
+    /// Number of the cross-section nodes already processed.
+    #[getter(as_copy)]
+    processed: u32,
+
+    /// Digest of the already processed cross-section nodes (see
+    /// [`VerifyCheckpoint::PREFIX_TAG`]).
+    #[getter(as_copy)]
+    prefix: Bytes32,
+
+    /// Roots of the already completed subtrees, with the depth increasing
+    /// towards the end.
+    #[getter(skip)]
+    stack: TinyVec<TreeNode>,
+}
+
+impl StrictSerialize for VerifyCheckpoint {}
+impl StrictDeserialize for VerifyCheckpoint {}
+
+impl VerifyCheckpoint {
+    /// Tag of the digest of the processed cross-section nodes.
+    pub const PREFIX_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:prfx:v01#23A";
+}
+
+/// Result of a single step of the resumable Merkle block verification.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum VerifyProgress {
+    /// Verification was suspended after exhausting the work budget and can be
+    /// resumed from the checkpoint.
+    Suspended(VerifyCheckpoint),
+
+    /// Verification was completed successfully.
+    Verified,
+}
+
 /// LNPBP-4 Merkle tree node.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -84,7 +385,7 @@ pub enum MergeError {
     dumb = { TreeNode::ConcealedNode { depth: u5::ZERO, hash: [0u8; 32].into() } }
 )]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
-enum TreeNode {
+pub enum TreeNode {
     /// A node of the tree with concealed leaf or tree branch information.
     ConcealedNode {
         /// Depth of the node.
@@ -195,6 +496,22 @@ impl From<MerkleTree> for MerkleBlock {
 }
 
 impl MerkleBlock {
+    pub(crate) fn with_cross_section(
+        depth: u5,
+        cofactor: u16,
+        entropy: Option<u64>,
+        cross_section: LargeVec<TreeNode>,
+    ) -> Self {
+        MerkleBlock {
+            depth,
+            cofactor,
+            cross_section,
+            entropy,
+        }
+    }
+
+    pub(crate) fn cross_section(&self) -> &[TreeNode] { &self.cross_section }
+
     /// Constructs merkle block from a merkle proof
     pub fn with(
         proof: &MerkleProof,
@@ -295,6 +612,15 @@ impl MerkleBlock {
             return Err(LeafNotKnown(protocol_id));
         }
 
+        count += self.aggregate_concealed();
+
+        Ok(count)
+    }
+
+    /// Aggregates pairs of concealed sibling nodes into their parent concealed
+    /// node, returning the number of aggregated nodes.
+    fn aggregate_concealed(&mut self) -> usize {
+        let mut count = 0usize;
         loop {
             debug_assert!(!self.cross_section.is_empty());
             let prev_count = count;
@@ -370,7 +696,113 @@ impl MerkleBlock {
             debug_assert_eq!(offset, self.width());
         }
 
-        Ok(count)
+        count
+    }
+
+    /// Conceals all revealed commitments in the block under protocols not
+    /// matching the `keep` predicate, while preserving the block commitment id
+    /// and proofs for the retained protocols. Like [`Self::conceal_except`],
+    /// removes information about the entropy value used.
+    ///
+    /// # Returns
+    ///
+    /// Number of concealed nodes.
+    pub fn prune(&mut self, keep: impl Fn(ProtocolId) -> bool) -> usize {
+        let protocols = self
+            .cross_section
+            .iter()
+            .filter_map(|node| match node {
+                TreeNode::CommitmentLeaf { protocol_id, .. } if keep(*protocol_id) => {
+                    Some(*protocol_id)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        self.conceal_except(protocols)
+            .expect("protocols are taken from the revealed leaves of the block")
+    }
+
+    /// Checks that the block is encoded canonically, such that no other
+    /// encoding of a block with the same commitment id and the same revealed
+    /// information exists.
+    ///
+    /// The canonical cofactor is the smallest one placing all revealed leaves
+    /// at their positions. Since it depends on the set of the revealed leaves,
+    /// blocks must be canonicalized only after all the merges (see
+    /// [`Self::merge_reveal`]). The cofactor is committed to by the
+    /// [`Self::extended_commitment`], which is not preserved by the
+    /// canonicalization.
+    ///
+    /// Blocks with known entropy keep all their placeholder leaves, which are
+    /// required for [`Self::verify_precommitment`], so their concealed nodes
+    /// are not checked.
+    pub fn check_canonical(&self) -> Result<(), NonCanonical> {
+        let canonical = canonical_cofactor(self.cofactor, &self.revealed_leaves(), self.width());
+        if canonical != self.cofactor {
+            return Err(NonCanonical::Cofactor {
+                actual: self.cofactor,
+                canonical,
+            });
+        }
+        if self.entropy.is_none() {
+            let count = self.clone().aggregate_concealed();
+            if count > 0 {
+                return Err(NonCanonical::RedundantConcealed(count));
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts the block into its canonical encoding (see
+    /// [`Self::check_canonical`]), preserving its commitment id.
+    ///
+    /// The canonical cofactor is computed from the revealed leaves only and
+    /// may differ from the cofactor of the original tree, so the
+    /// canonicalization changes the [`Self::extended_commitment`] and proofs
+    /// for the leaves concealed in the block may not merge into it anymore.
+    ///
+    /// # Returns
+    ///
+    /// Whether the encoding was changed.
+    pub fn canonicalize(&mut self) -> bool {
+        let canonical = canonical_cofactor(self.cofactor, &self.revealed_leaves(), self.width());
+        let mut changed = canonical != self.cofactor;
+        self.cofactor = canonical;
+        if self.entropy.is_none() {
+            changed |= self.aggregate_concealed() > 0;
+        }
+        changed
+    }
+
+    /// Enumerates encodings of the block which differ from it only by the
+    /// cofactor, having the same commitment id and revealing the same leaves
+    /// at the same positions.
+    pub fn alternative_encodings(&self) -> impl Iterator<Item = MerkleBlock> + '_ {
+        let leaves = self.revealed_leaves();
+        let cofactors = equivalent_cofactors(&leaves, self.width()).collect::<Vec<_>>();
+        cofactors
+            .into_iter()
+            .filter(move |cofactor| *cofactor != self.cofactor)
+            .map(move |cofactor| MerkleBlock {
+                cofactor,
+                ..self.clone()
+            })
+    }
+
+    /// Returns protocol ids of the revealed leaves together with their
+    /// positions within the tree.
+    fn revealed_leaves(&self) -> Vec<(u32, ProtocolId)> {
+        let mut leaves = vec![];
+        let mut offset = 0u32;
+        for node in &self.cross_section {
+            if let TreeNode::CommitmentLeaf { protocol_id, .. } = node {
+                leaves.push((offset, *protocol_id));
+            }
+            let depth = node.depth_or(self.depth);
+            offset = offset
+                .saturating_add(2u32.pow(self.depth.to_u8().saturating_sub(depth.to_u8()) as u32));
+        }
+        leaves
     }
 
     /// Merges information from the given `proof` to the merkle block, revealing
@@ -503,21 +935,16 @@ Changed commitment id: {}",
 
     /// Converts the merkle block into a merkle proof for the inclusion of a
     /// commitment under given `protocol_id`.
-    pub fn into_merkle_proof(
-        mut self,
-        protocol_id: ProtocolId,
-    ) -> Result<MerkleProof, LeafNotKnown> {
-        self.conceal_except([protocol_id])?;
-        let mut map = BTreeMap::<u5, MerkleNode>::new();
-        for node in &self.cross_section {
-            match node {
-                TreeNode::ConcealedNode { depth, hash } => {
-                    let inserted = map.insert(*depth, *hash).is_none();
-                    debug_assert!(inserted, "MerkleBlock conceal procedure is broken");
-                }
-                TreeNode::CommitmentLeaf { .. } => {}
-            }
-        }
+    #[inline]
+    pub fn into_merkle_proof(self, protocol_id: ProtocolId) -> Result<MerkleProof, LeafNotKnown> {
+        self.to_merkle_proof(protocol_id)
+    }
+
+    /// Constructs merkle proof for the inclusion of a commitment under given
+    /// `protocol_id` for the current Merkle block.
+    pub fn to_merkle_proof(&self, protocol_id: ProtocolId) -> Result<MerkleProof, LeafNotKnown> {
+        let (_, map) = self.merklize(Some(protocol_id));
+        let map = map.ok_or(LeafNotKnown(protocol_id))?;
         debug_assert_eq!(
             self.depth.to_u8() as usize,
             map.len(),
@@ -526,15 +953,61 @@ Changed commitment id: {}",
         Ok(MerkleProof {
             pos: self.protocol_id_pos(protocol_id),
             cofactor: self.cofactor,
-            path: Confined::try_from_iter(map.into_values())
+            path: MerklePath::try_from_iter(map.into_values())
                 .expect("tree width guarantees are broken"),
         })
     }
 
-    /// Constructs merkle proof for the inclusion of a commitment under given
-    /// `protocol_id` for the current Merkle block.
-    pub fn to_merkle_proof(&self, protocol_id: ProtocolId) -> Result<MerkleProof, LeafNotKnown> {
-        self.clone().into_merkle_proof(protocol_id)
+    /// Computes the root of the block by merging its cross-section without
+    /// modifying (or cloning) it, collecting along the way the path of
+    /// hashing partners of the leaf for the `protocol_id`, if provided.
+    ///
+    /// The path is returned only if the leaf for the `protocol_id` is present
+    /// in the block.
+    fn merklize(
+        &self,
+        protocol_id: Option<ProtocolId>,
+    ) -> (MerkleNode, Option<BTreeMap<u5, MerkleNode>>) {
+        let width = self.width();
+        let mut path = BTreeMap::<u5, MerkleNode>::new();
+        let mut found = false;
+        // Nodes which are waiting for their siblings, with the flag indicating
+        // whether they contain the leaf for the `protocol_id`
+        let mut stack = Vec::<(u5, MerkleNode, bool)>::with_capacity(self.depth.to_usize() + 1);
+        for node in &self.cross_section {
+            let mut depth = node.depth_or(self.depth);
+            let mut hash = node.to_merkle_node();
+            let mut on_path = matches!(node, TreeNode::CommitmentLeaf { protocol_id: p, .. }
+                if Some(*p) == protocol_id);
+            found |= on_path;
+            while let Some((sibling_depth, sibling, sibling_on_path)) = stack.last().copied() {
+                if sibling_depth != depth || depth.to_u8() == 0 {
+                    break;
+                }
+                stack.pop();
+                if on_path {
+                    path.insert(depth, sibling);
+                } else if sibling_on_path {
+                    path.insert(depth, hash);
+                }
+                on_path |= sibling_on_path;
+                depth = depth - 1;
+                hash = MerkleNode::branches(
+                    MERKLE_LNPBP4_TAG.to_be_bytes(),
+                    depth,
+                    width,
+                    sibling,
+                    hash,
+                );
+            }
+            stack.push((depth, hash, on_path));
+        }
+        debug_assert_eq!(stack.len(), 1, "broken internal MerkleBlock structure");
+        let root = stack
+            .first()
+            .map(|(_, hash, _)| *hash)
+            .expect("broken internal MerkleBlock structure");
+        (root, if found { Some(path) } else { None })
     }
 
     /// Computes position for a given `protocol_id` within the tree leaves.
@@ -545,6 +1018,191 @@ Changed commitment id: {}",
     /// Computes the width of the merkle tree.
     pub fn width(&self) -> u32 { 2u32.pow(self.depth.to_u8() as u32) }
 
+    /// Verifies that the block commits to exactly the set of messages
+    /// pre-committed with [`crate::mpc::MultiSource::precommitment`].
+    ///
+    /// This requires all block leaves to be either revealed, or to be entropy
+    /// placeholders, which are checked using the entropy value known to the
+    /// block.
+    ///
+    /// # Error
+    ///
+    /// Errors if the entropy is unknown, some of the leaves are concealed and
+    /// are not placeholders, the revealed messages are not placed at the
+    /// positions defined by their protocol ids, the same protocol is revealed
+    /// more than once, or the revealed messages don't match the
+    /// pre-commitment.
+    pub fn verify_precommitment(
+        &self,
+        precommitment: Precommitment,
+    ) -> Result<(), PrecommitmentError> {
+        let entropy = self.entropy.ok_or(PrecommitmentError::NoEntropy)?;
+        let mut protocols = BTreeSet::new();
+        for (pos, node) in self.cross_section.iter().enumerate() {
+            let pos = pos as u32;
+            match node {
+                TreeNode::ConcealedNode { depth, hash }
+                    if *depth == self.depth &&
+                        *hash == Leaf::entropy(entropy, pos).commitment_id() => {}
+                TreeNode::ConcealedNode { .. } => {
+                    return Err(PrecommitmentError::ConcealedLeaf(pos));
+                }
+                TreeNode::CommitmentLeaf { protocol_id, .. } => {
+                    if !protocols.insert(*protocol_id) {
+                        return Err(PrecommitmentError::DuplicateProtocol(*protocol_id));
+                    }
+                    let expected = self.protocol_id_pos(*protocol_id);
+                    if expected != pos {
+                        return Err(PrecommitmentError::Misplaced {
+                            protocol_id: *protocol_id,
+                            expected,
+                            actual: pos,
+                        });
+                    }
+                }
+            }
+        }
+        if Precommitment::with(&self.to_known_message_map()) != precommitment {
+            return Err(PrecommitmentError::Mismatch);
+        }
+        Ok(())
+    }
+
+    /// Verifies the block against the `expected` commitment processing no more
+    /// than `budget` cross-section nodes, which allows splitting verification
+    /// of very large blocks into bounded work units.
+    ///
+    /// The verification starts from the beginning if no `checkpoint` is
+    /// provided; otherwise it continues from the provided checkpoint returned
+    /// by the previous call. A zero `budget` is treated as one, such that each
+    /// call makes progress.
+    ///
+    /// Resuming recomputes the digest of the already processed nodes to check
+    /// that the checkpoint was created for this block. This is a single hash
+    /// over the nodes data, which is not limited by the `budget`.
+    ///
+    /// # Error
+    ///
+    /// Errors if the checkpoint was created for a different block (including
+    /// blocks of the same depth and size, but with different nodes) or a
+    /// different `expected` commitment, the block has an invalid structure or
+    /// doesn't match the `expected` commitment.
+    pub fn verify_resumable(
+        &self,
+        expected: Commitment,
+        checkpoint: Option<VerifyCheckpoint>,
+        budget: u32,
+    ) -> Result<VerifyProgress, ResumableError> {
+        let len = self.cross_section.len() as u32;
+        let mut checkpoint = checkpoint.unwrap_or_else(|| VerifyCheckpoint {
+            commitment: expected,
+            depth: self.depth,
+            len,
+            processed: 0,
+            prefix: self.prefix_digest(0),
+            stack: empty!(),
+        });
+        if checkpoint.commitment != expected ||
+            checkpoint.depth != self.depth ||
+            checkpoint.len != len ||
+            checkpoint.processed > len ||
+            checkpoint.prefix != self.prefix_digest(checkpoint.processed)
+        {
+            return Err(ResumableError::CheckpointMismatch);
+        }
+
+        let start = checkpoint.processed;
+        let end = start.saturating_add(budget.max(1)).min(len);
+        let mut stack = checkpoint
+            .stack
+            .release()
+            .into_iter()
+            .map(|node| (node.depth_or(self.depth), node.to_merkle_node()))
+            .collect::<Vec<_>>();
+        for pos in start..end {
+            let node = self.cross_section[pos as usize];
+            let depth = node.depth_or(self.depth);
+            if depth > self.depth {
+                return Err(ResumableError::InvalidCrossSection(pos));
+            }
+            stack.push((depth, node.to_merkle_node()));
+            // Aggregate sibling subtrees as long as they are complete
+            while let [.., (depth1, hash1), (depth2, hash2)] = stack[..] {
+                if depth1 > depth2 || depth1 == u5::ZERO {
+                    return Err(ResumableError::InvalidCrossSection(pos));
+                }
+                if depth1 < depth2 {
+                    break;
+                }
+                let depth = depth1 - 1;
+                let hash = MerkleNode::branches(
+                    MERKLE_LNPBP4_TAG.to_be_bytes(),
+                    depth,
+                    self.width(),
+                    hash1,
+                    hash2,
+                );
+                stack.truncate(stack.len() - 2);
+                stack.push((depth, hash));
+            }
+        }
+        // Depths of the nodes in the stack are strictly increasing and never
+        // exceed 32
+        checkpoint.stack = Confined::try_from_iter(
+            stack
+                .into_iter()
+                .map(|(depth, hash)| TreeNode::ConcealedNode { depth, hash }),
+        )
+        .expect("stack size never exceeds 33");
+        checkpoint.processed = end;
+        checkpoint.prefix = self.prefix_digest(end);
+
+        if end < len {
+            return Ok(VerifyProgress::Suspended(checkpoint));
+        }
+        let root = match checkpoint.stack.as_slice() {
+            [TreeNode::ConcealedNode { depth, hash }] if *depth == u5::ZERO => *hash,
+            _ => return Err(ResumableError::InvalidCrossSection(len)),
+        };
+        if Commitment::from_root(root) != expected {
+            return Err(ResumableError::CommitmentMismatch);
+        }
+        Ok(VerifyProgress::Verified)
+    }
+
+    /// Computes digest of the first `len` nodes of the cross-section for
+    /// [`VerifyCheckpoint`].
+    fn prefix_digest(&self, len: u32) -> Bytes32 {
+        let mut engine = Sha256::from_tag(VerifyCheckpoint::PREFIX_TAG);
+        for node in &self.cross_section[..len as usize] {
+            match node {
+                TreeNode::ConcealedNode { depth, hash } => {
+                    engine.input_raw(&[0x00, depth.to_u8()]);
+                    engine.input_raw(hash.as_slice());
+                }
+                TreeNode::CommitmentLeaf {
+                    protocol_id,
+                    message,
+                } => {
+                    engine.input_raw(&[0x01]);
+                    engine.input_raw(protocol_id.as_slice());
+                    engine.input_raw(message.as_slice());
+                }
+            }
+        }
+        engine.finish().into()
+    }
+
+    /// Computes extended commitment, which additionally commits to the tree
+    /// depth, cofactor and the number of messages. See [`Commitment::extend`].
+    ///
+    /// Since the block may not reveal all of the messages, their total `count`
+    /// must be provided by the caller.
+    pub fn extended_commitment(&self, count: u32) -> Commitment {
+        self.commitment_id()
+            .extend(self.depth, self.cofactor, count)
+    }
+
     /// Constructs [`MessageMap`] for revealed protocols and messages.
     pub fn to_known_message_map(&self) -> MessageMap {
         Confined::try_from_iter(
@@ -561,20 +1219,25 @@ Changed commitment id: {}",
         )
         .expect("same collection size")
     }
+
+    /// Returns message committed under the `protocol_id`, if the leaf for the
+    /// protocol is revealed in the block.
+    pub fn known_message(&self, protocol_id: ProtocolId) -> Option<Message> {
+        self.cross_section.iter().find_map(|item| match item {
+            TreeNode::CommitmentLeaf {
+                protocol_id: p,
+                message,
+            } if *p == protocol_id => Some(*message),
+            _ => None,
+        })
+    }
 }
 
 impl Conceal for MerkleBlock {
     type Concealed = MerkleNode;
 
     /// Reduces merkle tree into merkle tree root.
-    fn conceal(&self) -> Self::Concealed {
-        let mut concealed = self.clone();
-        concealed
-            .conceal_except([])
-            .expect("broken internal MerkleBlock structure");
-        debug_assert_eq!(concealed.cross_section.len(), 1);
-        concealed.cross_section[0].to_merkle_node()
-    }
+    fn conceal(&self) -> Self::Concealed { self.merklize(None).0 }
 }
 
 impl CommitmentId for MerkleBlock {
@@ -582,6 +1245,16 @@ impl CommitmentId for MerkleBlock {
     type Id = Commitment;
 }
 
+impl VerifiableProof for MerkleBlock {
+    /// Verifies that the block commits to the `commitment`.
+    fn verify_dyn(&self, commitment: &[u8; 32]) -> Result<(), VerifyError> {
+        if self.commitment_id() != Commitment::from(*commitment) {
+            return Err(VerifyError::InvalidCommitment);
+        }
+        Ok(())
+    }
+}
+
 /// A proof of the merkle commitment.
 #[derive(Getters, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
@@ -603,26 +1276,77 @@ pub struct MerkleProof {
 
     /// Merkle proof path consisting of node hashing partners.
     #[getter(skip)]
-    path: Confined<Vec<MerkleNode>, 0, 32>,
+    path: MerklePath,
 }
 
+impl StrictSerialize for MerkleProof {}
+impl StrictDeserialize for MerkleProof {}
+
 impl Proof for MerkleProof {}
 
 impl MerkleProof {
     /// Computes the depth of the merkle tree.
-    pub fn depth(&self) -> u8 { self.path.len() as u8 }
+    pub fn depth(&self) -> u8 { self.path.depth() }
 
     /// Computes the width of the merkle tree.
     pub fn width(&self) -> u32 { 2u32.pow(self.depth() as u32) }
 
     /// Converts the proof into inner merkle path representation
-    pub fn into_path(self) -> Confined<Vec<MerkleNode>, 0, 32> { self.path }
+    pub fn into_path(self) -> MerklePath { self.path }
 
     /// Constructs the proof into inner merkle path representation
-    pub fn to_path(&self) -> Confined<Vec<MerkleNode>, 0, 32> { self.path.clone() }
+    pub fn to_path(&self) -> MerklePath { self.path.clone() }
 
     /// Returns inner merkle path representation
-    pub fn as_path(&self) -> &[MerkleNode] { &self.path }
+    pub fn as_path(&self) -> &[MerkleNode] { self.path.as_slice() }
+
+    /// Checks that the proof for the `protocol_id` is encoded canonically.
+    /// Tree depth and the path nodes are committed to, so only the cofactor
+    /// may be malleated; the canonical cofactor is the smallest one placing
+    /// the `protocol_id` at the proof position. The cofactor is committed to
+    /// by the [`Self::convolve_extended`], which is not preserved by the
+    /// canonicalization.
+    pub fn check_canonical(&self, protocol_id: ProtocolId) -> Result<(), NonCanonical> {
+        let canonical = canonical_cofactor(self.cofactor, &[(self.pos, protocol_id)], self.width());
+        if canonical != self.cofactor {
+            return Err(NonCanonical::Cofactor {
+                actual: self.cofactor,
+                canonical,
+            });
+        }
+        Ok(())
+    }
+
+    /// Converts the proof for the `protocol_id` into its canonical encoding
+    /// (see [`Self::check_canonical`]), preserving the commitment it convolves
+    /// into.
+    ///
+    /// # Returns
+    ///
+    /// Whether the encoding was changed.
+    pub fn canonicalize(&mut self, protocol_id: ProtocolId) -> bool {
+        let canonical = canonical_cofactor(self.cofactor, &[(self.pos, protocol_id)], self.width());
+        let changed = canonical != self.cofactor;
+        self.cofactor = canonical;
+        changed
+    }
+
+    /// Enumerates encodings of the proof for the `protocol_id` which differ
+    /// from it only by the cofactor and convolve into the same commitment.
+    pub fn alternative_encodings(
+        &self,
+        protocol_id: ProtocolId,
+    ) -> impl Iterator<Item = MerkleProof> + '_ {
+        let cofactors =
+            equivalent_cofactors(&[(self.pos, protocol_id)], self.width()).collect::<Vec<_>>();
+        cofactors
+            .into_iter()
+            .filter(move |cofactor| *cofactor != self.cofactor)
+            .map(move |cofactor| MerkleProof {
+                cofactor,
+                ..self.clone()
+            })
+    }
 
     /// Convolves the proof with the `message` under the given `protocol_id`,
     /// producing [`Commitment`].
@@ -634,14 +1358,86 @@ impl MerkleProof {
         let block = MerkleBlock::with(self, protocol_id, message)?;
         Ok(block.commitment_id())
     }
+
+    /// Convolves the proof with the `message` under the given `protocol_id`,
+    /// producing [`Commitment`], like [`Self::convolve`], but without any heap
+    /// allocations. This allows to run the verification on constrained
+    /// hardware, like signing devices.
+    pub fn convolve_in_place(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+    ) -> Result<Commitment, InvalidProof> {
+        let depth = self.depth();
+        let width = self.width();
+        let expected = protocol_id_pos(protocol_id, self.cofactor, width);
+        if expected != self.pos {
+            return Err(InvalidProof {
+                protocol_id,
+                expected,
+                actual: self.pos,
+                width,
+            });
+        }
+
+        let mut node = Leaf::inhabited(protocol_id, message).commitment_id();
+        // The path goes from the root towards the leaf, so we iterate it in
+        // reverse order
+        for (index, sibling) in self.path.iter().enumerate().rev() {
+            let height = depth as u32 - index as u32 - 1;
+            let (left, right) = if (self.pos >> height) & 1 == 1 {
+                (*sibling, node)
+            } else {
+                (node, *sibling)
+            };
+            node = MerkleNode::branches(
+                MERKLE_LNPBP4_TAG.to_be_bytes(),
+                u5::with(index as u8),
+                width,
+                left,
+                right,
+            );
+        }
+
+        Ok(Commitment::from_root(node))
+    }
+
+    /// Verifies that the proof of the `message` under the given `protocol_id`
+    /// matches the `commitment` without any heap allocations (see
+    /// [`Self::convolve_in_place`]).
+    pub fn verify_in_place(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        commitment: Commitment,
+    ) -> bool {
+        self.convolve_in_place(protocol_id, message) == Ok(commitment)
+    }
+
+    /// Convolves the proof with the `message` under the given `protocol_id`,
+    /// producing extended commitment to the tree with `count` messages (see
+    /// [`Commitment::extend`]).
+    pub fn convolve_extended(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        count: u32,
+    ) -> Result<Commitment, InvalidProof> {
+        let block = MerkleBlock::with(self, protocol_id, message)?;
+        Ok(block.extended_commitment(count))
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use amplify::confinement::U16;
+
     use super::*;
     use crate::mpc::tree::test_helpers::{
         make_det_messages, make_random_messages, make_random_tree,
     };
+    use crate::mpc::MultiSource;
+    use crate::TryCommitVerify;
 
     #[test]
     fn entropy() {
@@ -702,6 +1498,334 @@ mod test {
         }
     }
 
+    #[test]
+    fn conceal_borrowed() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+        let root = tree.root();
+        assert_eq!(block.conceal(), root);
+
+        for (pid, msg) in &msgs {
+            let proof = block.to_merkle_proof(*pid).unwrap();
+            assert_eq!(proof.convolve(*pid, *msg), Ok(tree.commitment_id()));
+
+            let mut partial = block.clone();
+            partial.conceal_except([*pid]).unwrap();
+            assert_eq!(partial.conceal(), root);
+            assert_eq!(partial.to_merkle_proof(*pid), Ok(proof.clone()));
+            assert_eq!(partial.into_merkle_proof(*pid), Ok(proof));
+        }
+
+        let unknown = ProtocolId::from([0xFFu8; 32]);
+        assert_eq!(block.to_merkle_proof(unknown), Err(LeafNotKnown(unknown)));
+    }
+
+    #[test]
+    fn verify_dyn() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let commitment = tree.commitment_id().to_byte_array();
+        let (pid, msg) = msgs.into_iter().next().unwrap();
+        let proof = MerkleBlock::from(&tree).to_merkle_proof(pid).unwrap();
+
+        let proofs: Vec<Box<dyn VerifiableProof>> = vec![
+            Box::new(tree.clone()),
+            Box::new(MerkleBlock::from(&tree)),
+            Box::new(MerkleBlock::with(&proof, pid, msg).unwrap()),
+        ];
+        for proof in proofs {
+            assert_eq!(proof.verify_dyn(&commitment), Ok(()));
+            assert_eq!(proof.verify_dyn(&[0u8; 32]), Err(VerifyError::InvalidCommitment));
+        }
+    }
+
+    #[test]
+    fn precommitment() {
+        let msgs = make_random_messages(9);
+        let src = MultiSource {
+            min_depth: u5::ZERO,
+            messages: Confined::try_from_iter(msgs.iter().map(|(a, b)| (*a, *b))).unwrap(),
+            static_entropy: None,
+        };
+        let precommitment = src.precommitment();
+        let tree = MerkleTree::try_commit(&src).unwrap();
+        assert_eq!(tree.precommitment(), precommitment);
+
+        let mut block = MerkleBlock::from(&tree);
+        assert_eq!(block.verify_precommitment(precommitment), Ok(()));
+
+        let other = make_random_messages(9);
+        let other = MultiSource {
+            messages: Confined::try_from_iter(other.into_iter()).unwrap(),
+            ..default!()
+        };
+        assert_eq!(
+            block.verify_precommitment(other.precommitment()),
+            Err(PrecommitmentError::Mismatch)
+        );
+
+        let leaves = block
+            .cross_section
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, node)| match node {
+                TreeNode::CommitmentLeaf { protocol_id, .. } => Some((pos, *protocol_id)),
+                TreeNode::ConcealedNode { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        let (pos1, pid1) = leaves[0];
+        let (pos2, pid2) = leaves[1];
+
+        let mut swapped = block.clone();
+        let mut nodes = swapped.cross_section.to_vec();
+        nodes.swap(pos1, pos2);
+        swapped.cross_section = Confined::try_from(nodes).unwrap();
+        assert_eq!(
+            swapped.verify_precommitment(precommitment),
+            Err(PrecommitmentError::Misplaced {
+                protocol_id: pid2,
+                expected: pos2 as u32,
+                actual: pos1 as u32
+            })
+        );
+
+        let mut duplicated = block.clone();
+        let mut nodes = duplicated.cross_section.to_vec();
+        nodes[pos2] = nodes[pos1];
+        duplicated.cross_section = Confined::try_from(nodes).unwrap();
+        assert_eq!(
+            duplicated.verify_precommitment(precommitment),
+            Err(PrecommitmentError::DuplicateProtocol(pid1))
+        );
+
+        let (pid, _) = msgs.first_key_value().unwrap();
+        block.conceal_except([*pid]).unwrap();
+        assert_eq!(block.verify_precommitment(precommitment), Err(PrecommitmentError::NoEntropy));
+    }
+
+    #[test]
+    fn prune() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let mut block = MerkleBlock::from(&tree);
+        let kept = msgs.keys().take(3).copied().collect::<BTreeSet<_>>();
+
+        block.prune(|pid| kept.contains(&pid));
+        assert_eq!(block.commitment_id(), tree.commitment_id());
+        assert_eq!(block.entropy, None);
+
+        let known = block.to_known_message_map();
+        assert_eq!(known.keys().copied().collect::<BTreeSet<_>>(), kept);
+        for pid in &kept {
+            let proof = block.to_merkle_proof(*pid).unwrap();
+            assert_eq!(proof.convolve(*pid, msgs[pid]).unwrap(), tree.commitment_id());
+        }
+
+        block.prune(|_| false);
+        assert!(block.to_known_message_map().is_empty());
+        assert_eq!(block.commitment_id(), tree.commitment_id());
+    }
+
+    #[test]
+    fn verify_resumable() {
+        let msgs = make_random_messages(17);
+        let tree = make_random_tree(&msgs);
+        let commitment = tree.commitment_id();
+        let mut block = MerkleBlock::from(&tree);
+        let pids = msgs.keys().take(2).copied().collect::<Vec<_>>();
+
+        for _ in 0..2 {
+            for budget in [1, 3, 1000] {
+                let mut checkpoint = None;
+                let mut steps = 0;
+                loop {
+                    steps += 1;
+                    match block
+                        .verify_resumable(commitment, checkpoint.take(), budget)
+                        .unwrap()
+                    {
+                        VerifyProgress::Suspended(c) => {
+                            let data = c.to_strict_serialized::<U16>().unwrap();
+                            checkpoint = Some(
+                                VerifyCheckpoint::from_strict_serialized::<U16>(data).unwrap(),
+                            );
+                        }
+                        VerifyProgress::Verified => break,
+                    }
+                }
+                let len = block.cross_section.len() as u32;
+                assert_eq!(steps, (len + budget - 1) / budget);
+            }
+            block.conceal_except(&pids).unwrap();
+        }
+
+        let other = make_random_tree(&make_random_messages(3)).commitment_id();
+        assert_eq!(
+            block.verify_resumable(other, None, 1000),
+            Err(ResumableError::CommitmentMismatch)
+        );
+        let VerifyProgress::Suspended(checkpoint) =
+            block.verify_resumable(commitment, None, 1).unwrap()
+        else {
+            panic!("verification must be suspended");
+        };
+        assert_eq!(
+            block.verify_resumable(other, Some(checkpoint.clone()), 1),
+            Err(ResumableError::CheckpointMismatch)
+        );
+        let other_block = MerkleBlock::from(&tree);
+        assert_eq!(
+            other_block.verify_resumable(commitment, Some(checkpoint), 1),
+            Err(ResumableError::CheckpointMismatch)
+        );
+
+        // Same messages with different placeholders give a block of the same
+        // shape
+        let full_block = MerkleBlock::from(&tree);
+        let twin_tree = make_random_tree(&msgs);
+        let twin_block = MerkleBlock::from(&twin_tree);
+        assert_eq!(twin_block.depth, full_block.depth);
+        let len = full_block.cross_section.len() as u32;
+        assert_eq!(twin_block.cross_section.len() as u32, len);
+        let VerifyProgress::Suspended(checkpoint) = twin_block
+            .verify_resumable(twin_tree.commitment_id(), None, len - 1)
+            .unwrap()
+        else {
+            panic!("verification must be suspended");
+        };
+        assert_eq!(
+            full_block.verify_resumable(
+                commitment,
+                Some(VerifyCheckpoint {
+                    commitment,
+                    ..checkpoint
+                }),
+                1
+            ),
+            Err(ResumableError::CheckpointMismatch)
+        );
+
+        let mut checkpoint = None;
+        let mut steps = 0;
+        while let VerifyProgress::Suspended(c) = block
+            .verify_resumable(commitment, checkpoint.take(), 0)
+            .unwrap()
+        {
+            checkpoint = Some(c);
+            steps += 1;
+        }
+        assert_eq!(steps + 1, block.cross_section.len());
+    }
+
+    #[test]
+    fn convolve_in_place() {
+        for count in [1, 2, 7, 20] {
+            let msgs = make_random_messages(count);
+            let tree = make_random_tree(&msgs);
+            let commitment = tree.commitment_id();
+            let block = MerkleBlock::from(&tree);
+            for (pid, msg) in &msgs {
+                let proof = block.to_merkle_proof(*pid).unwrap();
+                assert_eq!(proof.convolve_in_place(*pid, *msg), proof.convolve(*pid, *msg));
+                assert!(proof.verify_in_place(*pid, *msg, commitment));
+                assert!(!proof.verify_in_place(*pid, Message::from([0xFFu8; 32]), commitment));
+            }
+        }
+    }
+
+    #[test]
+    fn extended_commitment() {
+        let msgs = make_random_messages(7);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+        let ext = tree.extended_commitment();
+        assert_ne!(ext, tree.commitment_id());
+        assert_eq!(block.extended_commitment(7), ext);
+        assert_ne!(block.extended_commitment(6), ext);
+
+        let (pid, msg) = msgs.first_key_value().unwrap();
+        let proof = block.to_merkle_proof(*pid).unwrap();
+        assert_eq!(proof.convolve_extended(*pid, *msg, 7), Ok(ext));
+        assert_eq!(tree.commitment_id().extend(tree.depth(), tree.cofactor, 7), ext);
+        assert_ne!(
+            tree.commitment_id()
+                .extend(tree.depth(), tree.cofactor.wrapping_add(1), 7),
+            ext
+        );
+    }
+
+    #[test]
+    fn canonicalize() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let protocols = msgs.keys().copied().collect::<Vec<_>>();
+
+        let mut block = MerkleBlock::from(&tree);
+        assert_eq!(block.check_canonical(), Ok(()));
+        block.entropy = None;
+        assert!(matches!(block.check_canonical(), Err(NonCanonical::RedundantConcealed(_))));
+        let mut expected = block.clone();
+        expected.conceal_except(&protocols).unwrap();
+        assert!(block.canonicalize());
+        assert_eq!(block, expected);
+        assert_eq!(block.check_canonical(), Ok(()));
+        assert_eq!(block.commitment_id(), tree.commitment_id());
+
+        // Without revealed leaves any cofactor is canonicalized to zero, which
+        // keeps the commitment id but changes the extended commitment
+        let mut concealed = block.clone();
+        concealed.prune(|_| false);
+        concealed.cofactor = 5;
+        let extended = concealed.extended_commitment(1);
+        assert!(concealed.canonicalize());
+        assert_eq!(concealed.cofactor, 0);
+        assert_eq!(concealed.commitment_id(), tree.commitment_id());
+        assert_ne!(concealed.extended_commitment(1), extended);
+        assert_eq!(concealed.alternative_encodings().count(), u16::MAX as usize);
+
+        for alternative in block.alternative_encodings() {
+            assert!(alternative.cofactor > block.cofactor);
+            assert_eq!(alternative.commitment_id(), tree.commitment_id());
+            assert_eq!(
+                alternative.check_canonical(),
+                Err(NonCanonical::Cofactor {
+                    actual: alternative.cofactor,
+                    canonical: block.cofactor
+                })
+            );
+        }
+
+        let protocol_id = protocols[0];
+        let message = msgs[&protocol_id];
+        let mut proof = block.to_merkle_proof(protocol_id).unwrap();
+        let width = proof.width();
+        let cofactor = proof.cofactor;
+        proof.canonicalize(protocol_id);
+        assert!(!proof.canonicalize(protocol_id));
+        assert_eq!(proof.check_canonical(protocol_id), Ok(()));
+        assert!(proof.cofactor <= cofactor);
+        assert_eq!(proof.convolve(protocol_id, message), Ok(tree.commitment_id()));
+        for smaller in 0..proof.cofactor {
+            assert_ne!(protocol_id_pos(protocol_id, smaller, width), proof.pos);
+        }
+
+        let alternatives = proof.alternative_encodings(protocol_id).collect::<Vec<_>>();
+        assert_eq!(alternatives.iter().any(|alt| alt.cofactor == u16::MAX), proof.pos == 0);
+        for mut alternative in alternatives {
+            assert_eq!(alternative.convolve(protocol_id, message), Ok(tree.commitment_id()));
+            assert_eq!(
+                alternative.check_canonical(protocol_id),
+                Err(NonCanonical::Cofactor {
+                    actual: alternative.cofactor,
+                    canonical: proof.cofactor
+                })
+            );
+            assert!(alternative.canonicalize(protocol_id));
+            assert_eq!(alternative, proof);
+        }
+    }
+
     #[test]
     fn merge_reveal() {
         for size in 2..9 {
@@ -0,0 +1,319 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Off-chain checkpoints chaining successive LNPBP-4 commitments.
+//!
+//! Protocols updating their state more often than they anchor it may chain
+//! the commitments of each update into checkpoints, where each checkpoint
+//! commits to the id of the previous one. Only the id of the last checkpoint
+//! gets committed into the on-chain LNPBP-4 tree, anchoring the whole chain.
+
+use amplify::confinement::{self, MediumVec};
+use amplify::Bytes32;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::mpc::{Commitment, InvalidProof, MerkleProof, Message, ProtocolId};
+use crate::{
+    strategies, CommitStrategy, CommitmentId, ErrorCode, ErrorCodes, VerifiableProof, VerifyError,
+    LIB_NAME_COMMIT_VERIFY,
+};
+
+/// Errors verifying [`CheckpointChain`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum CheckpointError {
+    /// checkpoint chain is empty.
+    Empty,
+
+    /// first checkpoint in the chain is not a genesis checkpoint.
+    NoGenesis,
+
+    /// checkpoint #{0} doesn't commit to the previous checkpoint.
+    BrokenLink(usize),
+
+    /// checkpoint #{0} has invalid sequence number.
+    InvalidSequence(usize),
+
+    #[from]
+    #[display(inner)]
+    InvalidProof(InvalidProof),
+
+    /// chain tip is anchored in {actual} instead of the expected commitment
+    /// {expected}.
+    AnchorMismatch {
+        expected: Commitment,
+        actual: Commitment,
+    },
+}
+
+impl ErrorCode for CheckpointError {
+    fn code(&self) -> &'static str {
+        match self {
+            CheckpointError::Empty => "mpc.checkpoint.empty",
+            CheckpointError::NoGenesis => "mpc.checkpoint.no-genesis",
+            CheckpointError::BrokenLink(_) => "mpc.checkpoint.broken-link",
+            CheckpointError::InvalidSequence(_) => "mpc.checkpoint.invalid-sequence",
+            CheckpointError::InvalidProof(err) => err.code(),
+            CheckpointError::AnchorMismatch { .. } => "mpc.checkpoint.anchor-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            CheckpointError::Empty | CheckpointError::NoGenesis => vec![],
+            CheckpointError::BrokenLink(index) | CheckpointError::InvalidSequence(index) => {
+                vec![("index", index.to_string())]
+            }
+            CheckpointError::InvalidProof(err) => err.params(),
+            CheckpointError::AnchorMismatch { expected, actual } => {
+                vec![("actual", actual.to_string()), ("expected", expected.to_string())]
+            }
+        }
+    }
+}
+
+impl ErrorCodes for CheckpointError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.checkpoint.empty",
+        "mpc.checkpoint.no-genesis",
+        "mpc.checkpoint.broken-link",
+        "mpc.checkpoint.invalid-sequence",
+        "mpc.checkpoint.anchor-mismatch",
+        "mpc.invalid-proof",
+    ];
+}
+
+/// Identifier of a [`Checkpoint`].
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Default)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct CheckpointId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitStrategy for CheckpointId {
+    type Strategy = strategies::Strict;
+}
+
+impl From<CheckpointId> for Message {
+    fn from(id: CheckpointId) -> Self { Message::from(id.0) }
+}
+
+/// Off-chain checkpoint committing to an LNPBP-4 commitment and the previous
+/// checkpoint.
+#[derive(Getters, Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[derive(CommitEncode)]
+#[commit_encode(crate = crate, strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Checkpoint {
+    /// Id of the previous checkpoint; zero for the genesis checkpoint.
+    #[getter(as_copy)]
+    prev: CheckpointId,
+
+    /// Sequence number of the checkpoint in the chain, starting from zero.
+    #[getter(as_copy)]
+    seq: u64,
+
+    /// Commitment made by the checkpoint.
+    #[getter(as_copy)]
+    commitment: Commitment,
+}
+
+impl CommitmentId for Checkpoint {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:chkp:v01#23A";
+    type Id = CheckpointId;
+}
+
+impl Checkpoint {
+    /// Constructs the first checkpoint of a chain.
+    pub fn genesis(commitment: Commitment) -> Self {
+        Checkpoint {
+            prev: CheckpointId::default(),
+            seq: 0,
+            commitment,
+        }
+    }
+
+    /// Constructs the checkpoint following the current one.
+    pub fn next(&self, commitment: Commitment) -> Self {
+        Checkpoint {
+            prev: self.commitment_id(),
+            seq: self.seq + 1,
+            commitment,
+        }
+    }
+
+    /// Detects whether the checkpoint is the first in a chain.
+    pub fn is_genesis(&self) -> bool { self.seq == 0 && self.prev == CheckpointId::default() }
+}
+
+/// Chain of off-chain checkpoints.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct CheckpointChain {
+    /// Checkpoints, starting from the genesis.
+    checkpoints: MediumVec<Checkpoint>,
+}
+
+impl StrictSerialize for CheckpointChain {}
+impl StrictDeserialize for CheckpointChain {}
+
+impl CheckpointChain {
+    /// Constructs chain with the genesis checkpoint committing to
+    /// `commitment`.
+    pub fn with(commitment: Commitment) -> Self {
+        CheckpointChain {
+            checkpoints: medium_vec![Checkpoint::genesis(commitment)],
+        }
+    }
+
+    /// Returns the last checkpoint of the chain.
+    pub fn tip(&self) -> Option<&Checkpoint> { self.checkpoints.last() }
+
+    /// Returns the id of the last checkpoint of the chain, which has to be
+    /// committed into the anchor.
+    pub fn tip_id(&self) -> Option<CheckpointId> { self.tip().map(Checkpoint::commitment_id) }
+
+    /// Appends new checkpoint committing to `commitment`, returning its id.
+    pub fn push(&mut self, commitment: Commitment) -> Result<CheckpointId, confinement::Error> {
+        let checkpoint = match self.tip() {
+            Some(tip) => tip.next(commitment),
+            None => Checkpoint::genesis(commitment),
+        };
+        self.checkpoints.push(checkpoint)?;
+        Ok(checkpoint.commitment_id())
+    }
+
+    /// Verifies that the checkpoints form a chain starting from the genesis.
+    pub fn verify(&self) -> Result<CheckpointId, CheckpointError> {
+        let mut iter = self.checkpoints.iter();
+        let genesis = iter.next().ok_or(CheckpointError::Empty)?;
+        if !genesis.is_genesis() {
+            return Err(CheckpointError::NoGenesis);
+        }
+        let mut prev = genesis;
+        for (index, checkpoint) in iter.enumerate().map(|(index, c)| (index + 1, c)) {
+            if checkpoint.prev != prev.commitment_id() {
+                return Err(CheckpointError::BrokenLink(index));
+            }
+            if checkpoint.seq != prev.seq + 1 {
+                return Err(CheckpointError::InvalidSequence(index));
+            }
+            prev = checkpoint;
+        }
+        Ok(prev.commitment_id())
+    }
+
+    /// Verifies the chain and its anchoring: the id of the chain tip must be
+    /// committed under `protocol_id` into the on-chain `anchor` commitment
+    /// with the `proof`.
+    pub fn verify_anchored(
+        &self,
+        protocol_id: ProtocolId,
+        proof: &MerkleProof,
+        anchor: Commitment,
+    ) -> Result<(), CheckpointError> {
+        let tip = self.verify()?;
+        let actual = proof.convolve(protocol_id, tip.into())?;
+        if actual != anchor {
+            return Err(CheckpointError::AnchorMismatch {
+                expected: anchor,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl VerifiableProof for CheckpointChain {
+    /// Verifies that the chain is valid and the `commitment` is the id of its
+    /// last checkpoint.
+    fn verify_dyn(&self, commitment: &[u8; 32]) -> Result<(), VerifyError> {
+        let tip = self.verify().map_err(|_| VerifyError::InvalidMessage)?;
+        if tip != CheckpointId::from(*commitment) {
+            return Err(VerifyError::InvalidCommitment);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::MerkleBlock;
+
+    #[test]
+    fn checkpoint_chain() {
+        let mut chain = CheckpointChain::with(Commitment::from([1u8; 32]));
+        for no in 2..6u8 {
+            chain.push(Commitment::from([no; 32])).unwrap();
+        }
+        assert_eq!(chain.checkpoints().len(), 5);
+        assert_eq!(chain.verify(), Ok(chain.tip_id().unwrap()));
+
+        let mut msgs = make_random_messages(7);
+        let protocol_id = *msgs.keys().next().unwrap();
+        msgs.insert(protocol_id, chain.tip_id().unwrap().into());
+        let tree = make_random_tree(&msgs);
+        let proof = MerkleBlock::from(&tree)
+            .to_merkle_proof(protocol_id)
+            .unwrap();
+        let anchor = tree.commitment_id();
+        assert_eq!(chain.verify_anchored(protocol_id, &proof, anchor), Ok(()));
+        assert!(matches!(
+            chain.verify_anchored(protocol_id, &proof, Commitment::from([0u8; 32])),
+            Err(CheckpointError::AnchorMismatch { .. })
+        ));
+
+        let mut broken = chain.clone();
+        broken.checkpoints[2].commitment = Commitment::from([0xFF; 32]);
+        assert_eq!(broken.verify(), Err(CheckpointError::BrokenLink(3)));
+
+        let mut broken = chain.clone();
+        broken.checkpoints[4].seq = 7;
+        assert_eq!(broken.verify(), Err(CheckpointError::InvalidSequence(4)));
+
+        let mut broken = chain;
+        broken.checkpoints[0].seq = 1;
+        assert_eq!(broken.verify(), Err(CheckpointError::NoGenesis));
+    }
+}
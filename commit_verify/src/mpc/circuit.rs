@@ -0,0 +1,176 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of LNPBP-4 inclusion proofs as witnesses for zero-knowledge circuits.
+//!
+//! A circuit verifying the inclusion has to re-compute the tree root from the
+//! leaf and the proof path. Each step of the path computes SHA-256 hash of
+//!
+//! ```text
+//! MERKLE_LNPBP4_TAG (16 bytes, big-endian)
+//! || depth (1 byte)
+//! || width (32 bytes, little-endian u256)
+//! || 0x02 (branching marker)
+//! || left || right (32 bytes each)
+//! ```
+//!
+//! where `depth` is the depth of the produced node (from
+//! [`CircuitWitness::depths`]), `width` is [`CircuitWitness::width`], and the
+//! current node is `right` if [`CircuitWitness::directions`] is set for the
+//! step, and `left` otherwise. The final node is the tree root, which is
+//! converted into [`super::Commitment`] with a tagged hash of
+//! [`super::MerkleBlock`] commitment id tag.
+
+use crate::mpc::MerkleProof;
+
+/// Witness for zero-knowledge circuits verifying [`MerkleProof`].
+///
+/// All arrays have fixed size of [`CircuitWitness::MAX_DEPTH`] and are ordered
+/// from the leaf towards the root: element `i` corresponds to the `i`-th
+/// hashing step starting from the leaf. Elements after [`Self::depth`] steps
+/// are zero-filled.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CircuitWitness {
+    /// Depth of the tree, which is the number of the hashing steps.
+    pub depth: u8,
+    /// Width of the tree, committed into each of the branch nodes.
+    pub width: u32,
+    /// Position of the leaf in the tree.
+    pub pos: u32,
+    /// Cofactor of the tree, required to check the leaf position.
+    pub cofactor: u16,
+    /// Depths of the nodes produced by each of the hashing steps.
+    pub depths: [u8; 32],
+    /// Whether the current node is the right one at each of the hashing steps.
+    pub directions: [bool; 32],
+    /// Sibling nodes hashed together with the current node at each of the
+    /// hashing steps.
+    pub siblings: [[u8; 32]; 32],
+}
+
+impl CircuitWitness {
+    /// Maximal depth of the tree and size of the witness arrays.
+    pub const MAX_DEPTH: usize = 32;
+
+    /// Represents sibling nodes as field elements.
+    ///
+    /// Each node is split into two 128-bit limbs, the lower (from the first 16
+    /// bytes of the node, read as little-endian number) coming first. The
+    /// limbs fit into any prime field of at least 129 bits.
+    pub fn siblings_as_limbs(&self) -> [[u128; 2]; 32] {
+        let mut limbs = [[0u128; 2]; 32];
+        for (limb, sibling) in limbs.iter_mut().zip(&self.siblings) {
+            let mut lo = [0u8; 16];
+            let mut hi = [0u8; 16];
+            lo.copy_from_slice(&sibling[..16]);
+            hi.copy_from_slice(&sibling[16..]);
+            *limb = [u128::from_le_bytes(lo), u128::from_le_bytes(hi)];
+        }
+        limbs
+    }
+}
+
+impl MerkleProof {
+    /// Exports the proof as a witness for zero-knowledge circuits, in the
+    /// layout described in [`CircuitWitness`].
+    pub fn to_circuit_witness(&self) -> CircuitWitness {
+        let depth = self.depth();
+        let mut witness = CircuitWitness {
+            depth,
+            width: self.width(),
+            pos: self.pos(),
+            cofactor: self.cofactor(),
+            depths: [0u8; 32],
+            directions: [false; 32],
+            siblings: [[0u8; 32]; 32],
+        };
+        // The path goes from the root towards the leaf, so we iterate it in
+        // reverse order
+        for (step, (index, sibling)) in self.as_path().iter().enumerate().rev().enumerate() {
+            let height = depth as u32 - index as u32 - 1;
+            witness.depths[step] = index as u8;
+            witness.directions[step] = (self.pos() >> height) & 1 == 1;
+            witness.siblings[step] = sibling.to_byte_array();
+        }
+        witness
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::num::u256;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::merkle::MerkleNode;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::{Commitment, Leaf, MerkleBlock, MERKLE_LNPBP4_TAG};
+    use crate::CommitmentId;
+
+    #[test]
+    fn witness_layout() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+
+        for (protocol_id, message) in msgs {
+            let proof = block.to_merkle_proof(protocol_id).unwrap();
+            let witness = proof.to_circuit_witness();
+            assert_eq!(witness.depth, proof.depth());
+
+            // Re-compute the root following the documented layout
+            let mut node = Leaf::inhabited(protocol_id, message)
+                .commitment_id()
+                .to_byte_array();
+            let steps = witness
+                .siblings
+                .iter()
+                .zip(witness.directions)
+                .zip(witness.depths)
+                .take(witness.depth as usize);
+            for ((sibling, is_right), depth) in steps {
+                let (left, right) = if is_right {
+                    (*sibling, node)
+                } else {
+                    (node, *sibling)
+                };
+                let mut engine = Sha256::default();
+                engine.update(MERKLE_LNPBP4_TAG.to_be_bytes());
+                engine.update([depth]);
+                engine.update(u256::from(witness.width).to_le_bytes());
+                engine.update([0x02]);
+                engine.update(left);
+                engine.update(right);
+                node = engine.finalize().into();
+            }
+            assert_eq!(MerkleNode::from(node), tree.root());
+            assert_eq!(
+                proof.convolve(protocol_id, message),
+                Ok(Commitment::from_root(MerkleNode::from(node)))
+            );
+
+            let limbs = witness.siblings_as_limbs();
+            assert!(limbs[witness.depth as usize..]
+                .iter()
+                .all(|limb| *limb == [0, 0]));
+        }
+    }
+}
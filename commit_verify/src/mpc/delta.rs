@@ -0,0 +1,241 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Incremental updates of the LNPBP-4 trees with message map deltas.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use amplify::confinement::Confined;
+
+use crate::mpc::limits::MAX_MESSAGES;
+use crate::mpc::{self, MerkleTree, Message, MessageMap, MultiSource, ProtocolId};
+use crate::{ErrorCode, ErrorCodes, TryCommitVerify};
+
+/// Errors applying [`MessageMapDelta`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DeltaError {
+    /// message for protocol {0} is removed or changed by the delta, but is not
+    /// present in the message map.
+    NotPresent(ProtocolId),
+
+    /// message for protocol {0} is added by the delta, but is already present
+    /// in the message map.
+    AlreadyPresent(ProtocolId),
+
+    #[from]
+    #[display(inner)]
+    Tree(mpc::Error),
+}
+
+impl ErrorCode for DeltaError {
+    fn code(&self) -> &'static str {
+        match self {
+            DeltaError::NotPresent(_) => "mpc.delta.not-present",
+            DeltaError::AlreadyPresent(_) => "mpc.delta.already-present",
+            DeltaError::Tree(err) => err.code(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            DeltaError::NotPresent(protocol_id) | DeltaError::AlreadyPresent(protocol_id) => {
+                vec![("protocol_id", protocol_id.to_string())]
+            }
+            DeltaError::Tree(err) => err.params(),
+        }
+    }
+}
+
+impl ErrorCodes for DeltaError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.delta.not-present",
+        "mpc.delta.already-present",
+        "mpc.empty",
+        "mpc.too-many-messages",
+        "mpc.cant-fit",
+    ];
+}
+
+/// The way [`MessageMapDelta`] was applied to a [`MerkleTree`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum DeltaApplication {
+    /// Only the slots of the affected protocols were updated.
+    InPlace,
+    /// The messages had to be re-placed (for instance, due to a collision of
+    /// the added protocols with the existing ones).
+    Rebuilt,
+}
+
+/// Difference between two message maps.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct MessageMapDelta {
+    /// Messages under protocols absent from the original map.
+    pub added: BTreeMap<ProtocolId, Message>,
+    /// Protocols removed from the original map.
+    pub removed: BTreeSet<ProtocolId>,
+    /// New messages for the protocols present in the original map.
+    pub changed: BTreeMap<ProtocolId, Message>,
+}
+
+impl MessageMapDelta {
+    /// Computes delta transforming message map `old` into `new`.
+    pub fn between(old: &MessageMap, new: &MessageMap) -> Self {
+        let mut delta = MessageMapDelta::default();
+        for (protocol_id, message) in new.iter() {
+            match old.get(protocol_id) {
+                None => {
+                    delta.added.insert(*protocol_id, *message);
+                }
+                Some(prev) if prev != message => {
+                    delta.changed.insert(*protocol_id, *message);
+                }
+                Some(_) => {}
+            }
+        }
+        delta.removed = old
+            .keys()
+            .filter(|protocol_id| !new.contains_key(protocol_id))
+            .copied()
+            .collect();
+        delta
+    }
+
+    /// Detects whether the delta doesn't change anything.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Applies the delta to the message map, leaving it unchanged on error.
+    pub fn apply_to(&self, messages: &mut MessageMap) -> Result<(), DeltaError> {
+        let mut updated = messages.to_unconfined();
+        for protocol_id in &self.removed {
+            updated
+                .remove(protocol_id)
+                .ok_or(DeltaError::NotPresent(*protocol_id))?;
+        }
+        for (protocol_id, message) in &self.changed {
+            *updated
+                .get_mut(protocol_id)
+                .ok_or(DeltaError::NotPresent(*protocol_id))? = *message;
+        }
+        for (protocol_id, message) in &self.added {
+            if updated.insert(*protocol_id, *message).is_some() {
+                return Err(DeltaError::AlreadyPresent(*protocol_id));
+            }
+        }
+        if updated.len() > MAX_MESSAGES {
+            return Err(mpc::Error::TooManyMessages(updated.len()).into());
+        }
+        *messages = Confined::try_from(updated).expect("message limit is checked above");
+        Ok(())
+    }
+}
+
+impl MerkleTree {
+    /// Applies delta to the messages of the tree, keeping its entropy.
+    ///
+    /// Only the slots of the affected protocols are updated, unless an added
+    /// protocol collides with an existing one, in which case all messages are
+    /// re-placed into a tree of the same or larger depth.
+    pub fn apply_delta(&mut self, delta: &MessageMapDelta) -> Result<DeltaApplication, DeltaError> {
+        let mut messages = self.messages.clone();
+        delta.apply_to(&mut messages)?;
+
+        let mut map = self.map.to_unconfined();
+        for protocol_id in &delta.removed {
+            map.remove(&self.protocol_id_pos(*protocol_id));
+        }
+        for (protocol_id, message) in &delta.changed {
+            map.insert(self.protocol_id_pos(*protocol_id), (*protocol_id, *message));
+        }
+        let in_place = delta.added.iter().all(|(protocol_id, message)| {
+            map.insert(self.protocol_id_pos(*protocol_id), (*protocol_id, *message))
+                .is_none()
+        });
+
+        if in_place {
+            self.messages = messages;
+            self.map = Confined::try_from(map).expect("message limit is checked by delta");
+            return Ok(DeltaApplication::InPlace);
+        }
+
+        let source = MultiSource {
+            min_depth: self.depth,
+            messages,
+            static_entropy: Some(self.entropy),
+        };
+        *self = MerkleTree::try_commit(&source)?;
+        Ok(DeltaApplication::Rebuilt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+
+    #[test]
+    fn delta_between() {
+        let msgs = make_random_messages(9);
+        let old = Confined::try_from(msgs.clone()).unwrap();
+        let mut ids = msgs.keys().copied();
+        let (changed, removed) = (ids.next().unwrap(), ids.next().unwrap());
+
+        let mut new = old.clone();
+        new.insert(changed, Message::from([0xAA; 32])).unwrap();
+        new.remove(&removed).unwrap();
+        new.insert(ProtocolId::from([0xFF; 32]), Message::from([0x01; 32]))
+            .unwrap();
+
+        let delta = MessageMapDelta::between(&old, &new);
+        assert_eq!(delta.added.len(), 1);
+        assert_eq!(delta.changed.len(), 1);
+        assert_eq!(delta.removed, bset![removed]);
+        assert!(MessageMapDelta::between(&old, &old).is_empty());
+
+        let mut applied = old.clone();
+        delta.apply_to(&mut applied).unwrap();
+        assert_eq!(applied, new);
+        assert_eq!(delta.apply_to(&mut applied), Err(DeltaError::NotPresent(removed)));
+        assert_eq!(applied, new);
+    }
+
+    #[test]
+    fn delta_tree() {
+        let msgs = make_random_messages(9);
+        let mut tree = make_random_tree(&msgs);
+        let entropy = tree.entropy();
+
+        for _ in 0..10 {
+            let mut target = tree.messages.clone();
+            let (protocol_id, message) = make_random_messages(1).into_iter().next().unwrap();
+            target.insert(protocol_id, message).unwrap();
+            let delta = MessageMapDelta::between(&tree.messages, &target);
+
+            tree.apply_delta(&delta).unwrap();
+            tree.validate().unwrap();
+            assert_eq!(tree.messages, target);
+            assert_eq!(tree.entropy(), entropy);
+        }
+        assert_eq!(tree.messages.len(), 19);
+    }
+}
@@ -0,0 +1,114 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of protocol message updates between two LNPBP-4 commitments.
+
+use crate::mpc::{MerkleBlock, Message, ProtocolId};
+
+/// Change of the message committed under some protocol between two LNPBP-4
+/// blocks (see [`compare_protocol`]).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ProtocolDiff {
+    /// The same message is committed in both blocks.
+    Unchanged(Message),
+
+    /// Different messages are committed in the blocks.
+    Changed {
+        /// Message committed in the first block.
+        from: Message,
+        /// Message committed in the second block.
+        to: Message,
+    },
+
+    /// Message is known only in the second block.
+    Added(Message),
+
+    /// Message is known only in the first block.
+    Removed(Message),
+
+    /// Message is not known in either of the blocks.
+    Absent,
+}
+
+impl ProtocolDiff {
+    /// Detects whether the message has changed, appeared or disappeared.
+    pub fn is_updated(&self) -> bool {
+        !matches!(self, ProtocolDiff::Unchanged(_) | ProtocolDiff::Absent)
+    }
+}
+
+/// Compares messages committed under the `protocol_id` in two LNPBP-4 blocks,
+/// for instance belonging to two consequent anchors of a state history.
+///
+/// NB: a message is known only if its leaf is revealed in the block, so a
+/// message absent from a block may be just concealed in it.
+pub fn compare_protocol(
+    block_a: &MerkleBlock,
+    block_b: &MerkleBlock,
+    protocol_id: ProtocolId,
+) -> ProtocolDiff {
+    match (block_a.known_message(protocol_id), block_b.known_message(protocol_id)) {
+        (Some(from), Some(to)) if from == to => ProtocolDiff::Unchanged(from),
+        (Some(from), Some(to)) => ProtocolDiff::Changed { from, to },
+        (None, Some(to)) => ProtocolDiff::Added(to),
+        (Some(from), None) => ProtocolDiff::Removed(from),
+        (None, None) => ProtocolDiff::Absent,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+
+    #[test]
+    fn protocol_diff() {
+        let mut msgs = make_random_messages(5);
+        let block_a = MerkleBlock::from(&make_random_tree(&msgs));
+        let mut ids = msgs.keys().copied();
+        let (same, changed, removed) =
+            (ids.next().unwrap(), ids.next().unwrap(), ids.next().unwrap());
+        let old = msgs[&changed];
+        msgs.remove(&removed);
+        msgs.insert(changed, Message::from([!old[0]; 32]));
+        let added = ProtocolId::from([0xFF; 32]);
+        msgs.insert(added, Message::from([0x01; 32]));
+        let block_b = MerkleBlock::from(&make_random_tree(&msgs));
+
+        assert_eq!(
+            compare_protocol(&block_a, &block_b, same),
+            ProtocolDiff::Unchanged(msgs[&same])
+        );
+        assert_eq!(compare_protocol(&block_a, &block_b, changed), ProtocolDiff::Changed {
+            from: old,
+            to: msgs[&changed]
+        });
+        assert!(matches!(compare_protocol(&block_a, &block_b, removed), ProtocolDiff::Removed(_)));
+        assert_eq!(
+            compare_protocol(&block_a, &block_b, added),
+            ProtocolDiff::Added(Message::from([0x01; 32]))
+        );
+        let unknown = ProtocolId::from([0xFE; 32]);
+        assert_eq!(compare_protocol(&block_a, &block_b, unknown), ProtocolDiff::Absent);
+        assert!(!compare_protocol(&block_a, &block_b, same).is_updated());
+        assert!(compare_protocol(&block_a, &block_b, added).is_updated());
+    }
+}
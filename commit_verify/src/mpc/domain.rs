@@ -0,0 +1,195 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key domains allowing to reuse LNPBP-4 trees for committing maps over
+//! arbitrary keys (usually 32-byte hashes).
+//!
+//! Each key domain maps its keys into [`ProtocolId`]s using its own tag, so
+//! keys from different domains never clash inside a tree, while the tree,
+//! cofactor and proof machinery stays the same.
+
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use amplify::confinement::{self, Confined, MediumOrdMap};
+use amplify::num::u5;
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::mpc::{
+    Commitment, Error, InvalidProof, LeafNotKnown, MerkleBlock, MerkleProof, MerkleTree, Message,
+    MultiSource, ProtocolId, MPC_MINIMAL_DEPTH,
+};
+use crate::TryCommitVerify;
+
+/// Maps the `key` into protocol id with a hash tagged with the key domain
+/// `tag`. Used by the key domains which need separation from the other
+/// domains.
+pub fn tagged_protocol_id(tag: [u8; 32], key: &[u8]) -> ProtocolId {
+    let mut engine = Sha256::from_tag(tag);
+    engine.input_raw(key);
+    engine.finish().into()
+}
+
+/// Domain of 32-byte keys which can be committed inside LNPBP-4 trees.
+pub trait KeyDomain {
+    /// Type of keys in the domain.
+    type Key: Copy + Ord + Hash + Debug + Borrow<[u8]>;
+
+    /// Maps the key into protocol id used for its placement in the tree.
+    ///
+    /// Domains sharing trees with other domains should use
+    /// [`tagged_protocol_id`] with their own tag.
+    fn protocol_id(key: Self::Key) -> ProtocolId;
+
+    /// Extracts merkle proof for the value under the `key` from the block.
+    fn prove(block: &MerkleBlock, key: Self::Key) -> Result<MerkleProof, LeafNotKnown> {
+        block.to_merkle_proof(Self::protocol_id(key))
+    }
+
+    /// Convolves the `proof` with the `value` under the `key`, producing
+    /// commitment.
+    fn convolve(
+        proof: &MerkleProof,
+        key: Self::Key,
+        value: Message,
+    ) -> Result<Commitment, InvalidProof> {
+        proof.convolve(Self::protocol_id(key), value)
+    }
+}
+
+/// Native LNPBP-4 key domain, where keys are protocol ids used without any
+/// transformation.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct Lnpbp4;
+
+impl KeyDomain for Lnpbp4 {
+    type Key = ProtocolId;
+
+    fn protocol_id(key: Self::Key) -> ProtocolId { key }
+}
+
+/// Source data for committing a map of keys from some [`KeyDomain`] into
+/// LNPBP-4 tree.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct KeyedSource<D: KeyDomain> {
+    /// Minimal depth of the created LNPBP-4 commitment tree
+    pub min_depth: u5,
+    /// Map of the values by their keys
+    pub values: MediumOrdMap<D::Key, Message>,
+    /// Static entropy for the placeholders of the tree; if not provided, the
+    /// entropy is generated randomly (requires `rand` feature).
+    pub static_entropy: Option<u64>,
+    #[doc(hidden)]
+    pub _phantom: PhantomData<D>,
+}
+
+impl<D: KeyDomain> Default for KeyedSource<D> {
+    fn default() -> Self {
+        KeyedSource {
+            min_depth: MPC_MINIMAL_DEPTH,
+            values: empty!(),
+            static_entropy: None,
+            _phantom: default!(),
+        }
+    }
+}
+
+impl<D: KeyDomain> KeyedSource<D> {
+    /// Constructs empty source.
+    pub fn new() -> Self { default!() }
+
+    /// Adds value under the `key`, returning previous value, if any.
+    pub fn insert(
+        &mut self,
+        key: D::Key,
+        value: Message,
+    ) -> Result<Option<Message>, confinement::Error> {
+        self.values.insert(key, value)
+    }
+
+    /// Converts the source into [`MultiSource`] by mapping keys into protocol
+    /// ids.
+    pub fn to_multi_source(&self) -> MultiSource {
+        MultiSource {
+            min_depth: self.min_depth,
+            messages: Confined::try_from_iter(
+                self.values
+                    .iter()
+                    .map(|(key, value)| (D::protocol_id(*key), *value)),
+            )
+            .expect("same collection size"),
+            static_entropy: self.static_entropy,
+        }
+    }
+
+    /// Commits to the source, producing LNPBP-4 tree.
+    pub fn try_commit(&self) -> Result<MerkleTree, Error> {
+        MerkleTree::try_commit(&self.to_multi_source())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::Bytes32;
+
+    use super::*;
+    use crate::CommitmentId;
+
+    struct TestDomain;
+
+    impl KeyDomain for TestDomain {
+        type Key = Bytes32;
+
+        fn protocol_id(key: Self::Key) -> ProtocolId {
+            tagged_protocol_id(*b"urn:lnpbp:test:keydomain:v01#23A", key.as_slice())
+        }
+    }
+
+    #[test]
+    fn keyed_source() {
+        let mut source = KeyedSource::<TestDomain>::new();
+        for no in 0u8..6 {
+            source
+                .insert(Bytes32::with_fill(no), Message::from([no; 32]))
+                .unwrap();
+        }
+        let tree = source.try_commit().unwrap();
+        let block = MerkleBlock::from(&tree);
+
+        let key = Bytes32::with_fill(3);
+        assert_ne!(TestDomain::protocol_id(key), ProtocolId::from(key.to_byte_array()));
+        let proof = TestDomain::prove(&block, key).unwrap();
+        assert_eq!(
+            TestDomain::convolve(&proof, key, Message::from([3u8; 32])),
+            Ok(tree.commitment_id())
+        );
+        assert_ne!(
+            TestDomain::convolve(&proof, key, Message::from([4u8; 32])),
+            Ok(tree.commitment_id())
+        );
+
+        let pid = ProtocolId::from([7u8; 32]);
+        assert_eq!(Lnpbp4::protocol_id(pid), pid);
+    }
+}
@@ -0,0 +1,155 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sources of entropy for the placeholders of LNPBP-4 trees.
+//!
+//! Besides the secret random entropy, the entropy may come from a public
+//! randomness beacon (like drand). In this case the beacon round is committed
+//! into the tree itself, under a reserved [`BeaconEntropy::protocol_id`],
+//! such that the provenance of the entropy can be audited later.
+
+use amplify::{confinement, Bytes32};
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::mpc::{MerkleTree, Message, MultiSource, Precommitment, ReservedSlot};
+use crate::{CommitEncode, CommitmentId, LIB_NAME_COMMIT_VERIFY};
+
+/// Source of entropy for the placeholders of LNPBP-4 trees.
+pub trait EntropySource {
+    /// Produces entropy for the tree committing to the set of messages with
+    /// the given pre-commitment.
+    fn entropy(&self, precommitment: Precommitment) -> u64;
+
+    /// Sets static entropy of the multi-message source using the current set
+    /// of its messages.
+    fn apply(&self, source: &mut MultiSource) {
+        source.static_entropy = Some(self.entropy(source.precommitment()));
+    }
+}
+
+/// Entropy derived from a round of a public randomness beacon.
+///
+/// The beacon round data are supplied by the caller; this library doesn't
+/// connect to the beacon and doesn't verify the beacon signatures.
+#[derive(Getters, Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[derive(CommitEncode)]
+#[commit_encode(crate = crate, strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BeaconEntropy {
+    /// Beacon round number.
+    #[getter(as_copy)]
+    round: u64,
+
+    /// Randomness published by the beacon for the round.
+    #[getter(as_copy)]
+    randomness: Bytes32,
+}
+
+impl CommitmentId for BeaconEntropy {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:bcon:v01#23A";
+    type Id = Message;
+}
+
+impl ReservedSlot for BeaconEntropy {
+    /// Commits the beacon round into the multi-message source and sets the
+    /// source entropy derived from the beacon and the resulting set of
+    /// messages.
+    fn commit_into(&self, source: &mut MultiSource) -> Result<(), confinement::Error> {
+        source
+            .messages
+            .insert(Self::protocol_id(), self.commitment_id())?;
+        self.apply(source);
+        Ok(())
+    }
+
+    /// Verifies that the tree commits to the beacon round and that its
+    /// placeholders use entropy derived from it.
+    fn verify(&self, tree: &MerkleTree) -> bool {
+        tree.messages.get(&Self::protocol_id()) == Some(&self.commitment_id()) &&
+            tree.entropy == self.entropy(tree.precommitment())
+    }
+}
+
+impl EntropySource for BeaconEntropy {
+    fn entropy(&self, precommitment: Precommitment) -> u64 {
+        let mut engine = Sha256::from_tag(Self::ENTROPY_TAG);
+        self.commit_encode(&mut engine);
+        precommitment.commit_encode(&mut engine);
+        let mut entropy = [0u8; 8];
+        entropy.copy_from_slice(&engine.finish()[..8]);
+        u64::from_le_bytes(entropy)
+    }
+}
+
+impl BeaconEntropy {
+    /// Tag of the hash deriving the entropy from the beacon round and the
+    /// pre-commitment to the tree messages, which is distinct from the tag of
+    /// the commitment to the round itself ([`CommitmentId::TAG`]).
+    pub const ENTROPY_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:bent:v01#23A";
+
+    /// Constructs entropy source from the beacon round number and its
+    /// published randomness.
+    pub fn new(round: u64, randomness: impl Into<Bytes32>) -> Self {
+        BeaconEntropy {
+            round,
+            randomness: randomness.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::make_random_messages;
+    use crate::TryCommitVerify;
+
+    #[test]
+    fn beacon_entropy() {
+        let beacon = BeaconEntropy::new(3_000_000, [0xA5; 32]);
+        let mut source = MultiSource::default();
+        for (protocol_id, message) in make_random_messages(5) {
+            source.messages.insert(protocol_id, message).unwrap();
+        }
+        beacon.commit_into(&mut source).unwrap();
+        let tree = MerkleTree::try_commit(&source).unwrap();
+        assert!(beacon.verify(&tree));
+        assert_eq!(tree, MerkleTree::try_commit(&source).unwrap());
+
+        let other = BeaconEntropy::new(3_000_001, [0xA5; 32]);
+        assert!(!other.verify(&tree));
+        assert_ne!(other.entropy(tree.precommitment()), tree.entropy());
+
+        let mut source = MultiSource::default();
+        for (protocol_id, message) in make_random_messages(5) {
+            source.messages.insert(protocol_id, message).unwrap();
+        }
+        beacon.apply(&mut source);
+        let unrecorded = MerkleTree::try_commit(&source).unwrap();
+        assert!(!beacon.verify(&unrecorded));
+    }
+}
@@ -0,0 +1,228 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Line-oriented textual representation of LNPBP-4 trees for test fixtures.
+//!
+//! The format is deterministic and designed to be reviewed as a plain text
+//! diff:
+//!
+//! ```text
+//! lnpbp4-tree v1
+//! depth 4
+//! cofactor 0
+//! entropy 3735928559
+//! message <protocol id hex> <message hex>
+//! ...
+//! ```
+//!
+//! Messages are listed in the order of their protocol ids; leaf positions are
+//! not stored and are re-computed from the protocol ids and cofactor when the
+//! tree is restored.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use amplify::confinement::Confined;
+use amplify::num::u5;
+
+use crate::mpc::{MerkleTree, Message, ProtocolId, TreeValidationError};
+use crate::{ErrorCode, ErrorCodes};
+
+const FIXTURE_HEADER: &str = "lnpbp4-tree v1";
+
+/// Errors parsing LNPBP-4 tree fixture.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum FixtureError {
+    /// fixture doesn't start with a supported `lnpbp4-tree` header.
+    Header,
+
+    /// fixture line {0} is malformed.
+    Line(usize),
+
+    /// fixture doesn't specify tree {0}.
+    Missing(&'static str),
+
+    /// fixture contains more messages than the tree may hold.
+    TooManyMessages,
+
+    /// fixture describes invalid tree: {0}
+    #[from]
+    Invalid(TreeValidationError),
+}
+
+impl ErrorCode for FixtureError {
+    fn code(&self) -> &'static str {
+        match self {
+            FixtureError::Header => "mpc.fixture.header",
+            FixtureError::Line(_) => "mpc.fixture.line",
+            FixtureError::Missing(_) => "mpc.fixture.missing",
+            FixtureError::TooManyMessages => "mpc.fixture.too-many-messages",
+            FixtureError::Invalid(_) => "mpc.fixture.invalid",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            FixtureError::Header | FixtureError::TooManyMessages => vec![],
+            FixtureError::Line(line) => vec![("line", line.to_string())],
+            FixtureError::Missing(field) => vec![("field", field.to_string())],
+            FixtureError::Invalid(err) => {
+                let mut params = vec![("cause", err.code().to_owned())];
+                params.extend(err.params());
+                params
+            }
+        }
+    }
+}
+
+impl ErrorCodes for FixtureError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.fixture.header",
+        "mpc.fixture.line",
+        "mpc.fixture.missing",
+        "mpc.fixture.too-many-messages",
+        "mpc.fixture.invalid",
+    ];
+}
+
+impl MerkleTree {
+    /// Produces deterministic line-oriented textual representation of the
+    /// tree, which can be restored with [`MerkleTree::from_fixture_string`].
+    pub fn to_fixture_string(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "{FIXTURE_HEADER}").expect("writing to string");
+        writeln!(s, "depth {}", self.depth).expect("writing to string");
+        writeln!(s, "cofactor {}", self.cofactor).expect("writing to string");
+        writeln!(s, "entropy {}", self.entropy).expect("writing to string");
+        for (protocol_id, message) in &self.messages {
+            writeln!(s, "message {protocol_id} {message}").expect("writing to string");
+        }
+        s
+    }
+
+    /// Restores tree from its textual representation produced by
+    /// [`MerkleTree::to_fixture_string`].
+    pub fn from_fixture_string(s: &str) -> Result<Self, FixtureError> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(no, line)| (no + 1, line.trim()));
+        if lines.next().map(|(_, line)| line) != Some(FIXTURE_HEADER) {
+            return Err(FixtureError::Header);
+        }
+
+        let mut depth = None;
+        let mut cofactor = None;
+        let mut entropy = None;
+        let mut messages = BTreeMap::new();
+        for (no, line) in lines.filter(|(_, line)| !line.is_empty()) {
+            let mut fields = line.split_whitespace();
+            let err = FixtureError::Line(no);
+            match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (Some("depth"), Some(val), None, None) if depth.is_none() => {
+                    let val = u8::from_str(val).map_err(|_| err)?;
+                    depth = Some(u5::try_from(val).map_err(|_| err)?);
+                }
+                (Some("cofactor"), Some(val), None, None) if cofactor.is_none() => {
+                    cofactor = Some(u16::from_str(val).map_err(|_| err)?);
+                }
+                (Some("entropy"), Some(val), None, None) if entropy.is_none() => {
+                    entropy = Some(u64::from_str(val).map_err(|_| err)?);
+                }
+                (Some("message"), Some(protocol_id), Some(message), None) => {
+                    let protocol_id = ProtocolId::from_str(protocol_id).map_err(|_| err)?;
+                    let message = Message::from_str(message).map_err(|_| err)?;
+                    if messages.insert(protocol_id, message).is_some() {
+                        return Err(err);
+                    }
+                }
+                _ => return Err(err),
+            }
+        }
+
+        let mut tree = MerkleTree {
+            depth: depth.ok_or(FixtureError::Missing("depth"))?,
+            entropy: entropy.ok_or(FixtureError::Missing("entropy"))?,
+            cofactor: cofactor.ok_or(FixtureError::Missing("cofactor"))?,
+            messages: Confined::try_from(messages).map_err(|_| FixtureError::TooManyMessages)?,
+            map: empty!(),
+        };
+        let map = tree
+            .messages
+            .iter()
+            .map(|(protocol_id, message)| {
+                (tree.protocol_id_pos(*protocol_id), (*protocol_id, *message))
+            })
+            .collect::<BTreeMap<_, _>>();
+        tree.map = Confined::try_from(map).map_err(|_| FixtureError::TooManyMessages)?;
+        tree.validate()?;
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+
+    #[test]
+    fn fixture_roundtrip() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let fixture = tree.to_fixture_string();
+        assert_eq!(fixture.lines().count(), 4 + 9);
+        assert!(fixture.starts_with(&format!(
+            "lnpbp4-tree v1\ndepth {}\ncofactor {}\nentropy {}\n",
+            tree.depth, tree.cofactor, tree.entropy
+        )));
+        assert_eq!(MerkleTree::from_fixture_string(&fixture), Ok(tree.clone()));
+        assert_eq!(MerkleTree::from_fixture_string(&fixture).unwrap().root(), tree.root());
+    }
+
+    #[test]
+    fn fixture_errors() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let fixture = tree.to_fixture_string();
+
+        assert_eq!(MerkleTree::from_fixture_string(""), Err(FixtureError::Header));
+        let unknown = fixture.replacen("lnpbp4-tree v1", "lnpbp4-tree v2", 1);
+        assert_eq!(MerkleTree::from_fixture_string(&unknown), Err(FixtureError::Header));
+
+        let no_entropy = fixture
+            .lines()
+            .filter(|line| !line.starts_with("entropy"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            MerkleTree::from_fixture_string(&no_entropy),
+            Err(FixtureError::Missing("entropy"))
+        );
+
+        let garbage = format!("{fixture}message 00\n");
+        assert_eq!(MerkleTree::from_fixture_string(&garbage), Err(FixtureError::Line(14)));
+
+        let duplicate = format!("{fixture}{}\n", fixture.lines().last().unwrap());
+        assert_eq!(MerkleTree::from_fixture_string(&duplicate), Err(FixtureError::Line(14)));
+    }
+}
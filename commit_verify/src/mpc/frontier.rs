@@ -0,0 +1,585 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Append-only logs of LNPBP-4 commitments.
+//!
+//! The log is a merkle tree of the same shape as defined in [RFC 6962], which
+//! allows appending commitments one at a time keeping just a logarithmic
+//! number of the subtree roots ([`Frontier`]), while [`CommitmentLog`]
+//! additionally keeps the leaves, being able to produce inclusion proofs
+//! against the current or any of the historical roots.
+//!
+//! [RFC 6962]: https://www.rfc-editor.org/rfc/rfc6962#section-2.1
+
+use amplify::confinement::{Confined, TinyVec};
+use amplify::{Bytes32, Wrapper};
+use sha2::Sha256;
+use strict_encoding::{DecodeError, StrictDecode, StrictDeserialize, StrictSerialize, TypedRead};
+
+use crate::digest::DigestExt;
+use crate::merkle::MerkleNode;
+use crate::mpc::Commitment;
+use crate::publication::{ProofOfPublication, PublicationMedium};
+use crate::rfc6962::{audit_path, root_from_path, split_point, tree_root};
+use crate::{VerifiableProof, VerifyError, LIB_NAME_COMMIT_VERIFY};
+
+pub const LOG_LEAF_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:log:leaf#23A";
+pub const LOG_NODE_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:log:node#23A";
+
+/// Computes hash of a commitment log leaf.
+pub fn log_leaf(commitment: Commitment) -> MerkleNode {
+    let mut engine = Sha256::from_tag(LOG_LEAF_TAG);
+    engine.input_raw(commitment.as_slice());
+    engine.finish().into()
+}
+
+/// Computes hash of a commitment log node from its two children.
+pub fn log_node(left: MerkleNode, right: MerkleNode) -> MerkleNode {
+    let mut engine = Sha256::from_tag(LOG_NODE_TAG);
+    engine.input_raw(left.as_slice());
+    engine.input_raw(right.as_slice());
+    engine.finish().into()
+}
+
+fn log_root(leaves: &[MerkleNode]) -> MerkleNode { tree_root(leaves, log_node) }
+
+/// Frontier of an append-only commitment log: roots of the perfect subtrees
+/// covering all of the log leaves, from the largest to the smallest.
+///
+/// The frontier always has exactly one peak for each bit set in the log
+/// length, which is checked when it is constructed with [`Frontier::with`] or
+/// deserialized.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase", try_from = "FrontierUnchecked")
+)]
+pub struct Frontier {
+    /// Number of the leaves in the log.
+    #[getter(as_copy)]
+    len: u64,
+
+    /// Roots of the perfect subtrees; one for each bit set in the log length.
+    #[getter(skip)]
+    peaks: TinyVec<MerkleNode>,
+}
+
+const PEAKS_MISMATCH: &str = "number of frontier peaks doesn't match the log length";
+
+impl StrictDecode for Frontier {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let len = u64::strict_decode(reader)?;
+        let peaks = TinyVec::<MerkleNode>::strict_decode(reader)?;
+        Frontier::with(len, peaks)
+            .ok_or_else(|| DecodeError::DataIntegrityError(PEAKS_MISMATCH.to_owned()))
+    }
+}
+
+impl StrictSerialize for Frontier {}
+impl StrictDeserialize for Frontier {}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+struct FrontierUnchecked {
+    len: u64,
+    peaks: TinyVec<MerkleNode>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<FrontierUnchecked> for Frontier {
+    type Error = &'static str;
+
+    fn try_from(frontier: FrontierUnchecked) -> Result<Self, Self::Error> {
+        Frontier::with(frontier.len, frontier.peaks).ok_or(PEAKS_MISMATCH)
+    }
+}
+
+impl Frontier {
+    /// Constructs frontier of an empty log.
+    pub fn new() -> Self { default!() }
+
+    /// Constructs frontier of a log with `len` leaves from its `peaks`,
+    /// returning `None` unless there is exactly one peak for each bit set in
+    /// the log length.
+    pub fn with(len: u64, peaks: TinyVec<MerkleNode>) -> Option<Self> {
+        if peaks.len() as u32 != len.count_ones() {
+            return None;
+        }
+        Some(Frontier { len, peaks })
+    }
+
+    /// Detects whether the log is empty.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Appends commitment to the log, returning its index.
+    pub fn append(&mut self, commitment: Commitment) -> u64 {
+        let mut node = log_leaf(commitment);
+        let mut len = self.len;
+        let mut peaks = self.peaks.release();
+        while len & 1 == 1 {
+            let left = peaks.pop().expect("number of peaks matches the log length");
+            node = log_node(left, node);
+            len >>= 1;
+        }
+        peaks.push(node);
+        self.peaks = Confined::try_from(peaks).expect("no more than 64 peaks");
+        self.len += 1;
+        self.len - 1
+    }
+
+    /// Computes the current root of the log, or `None` for an empty log.
+    pub fn root(&self) -> Option<MerkleNode> {
+        let mut peaks = self.peaks.iter().rev().copied();
+        let mut root = peaks.next()?;
+        for peak in peaks {
+            root = log_node(peak, root);
+        }
+        Some(root)
+    }
+}
+
+/// Append-only commitment log keeping all of its leaves.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct CommitmentLog {
+    frontier: Frontier,
+    leaves: Vec<MerkleNode>,
+}
+
+impl CommitmentLog {
+    /// Constructs empty log.
+    pub fn new() -> Self { default!() }
+
+    /// Returns number of the commitments in the log.
+    pub fn len(&self) -> u64 { self.frontier.len }
+
+    /// Detects whether the log is empty.
+    pub fn is_empty(&self) -> bool { self.frontier.is_empty() }
+
+    /// Returns log frontier.
+    pub fn frontier(&self) -> &Frontier { &self.frontier }
+
+    /// Appends commitment to the log, returning its index.
+    pub fn append(&mut self, commitment: Commitment) -> u64 {
+        self.leaves.push(log_leaf(commitment));
+        self.frontier.append(commitment)
+    }
+
+    /// Computes the current root of the log, or `None` for an empty log.
+    pub fn root(&self) -> Option<MerkleNode> { self.frontier.root() }
+
+    /// Computes the root of the log when it had `size` commitments.
+    pub fn root_at(&self, size: u64) -> Option<MerkleNode> {
+        if size == 0 || size > self.len() {
+            return None;
+        }
+        Some(log_root(&self.leaves[..size as usize]))
+    }
+
+    /// Returns checkpoint of the current log state, or `None` for an empty
+    /// log.
+    pub fn checkpoint(&self) -> Option<LogCheckpoint> { self.checkpoint_at(self.len()) }
+
+    /// Returns checkpoint of the historical log state when it had `size`
+    /// commitments.
+    pub fn checkpoint_at(&self, size: u64) -> Option<LogCheckpoint> {
+        Some(LogCheckpoint {
+            size,
+            root: self.root_at(size)?,
+        })
+    }
+
+    /// Constructs proof that the log of `new_size` commitments extends the
+    /// log of `old_size` commitments, i.e. that no commitments were removed
+    /// or replaced in between.
+    pub fn prove_consistency(&self, old_size: u64, new_size: u64) -> Option<ConsistencyProof> {
+        if old_size == 0 || old_size > new_size || new_size > self.len() {
+            return None;
+        }
+        let mut path = vec![];
+        consistency_path(old_size as usize, &self.leaves[..new_size as usize], true, &mut path);
+        Some(ConsistencyProof {
+            old_size,
+            new_size,
+            path: Confined::try_from(path).expect("tree depth never exceeds 64"),
+        })
+    }
+
+    /// Constructs proof of inclusion of the commitment with the given `index`
+    /// into the current log checkpoint.
+    pub fn prove_inclusion(&self, index: u64) -> Option<LogInclusion> {
+        LogInclusion::with(self.checkpoint()?, self.prove(index)?)
+    }
+
+    /// Constructs proof of inclusion of the commitment with the given `index`
+    /// against the current root of the log.
+    pub fn prove(&self, index: u64) -> Option<LogProof> { self.prove_at(index, self.len()) }
+
+    /// Constructs proof of inclusion of the commitment with the given `index`
+    /// against the historical root of the log when it had `size` commitments.
+    pub fn prove_at(&self, index: u64, size: u64) -> Option<LogProof> {
+        if index >= size || size > self.len() {
+            return None;
+        }
+        let mut path = vec![];
+        audit_path(index as usize, &self.leaves[..size as usize], log_node, &mut path);
+        Some(LogProof {
+            index,
+            size,
+            path: Confined::try_from(path).expect("tree depth never exceeds 64"),
+        })
+    }
+}
+
+/// Checkpoint of a [`CommitmentLog`] state: its size and root at some moment
+/// of time.
+#[derive(Getters, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct LogCheckpoint {
+    /// Number of the commitments in the log.
+    #[getter(as_copy)]
+    size: u64,
+
+    /// Log root.
+    #[getter(as_copy)]
+    root: MerkleNode,
+}
+
+impl StrictSerialize for LogCheckpoint {}
+impl StrictDeserialize for LogCheckpoint {}
+
+/// Proof of inclusion of a commitment into [`CommitmentLog`].
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct LogProof {
+    /// Index of the commitment in the log.
+    #[getter(as_copy)]
+    index: u64,
+
+    /// Number of the commitments in the log at the moment of the proven root.
+    #[getter(as_copy)]
+    size: u64,
+
+    /// Merkle path from the leaf to the root, starting from the bottom.
+    path: TinyVec<MerkleNode>,
+}
+
+impl LogProof {
+    /// Computes log root from the `commitment` using the proof.
+    ///
+    /// Returns `None` if the proof is malformed.
+    pub fn convolve(&self, commitment: Commitment) -> Option<MerkleNode> {
+        if self.index >= self.size {
+            return None;
+        }
+        root_from_path(self.index, self.size, log_leaf(commitment), &self.path, log_node)
+    }
+
+    /// Verifies that the `commitment` is a part of the log with the given
+    /// `root`.
+    pub fn verify(&self, commitment: Commitment, root: MerkleNode) -> bool {
+        self.convolve(commitment) == Some(root)
+    }
+}
+
+/// Proof of inclusion of a commitment into a [`CommitmentLog`] published
+/// with the given [`LogCheckpoint`].
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct LogInclusion {
+    /// Published log checkpoint.
+    #[getter(as_copy)]
+    checkpoint: LogCheckpoint,
+
+    /// Proof of inclusion against the checkpoint root.
+    proof: LogProof,
+}
+
+impl LogInclusion {
+    /// Constructs log inclusion, returning `None` if the proof is made
+    /// against a log of a different size than the one of the `checkpoint`.
+    pub fn with(checkpoint: LogCheckpoint, proof: LogProof) -> Option<Self> {
+        if checkpoint.size != proof.size {
+            return None;
+        }
+        Some(LogInclusion { checkpoint, proof })
+    }
+}
+
+impl ProofOfPublication for LogInclusion {
+    type Commitment = Commitment;
+
+    fn medium(&self) -> PublicationMedium { PublicationMedium::Log }
+
+    fn published_id(&self) -> Bytes32 { self.checkpoint.root.into_inner() }
+
+    fn verify(&self, commitment: &Commitment) -> bool {
+        self.checkpoint.size == self.proof.size &&
+            self.proof.verify(*commitment, self.checkpoint.root)
+    }
+}
+
+impl VerifiableProof for LogInclusion {
+    /// Verifies that the `commitment` is included into the log.
+    fn verify_dyn(&self, commitment: &[u8; 32]) -> Result<(), VerifyError> {
+        if !self.verify(&Commitment::from(*commitment)) {
+            return Err(VerifyError::InvalidCommitment);
+        }
+        Ok(())
+    }
+}
+
+/// Proof that one [`LogCheckpoint`] extends another one.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ConsistencyProof {
+    /// Size of the older log.
+    #[getter(as_copy)]
+    old_size: u64,
+
+    /// Size of the newer log.
+    #[getter(as_copy)]
+    new_size: u64,
+
+    /// Subtree roots required to reconstruct both log roots.
+    path: TinyVec<MerkleNode>,
+}
+
+impl ConsistencyProof {
+    /// Verifies that the `new` log checkpoint extends the `old` one, using the
+    /// algorithm from [RFC 9162].
+    ///
+    /// [RFC 9162]: https://www.rfc-editor.org/rfc/rfc9162#section-2.1.4.2
+    pub fn verify(&self, old: LogCheckpoint, new: LogCheckpoint) -> bool {
+        if old.size != self.old_size || new.size != self.new_size {
+            return false;
+        }
+        if self.old_size == self.new_size {
+            return self.path.is_empty() && old.root == new.root;
+        }
+        if self.old_size == 0 || self.old_size > self.new_size {
+            return false;
+        }
+
+        let mut path = self.path.iter().copied();
+        // For the old log being a perfect tree its root is omitted from the
+        // proof
+        let first = if self.old_size.is_power_of_two() {
+            Some(old.root)
+        } else {
+            path.next()
+        };
+        let Some(first) = first else {
+            return false;
+        };
+
+        let mut old_pos = self.old_size - 1;
+        let mut new_pos = self.new_size - 1;
+        while old_pos & 1 == 1 {
+            old_pos >>= 1;
+            new_pos >>= 1;
+        }
+        let mut old_root = first;
+        let mut new_root = first;
+        for node in path {
+            if new_pos == 0 {
+                return false;
+            }
+            if old_pos & 1 == 1 || old_pos == new_pos {
+                old_root = log_node(node, old_root);
+                new_root = log_node(node, new_root);
+                while old_pos & 1 == 0 && old_pos != 0 {
+                    old_pos >>= 1;
+                    new_pos >>= 1;
+                }
+            } else {
+                new_root = log_node(new_root, node);
+            }
+            old_pos >>= 1;
+            new_pos >>= 1;
+        }
+        new_pos == 0 && old_root == old.root && new_root == new.root
+    }
+}
+
+fn consistency_path(
+    old_size: usize,
+    leaves: &[MerkleNode],
+    whole: bool,
+    path: &mut Vec<MerkleNode>,
+) {
+    let n = leaves.len();
+    if old_size == n {
+        if !whole {
+            path.push(log_root(leaves));
+        }
+        return;
+    }
+    let k = split_point(n as u64) as usize;
+    if old_size <= k {
+        consistency_path(old_size, &leaves[..k], whole, path);
+        path.push(log_root(&leaves[k..]));
+    } else {
+        consistency_path(old_size - k, &leaves[k..], false, path);
+        path.push(log_root(&leaves[..k]));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U16;
+
+    use super::*;
+
+    fn commitment(no: u8) -> Commitment { Commitment::from([no; 32]) }
+
+    #[test]
+    fn frontier() {
+        let mut log = CommitmentLog::new();
+        assert_eq!(log.root(), None);
+        for no in 0u8..40 {
+            assert_eq!(log.append(commitment(no)), no as u64);
+            assert_eq!(log.frontier().peaks.len() as u32, log.len().count_ones());
+            assert_eq!(log.root(), Some(log_root(&log.leaves)));
+        }
+    }
+
+    #[test]
+    fn frontier_decode() {
+        let mut log = CommitmentLog::new();
+        for no in 0u8..5 {
+            log.append(commitment(no));
+        }
+        let frontier = log.frontier().clone();
+        let data = frontier.to_strict_serialized::<U16>().unwrap();
+        assert_eq!(Frontier::from_strict_serialized::<U16>(data).unwrap(), frontier);
+
+        let mut peaks = frontier.peaks.clone().release();
+        peaks.pop();
+        let peaks = TinyVec::try_from(peaks).unwrap();
+        assert_eq!(Frontier::with(frontier.len, peaks.clone()), None);
+        let malformed = Frontier {
+            len: frontier.len,
+            peaks,
+        };
+        let data = malformed.to_strict_serialized::<U16>().unwrap();
+        assert!(Frontier::from_strict_serialized::<U16>(data).is_err());
+    }
+
+    #[test]
+    fn inclusion_proofs() {
+        let mut log = CommitmentLog::new();
+        for no in 0u8..21 {
+            log.append(commitment(no));
+        }
+        let root = log.root().unwrap();
+        for index in 0..21u8 {
+            let proof = log.prove(index as u64).unwrap();
+            assert!(proof.verify(commitment(index), root));
+            assert!(!proof.verify(commitment(index + 1), root));
+
+            let size = (index as u64 + 3).min(21);
+            let proof = log.prove_at(index as u64, size).unwrap();
+            assert_eq!(proof.convolve(commitment(index)), log.root_at(size));
+        }
+        assert!(log.prove(21).is_none());
+        assert!(log.prove_at(5, 22).is_none());
+    }
+
+    #[test]
+    fn log_inclusion() {
+        let mut log = CommitmentLog::new();
+        for no in 0u8..9 {
+            log.append(commitment(no));
+        }
+        let inclusion = log.prove_inclusion(4).unwrap();
+        let evidence: Box<dyn ProofOfPublication<Commitment = Commitment>> =
+            Box::new(inclusion.clone());
+        assert_eq!(evidence.medium(), PublicationMedium::Log);
+        assert_eq!(evidence.published_id(), log.root().unwrap().into_inner());
+        assert!(evidence.verify(&commitment(4)));
+        assert!(!evidence.verify(&commitment(5)));
+
+        let old = log.checkpoint_at(5).unwrap();
+        assert_eq!(LogInclusion::with(old, inclusion.proof().clone()), None);
+        assert!(log.prove_inclusion(9).is_none());
+    }
+
+    #[test]
+    fn consistency_proofs() {
+        let mut log = CommitmentLog::new();
+        for no in 0u8..33 {
+            log.append(commitment(no));
+        }
+        for new_size in 1..=33 {
+            let new = log.checkpoint_at(new_size).unwrap();
+            for old_size in 1..=new_size {
+                let old = log.checkpoint_at(old_size).unwrap();
+                let proof = log.prove_consistency(old_size, new_size).unwrap();
+                assert!(proof.verify(old, new), "{old_size} -> {new_size}");
+                if old_size < new_size {
+                    let forged = LogCheckpoint {
+                        size: new_size,
+                        root: old.root,
+                    };
+                    assert!(!proof.verify(old, forged));
+                    assert!(!proof.verify(
+                        LogCheckpoint {
+                            size: old_size,
+                            root: new.root
+                        },
+                        new
+                    ));
+                }
+            }
+        }
+        assert!(log.prove_consistency(0, 3).is_none());
+        assert!(log.prove_consistency(4, 3).is_none());
+        assert!(log.prove_consistency(3, 34).is_none());
+    }
+}
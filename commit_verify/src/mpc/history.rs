@@ -0,0 +1,308 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! History of commitments made under a single protocol.
+//!
+//! A protocol committing its state into a sequence of LNPBP-4 trees keeps the
+//! merkle proofs and messages of each of its commitments in a
+//! [`ProtocolHistory`], which can be verified against the anchored LNPBP-4
+//! commitments all at once.
+
+use amplify::confinement::{self, MediumVec};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::mpc::{
+    Commitment, InvalidProof, LeafNotKnown, MerkleBlock, MerkleProof, Message, ProtocolId,
+};
+use crate::{CommitmentId, ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+/// Errors verifying [`ProtocolHistory`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum HistoryError {
+    /// history entry #{0} contains invalid merkle proof. Details: {1}
+    InvalidProof(u32, InvalidProof),
+
+    /// history entry #{index} commits to {actual} instead of the expected
+    /// commitment {expected}.
+    CommitmentMismatch {
+        index: u32,
+        expected: Commitment,
+        actual: Commitment,
+    },
+
+    /// number of history entries doesn't match the number of provided
+    /// commitments.
+    LengthMismatch,
+}
+
+impl ErrorCode for HistoryError {
+    fn code(&self) -> &'static str {
+        match self {
+            HistoryError::InvalidProof(..) => "mpc.history.invalid-proof",
+            HistoryError::CommitmentMismatch { .. } => "mpc.history.commitment-mismatch",
+            HistoryError::LengthMismatch => "mpc.history.length-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            HistoryError::InvalidProof(index, err) => {
+                let mut params = vec![("index", index.to_string())];
+                params.extend(err.params());
+                params
+            }
+            HistoryError::CommitmentMismatch {
+                index,
+                expected,
+                actual,
+            } => vec![
+                ("index", index.to_string()),
+                ("actual", actual.to_string()),
+                ("expected", expected.to_string()),
+            ],
+            HistoryError::LengthMismatch => vec![],
+        }
+    }
+}
+
+impl ErrorCodes for HistoryError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.history.invalid-proof",
+        "mpc.history.commitment-mismatch",
+        "mpc.history.length-mismatch",
+    ];
+}
+
+/// Errors adding an entry to [`ProtocolHistory`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum HistoryAppendError {
+    #[from]
+    #[display(inner)]
+    LeafNotKnown(LeafNotKnown),
+
+    /// protocol history has reached the maximum number of entries.
+    #[from(confinement::Error)]
+    TooLarge,
+}
+
+impl ErrorCode for HistoryAppendError {
+    fn code(&self) -> &'static str {
+        match self {
+            HistoryAppendError::LeafNotKnown(err) => err.code(),
+            HistoryAppendError::TooLarge => "mpc.history.too-large",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            HistoryAppendError::LeafNotKnown(err) => err.params(),
+            HistoryAppendError::TooLarge => vec![],
+        }
+    }
+}
+
+impl ErrorCodes for HistoryAppendError {
+    const CODES: &'static [&'static str] = &["mpc.history.too-large", "mpc.leaf-not-known"];
+}
+
+/// Single commitment made under some protocol, consisting of the LNPBP-4
+/// commitment (as it is used in the anchor), the merkle proof and the message.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct HistoryEntry {
+    /// LNPBP-4 commitment (root of the merkle tree).
+    pub commitment: Commitment,
+    /// Merkle proof of the message inclusion into the commitment.
+    pub proof: MerkleProof,
+    /// Message committed under the protocol.
+    pub message: Message,
+}
+
+impl HistoryEntry {
+    /// Verifies that the merkle proof with the message under `protocol_id`
+    /// convolves into the commitment of the entry.
+    pub fn verify(&self, protocol_id: ProtocolId) -> Result<(), HistoryError> {
+        self.verify_indexed(0, protocol_id)
+    }
+
+    fn verify_indexed(&self, index: u32, protocol_id: ProtocolId) -> Result<(), HistoryError> {
+        let actual = self
+            .proof
+            .convolve(protocol_id, self.message)
+            .map_err(|err| HistoryError::InvalidProof(index, err))?;
+        if actual != self.commitment {
+            return Err(HistoryError::CommitmentMismatch {
+                index,
+                expected: self.commitment,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Ordered history of all commitments made under a single protocol.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ProtocolHistory {
+    /// Protocol under which all commitments in the history are made.
+    #[getter(as_copy)]
+    protocol_id: ProtocolId,
+
+    /// History entries, in the order they were made.
+    entries: MediumVec<HistoryEntry>,
+}
+
+impl StrictSerialize for ProtocolHistory {}
+impl StrictDeserialize for ProtocolHistory {}
+
+impl ProtocolHistory {
+    /// Constructs empty history for the given protocol.
+    pub fn new(protocol_id: ProtocolId) -> Self {
+        ProtocolHistory {
+            protocol_id,
+            entries: empty!(),
+        }
+    }
+
+    /// Returns number of entries in the history.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    /// Detects whether the history is empty.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Appends an entry to the end of the history.
+    pub fn push(
+        &mut self,
+        commitment: Commitment,
+        proof: MerkleProof,
+        message: Message,
+    ) -> Result<(), confinement::Error> {
+        self.entries.push(HistoryEntry {
+            commitment,
+            proof,
+            message,
+        })
+    }
+
+    /// Appends an entry extracted from the merkle block, which must reveal the
+    /// message under the history protocol.
+    pub fn push_block(&mut self, block: &MerkleBlock) -> Result<(), HistoryAppendError> {
+        let proof = block.to_merkle_proof(self.protocol_id)?;
+        let message = *block
+            .to_known_message_map()
+            .get(&self.protocol_id)
+            .expect("the leaf is known since the proof was constructed");
+        self.push(block.commitment_id(), proof, message)?;
+        Ok(())
+    }
+
+    /// Verifies all entries of the history against the history protocol id.
+    pub fn verify(&self) -> Result<(), HistoryError> {
+        for (index, entry) in self.entries.iter().enumerate() {
+            entry.verify_indexed(index as u32, self.protocol_id)?;
+        }
+        Ok(())
+    }
+
+    /// Verifies all entries of the history and checks that they commit to the
+    /// provided sequence of commitments (for instance, taken from the anchors).
+    pub fn verify_commitments(
+        &self,
+        commitments: impl IntoIterator<Item = Commitment>,
+    ) -> Result<(), HistoryError> {
+        self.verify()?;
+        let mut commitments = commitments.into_iter();
+        for (index, entry) in self.entries.iter().enumerate() {
+            match commitments.next() {
+                Some(expected) if expected == entry.commitment => {}
+                Some(expected) => {
+                    return Err(HistoryError::CommitmentMismatch {
+                        index: index as u32,
+                        expected,
+                        actual: entry.commitment,
+                    });
+                }
+                None => return Err(HistoryError::LengthMismatch),
+            }
+        }
+        if commitments.next().is_some() {
+            return Err(HistoryError::LengthMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U16;
+
+    use super::*;
+    use crate::mpc::tree::test_helpers::{make_random_messages, make_random_tree};
+
+    #[test]
+    fn history() {
+        let mut history = None;
+        let mut commitments = vec![];
+        for _ in 0..4 {
+            let mut msgs = make_random_messages(5);
+            let pid = history
+                .get_or_insert_with(|| ProtocolHistory::new(*msgs.keys().next().unwrap()))
+                .protocol_id();
+            msgs.insert(pid, Message::default());
+            let tree = make_random_tree(&msgs);
+            let block = MerkleBlock::from(&tree);
+            history.as_mut().unwrap().push_block(&block).unwrap();
+            commitments.push(tree.commitment_id());
+        }
+        let mut history = history.unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.verify(), Ok(()));
+        assert_eq!(history.verify_commitments(commitments.iter().copied()), Ok(()));
+
+        let data = history.to_strict_serialized::<U16>().unwrap();
+        let decoded = ProtocolHistory::from_strict_serialized::<U16>(data).unwrap();
+        assert_eq!(decoded, history);
+
+        commitments.swap(1, 2);
+        assert!(matches!(
+            history.verify_commitments(commitments),
+            Err(HistoryError::CommitmentMismatch { index: 1, .. })
+        ));
+
+        history.entries[3].message = Message::from([1u8; 32]);
+        assert!(matches!(history.verify(), Err(HistoryError::CommitmentMismatch { index: 3, .. })));
+    }
+}
@@ -0,0 +1,50 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Size limits of LNPBP-4 commitments and the data structures holding them.
+//!
+//! The limits are tied together with compile-time assertions, such that a
+//! change to one of them can't silently desynchronize the others.
+
+use amplify::confinement;
+use amplify::num::u5;
+
+/// Maximal number of messages (protocols) in a single LNPBP-4 commitment, as
+/// defined by the standard.
+pub const MAX_MESSAGES: usize = 1 << 16;
+
+/// Maximal depth of LNPBP-4 merkle tree.
+pub const MAX_DEPTH: u8 = u5::MAX.to_u8();
+
+/// Maximal number of entries in [`super::MessageMap`].
+pub const MESSAGE_MAP_LIMIT: usize = confinement::U24;
+
+/// Maximal number of entries in the map of the tree leaf positions to the
+/// protocol messages.
+pub const ORDERED_MAP_LIMIT: usize = confinement::U24;
+
+const _: () = assert!(MAX_MESSAGES <= MESSAGE_MAP_LIMIT, "message map can't hold all messages");
+const _: () = assert!(
+    ORDERED_MAP_LIMIT >= MESSAGE_MAP_LIMIT,
+    "leaf position map can't hold all messages from a message map"
+);
+const _: () = assert!((MAX_DEPTH as u32) < u32::BITS, "tree width must fit u32");
+const _: () = assert!(MAX_MESSAGES <= 1 << MAX_DEPTH, "messages can't fit the deepest tree");
@@ -0,0 +1,110 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metadata leaf describing the software which produced LNPBP-4 commitment.
+//!
+//! Producers may commit a small descriptor of their implementation into the
+//! tree under a reserved [`ProducerInfo::protocol_id`], allowing forensic
+//! analysis of old anchors to identify the implementation which created them.
+//! The descriptor itself is not stored in the tree: only its commitment id is,
+//! and the descriptor must be supplied by the party doing the analysis.
+
+use amplify::confinement::{self, TinyString};
+
+use crate::mpc::{Message, ReservedSlot};
+use crate::{CommitmentId, LIB_NAME_COMMIT_VERIFY};
+
+/// Descriptor of the software producing LNPBP-4 commitments.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[derive(CommitEncode)]
+#[commit_encode(crate = crate, strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ProducerInfo {
+    /// Name of the software.
+    software: TinyString,
+
+    /// Version of the software.
+    version: TinyString,
+
+    /// Version of the data format used by the software.
+    #[getter(as_copy)]
+    format: u16,
+}
+
+impl CommitmentId for ProducerInfo {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:meta:v01#23A";
+    type Id = Message;
+}
+
+impl ReservedSlot for ProducerInfo {}
+
+impl ProducerInfo {
+    /// Constructs descriptor of the producing software, failing if the
+    /// `software` name or `version` exceed 255 bytes.
+    pub fn new(software: &str, version: &str, format: u16) -> Result<Self, confinement::Error> {
+        Ok(ProducerInfo {
+            software: TinyString::try_from(software.to_owned())?,
+            version: TinyString::try_from(version.to_owned())?,
+            format,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::make_random_messages;
+    use crate::mpc::{MerkleBlock, MerkleTree, MultiSource};
+    use crate::TryCommitVerify;
+
+    #[test]
+    fn producer_info() {
+        let info = ProducerInfo::new("commit_verify", "0.11.0", 1).unwrap();
+        let mut source = MultiSource::default();
+        for (protocol_id, message) in make_random_messages(5) {
+            source.messages.insert(protocol_id, message).unwrap();
+        }
+        info.commit_into(&mut source).unwrap();
+        let tree = MerkleTree::try_commit(&source).unwrap();
+        assert!(info.verify(&tree));
+
+        let mut block = MerkleBlock::from(&tree);
+        assert_eq!(ProducerInfo::message(&block), Some(info.commitment_id()));
+        assert!(info.verify_block(&block));
+
+        let other = ProducerInfo::new("commit_verify", "0.11.1", 1).unwrap();
+        assert!(!other.verify(&tree));
+        assert!(!other.verify_block(&block));
+
+        block.prune(|protocol_id| protocol_id != ProducerInfo::protocol_id());
+        assert_eq!(ProducerInfo::message(&block), None);
+        assert!(!info.verify_block(&block));
+        assert_eq!(block.commitment_id(), tree.commitment_id());
+
+        assert!(ProducerInfo::new(&"x".repeat(256), "1", 0).is_err());
+    }
+}
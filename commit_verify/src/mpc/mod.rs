@@ -23,15 +23,74 @@
 //!
 //! [LNPBP-4]: https://github.com/LNP-BP/LNPBPs/blob/master/lnpbp-0004.md
 
+mod aggregate;
 mod atoms;
+mod attachment;
 mod tree;
+mod blinded;
 mod block;
+mod checkpoint;
+mod circuit;
+mod delta;
+mod diff;
+mod domain;
+mod entropy;
+mod fixture;
+mod frontier;
+mod history;
+pub mod limits;
+mod metadata;
+mod namespace;
+mod path;
+mod policy;
+mod reference;
+mod registry;
+mod relay;
+mod seals;
+mod slot;
+mod tombstone;
+mod watermark;
 
+pub use aggregate::{producer_message, AggregatedProof, Aggregation, AggregationError};
 pub use atoms::{
-    Commitment, Leaf, Message, MessageMap, MultiSource, ProtocolId, MPC_MINIMAL_DEPTH,
+    Commitment, Leaf, Message, MessageMap, MultiSource, Precommitment, ProtocolId,
+    ProtocolNameError, MPC_MINIMAL_DEPTH,
 };
-pub use block::{InvalidProof, LeafNotKnown, MergeError, MerkleBlock, MerkleProof};
-pub use tree::{Error, MerkleTree};
+pub use attachment::{AttachedMessage, Attachment, VerifyAttachment};
+pub use blinded::{BlindedError, BlindedLeaf};
+pub use block::{
+    InvalidProof, LeafNotKnown, MergeError, MerkleBlock, MerkleProof, NonCanonical,
+    PrecommitmentError, ResumableError, TreeNode, VerifyCheckpoint, VerifyProgress,
+};
+pub use checkpoint::{Checkpoint, CheckpointChain, CheckpointError, CheckpointId};
+pub use circuit::CircuitWitness;
+pub use delta::{DeltaApplication, DeltaError, MessageMapDelta};
+pub use diff::{compare_protocol, ProtocolDiff};
+pub use domain::{tagged_protocol_id, KeyDomain, KeyedSource, Lnpbp4};
+pub use entropy::{BeaconEntropy, EntropySource};
+pub use fixture::FixtureError;
+pub use frontier::{
+    log_leaf, log_node, CommitmentLog, ConsistencyProof, Frontier, LogCheckpoint, LogInclusion,
+    LogProof, LOG_LEAF_TAG, LOG_NODE_TAG,
+};
+pub use history::{HistoryAppendError, HistoryEntry, HistoryError, ProtocolHistory};
+pub use metadata::ProducerInfo;
+pub use namespace::NamespaceProof;
+pub use path::{MerklePath, MerklePathError};
+pub use policy::{PolicyError, SourcePolicy};
+pub use reference::{
+    differential_check, differential_check_block, reference_block_commitment, reference_convolve,
+    DifferentialMismatch,
+};
+pub use registry::{MessageType, MessageTypeError, TypeRegistry, TypedProof};
+pub use relay::{RelayReceipt, RelayRejection, RelayRequest, RelayResponse};
+pub use seals::{ClosedSeals, ClosedSealsError};
+pub use slot::{ReservedSlot, RESERVED_SLOT_TAG};
+pub use tombstone::{AnnotatedBlock, TombstoneError};
+#[cfg(test)]
+pub(crate) use tree::test_helpers;
+pub use tree::{Error, MerkleTree, TreeValidationError};
+pub use watermark::{WatermarkKey, WatermarkedProof};
 
 pub const MERKLE_LNPBP4_TAG: u128 = u128::from_le_bytes(*b"urn:lnpbp:lnpbp4");
 
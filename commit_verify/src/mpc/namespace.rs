@@ -0,0 +1,119 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hierarchical namespaces of protocol ids.
+//!
+//! Applications running many sub-protocols may derive their ids from a single
+//! parent protocol id and a path of child indexes, instead of registering
+//! each of them separately. [`NamespaceProof`] allows to prove that a protocol
+//! id belongs to the namespace of a parent protocol.
+
+use amplify::confinement::{self, TinyVec};
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::mpc::ProtocolId;
+use crate::{CommitEncode, LIB_NAME_COMMIT_VERIFY};
+
+impl ProtocolId {
+    /// Tag of the hash deriving child protocol ids with [`ProtocolId::child`].
+    pub const CHILD_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:chld:v01#23A";
+
+    /// Derives id of a child protocol with the given `index` within the
+    /// namespace of this protocol.
+    pub fn child(&self, index: u32) -> ProtocolId {
+        let mut engine = Sha256::from_tag(Self::CHILD_TAG);
+        self.commit_encode(&mut engine);
+        index.commit_encode(&mut engine);
+        engine.finish().into()
+    }
+
+    /// Derives id of a descendant protocol following the `path` of child
+    /// indexes, starting from this protocol.
+    pub fn derive(&self, path: impl IntoIterator<Item = u32>) -> ProtocolId {
+        path.into_iter()
+            .fold(*self, |protocol_id, index| protocol_id.child(index))
+    }
+}
+
+/// Proof that a protocol id belongs to the namespace of a parent protocol.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct NamespaceProof {
+    /// Parent protocol id.
+    #[getter(as_copy)]
+    parent: ProtocolId,
+
+    /// Path of child indexes from the parent to the proven protocol.
+    path: TinyVec<u32>,
+}
+
+impl NamespaceProof {
+    /// Constructs proof for a protocol derived from the `parent` following the
+    /// `path` of child indexes.
+    pub fn with(
+        parent: ProtocolId,
+        path: impl IntoIterator<Item = u32>,
+    ) -> Result<Self, confinement::Error> {
+        Ok(NamespaceProof {
+            parent,
+            path: TinyVec::try_from_iter(path)?,
+        })
+    }
+
+    /// Returns the id of the protocol, which membership in the parent
+    /// namespace is proven.
+    pub fn protocol_id(&self) -> ProtocolId { self.parent.derive(self.path.iter().copied()) }
+
+    /// Verifies that the `protocol_id` belongs to the namespace of the parent
+    /// protocol.
+    pub fn verify(&self, protocol_id: ProtocolId) -> bool { self.protocol_id() == protocol_id }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn namespace() {
+        let root = ProtocolId::from([0xAB; 32]);
+        let child = root.child(1);
+        assert_ne!(child, root);
+        assert_ne!(child, root.child(2));
+        assert_eq!(root.derive([]), root);
+        assert_eq!(root.derive([1, 7]), child.child(7));
+        assert_ne!(root.derive([1, 7]), root.derive([7, 1]));
+
+        let proof = NamespaceProof::with(root, [1, 7, 0]).unwrap();
+        assert_eq!(proof.parent(), root);
+        assert!(proof.verify(child.child(7).child(0)));
+        assert!(!proof.verify(child.child(7)));
+        assert!(!NamespaceProof::with(child, [1, 7, 0])
+            .unwrap()
+            .verify(child.child(7).child(0)));
+    }
+}
@@ -0,0 +1,293 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle paths of LNPBP-4 inclusion proofs.
+//!
+//! [`MerklePath`] is a sequence of node hashing partners going from the tree
+//! root towards the leaf. Its length is confined to the maximal depth of an
+//! LNPBP-4 tree (31, i.e. [`u5::MAX`]), and it may be checked against a
+//! specific tree depth with [`MerklePath::check_depth`].
+
+use std::fmt::{self, Display, Formatter};
+use std::iter::Rev;
+use std::slice;
+use std::str::FromStr;
+
+use amplify::confinement::Confined;
+use amplify::hex::{self, FromHex, ToHex};
+use amplify::num::u5;
+use strict_encoding::{
+    DecodeError, StrictDecode, StrictEncode, StrictType, TypeName, TypedRead, TypedWrite,
+};
+
+use crate::merkle::MerkleNode;
+use crate::{ErrorCode, ErrorCodes};
+
+/// Errors constructing or parsing [`MerklePath`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum MerklePathError {
+    /// invalid hexadecimal encoding of the Merkle path - {0}.
+    #[from]
+    InvalidHex(hex::Error),
+
+    /// Merkle path string length {0} is not a multiple of 64 hex characters.
+    InvalidLength(usize),
+
+    /// Merkle path length {0} exceeds the maximal tree depth of 31.
+    TooLong(usize),
+
+    /// Merkle path length {actual} does not match the tree depth {expected}.
+    DepthMismatch { expected: u8, actual: u8 },
+}
+
+impl ErrorCode for MerklePathError {
+    fn code(&self) -> &'static str {
+        match self {
+            MerklePathError::InvalidHex(_) => "mpc.path.invalid-hex",
+            MerklePathError::InvalidLength(_) => "mpc.path.invalid-length",
+            MerklePathError::TooLong(_) => "mpc.path.too-long",
+            MerklePathError::DepthMismatch { .. } => "mpc.path.depth-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            MerklePathError::InvalidHex(_) => vec![],
+            MerklePathError::InvalidLength(len) | MerklePathError::TooLong(len) => {
+                vec![("len", len.to_string())]
+            }
+            MerklePathError::DepthMismatch { expected, actual } => {
+                vec![("actual", actual.to_string()), ("expected", expected.to_string())]
+            }
+        }
+    }
+}
+
+impl ErrorCodes for MerklePathError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.path.invalid-hex",
+        "mpc.path.invalid-length",
+        "mpc.path.too-long",
+        "mpc.path.depth-mismatch",
+    ];
+}
+
+/// Path of node hashing partners in a Merkle tree, going from the root towards
+/// the leaf.
+///
+/// The path is strict-encoded in exactly the same way as a list of
+/// [`MerkleNode`]s, so it doesn't introduce a new type into the strict type
+/// library. The library declares the list with up to 32 nodes, which keeps
+/// its id stable; paths longer than [`MerklePath::MAX_LEN`] are rejected
+/// during decoding.
+#[derive(Wrapper, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, From)]
+#[wrapper(Deref)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct MerklePath(Confined<Vec<MerkleNode>, 0, 31>);
+
+/// Strict type of the path in the type library.
+type LibPath = Confined<Vec<MerkleNode>, 0, 32>;
+
+impl StrictType for MerklePath {
+    const STRICT_LIB_NAME: &'static str = <LibPath as StrictType>::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { None }
+}
+
+impl StrictEncode for MerklePath {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> std::io::Result<W> {
+        self.0.strict_encode(writer)
+    }
+}
+
+impl StrictDecode for MerklePath {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let nodes = LibPath::strict_decode(reader)?.release();
+        let len = nodes.len();
+        Confined::try_from(nodes)
+            .map(Self)
+            .map_err(|_| DecodeError::DataIntegrityError(MerklePathError::TooLong(len).to_string()))
+    }
+}
+
+impl MerklePath {
+    /// Maximal length of a Merkle path, equal to the maximal tree depth
+    /// ([`u5::MAX`]).
+    pub const MAX_LEN: usize = 31;
+
+    /// Constructs path from the node hashing partners ordered from the root
+    /// towards the leaf.
+    ///
+    /// # Errors
+    ///
+    /// If the number of nodes exceeds [`Self::MAX_LEN`].
+    pub fn try_from_iter(
+        iter: impl IntoIterator<Item = MerkleNode>,
+    ) -> Result<Self, MerklePathError> {
+        let nodes = iter.into_iter().collect::<Vec<_>>();
+        let len = nodes.len();
+        Confined::try_from(nodes)
+            .map(Self)
+            .map_err(|_| MerklePathError::TooLong(len))
+    }
+
+    /// Returns depth of the tree the path belongs to.
+    pub fn depth(&self) -> u8 { self.0.len() as u8 }
+
+    /// Checks that the path length matches the depth of a Merkle tree.
+    pub fn check_depth(&self, depth: u5) -> Result<(), MerklePathError> {
+        if self.depth() != depth.to_u8() {
+            return Err(MerklePathError::DepthMismatch {
+                expected: depth.to_u8(),
+                actual: self.depth(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the path as a slice of nodes, from the root towards the leaf.
+    pub fn as_slice(&self) -> &[MerkleNode] { self.0.as_slice() }
+
+    /// Iterates over the nodes from the root towards the leaf.
+    pub fn iter(&self) -> slice::Iter<MerkleNode> { self.0.iter() }
+
+    /// Iterates over the nodes from the leaf towards the root, i.e. in the
+    /// order they are used for the proof convolution.
+    pub fn iter_from_leaf(&self) -> Rev<slice::Iter<MerkleNode>> { self.0.iter().rev() }
+}
+
+impl IntoIterator for MerklePath {
+    type Item = MerkleNode;
+    type IntoIter = std::vec::IntoIter<MerkleNode>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.release().into_iter() }
+}
+
+impl<'a> IntoIterator for &'a MerklePath {
+    type Item = &'a MerkleNode;
+    type IntoIter = slice::Iter<'a, MerkleNode>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
+
+impl PartialEq<Vec<MerkleNode>> for MerklePath {
+    fn eq(&self, other: &Vec<MerkleNode>) -> bool { self.as_slice() == other.as_slice() }
+}
+
+/// Displays the path as a concatenation of hex-encoded nodes, starting from
+/// the root.
+impl Display for MerklePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for node in self {
+            f.write_str(&node.to_hex())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MerklePath {
+    type Err = MerklePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() % 64 != 0 {
+            return Err(MerklePathError::InvalidLength(s.len()));
+        }
+        if s.len() / 64 > Self::MAX_LEN {
+            return Err(MerklePathError::TooLong(s.len() / 64));
+        }
+        let nodes = (0..s.len())
+            .step_by(64)
+            .map(|start| {
+                let chunk = s
+                    .get(start..start + 64)
+                    .ok_or(MerklePathError::InvalidLength(s.len()))?;
+                MerkleNode::from_hex(chunk).map_err(MerklePathError::from)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::try_from_iter(nodes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U16;
+    use strict_encoding::StrictDeserialize;
+
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::{MerkleBlock, MerkleProof};
+
+    #[test]
+    fn path_display_roundtrip() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(tree.clone());
+        let (pid, _) = msgs.first_key_value().unwrap();
+        let path = block.to_merkle_proof(*pid).unwrap().into_path();
+
+        assert_eq!(path.check_depth(tree.depth()), Ok(()));
+        assert!(path
+            .check_depth(u5::with(tree.depth().to_u8() + 1))
+            .is_err());
+        assert_eq!(path.iter_from_leaf().next(), path.as_slice().last());
+
+        let s = path.to_string();
+        assert_eq!(s.len(), path.depth() as usize * 64);
+        assert_eq!(MerklePath::from_str(&s).unwrap(), path);
+
+        assert_eq!(MerklePath::from_str(&s[1..]), Err(MerklePathError::InvalidLength(s.len() - 1)));
+        assert_eq!(
+            MerklePath::from_str(&s.repeat(33)),
+            Err(MerklePathError::TooLong(33 * path.depth() as usize))
+        );
+    }
+
+    #[test]
+    fn path_max_depth() {
+        let node = MerkleNode::from([0xA5; 32]);
+        assert!(MerklePath::try_from_iter([node; MerklePath::MAX_LEN]).is_ok());
+        assert_eq!(
+            MerklePath::try_from_iter([node; MerklePath::MAX_LEN + 1]),
+            Err(MerklePathError::TooLong(32))
+        );
+        assert_eq!(
+            MerklePath::from_str(&node.to_hex().repeat(32)),
+            Err(MerklePathError::TooLong(32))
+        );
+
+        // Strict-encoded proof: position, cofactor and the path prefixed
+        // with its length
+        let proof_data = |len: u8| {
+            let mut data = vec![0u8; 6];
+            data.push(len);
+            data.extend([0xA5; 32].repeat(len as usize));
+            Confined::<Vec<u8>, 0, U16>::try_from(data).unwrap()
+        };
+        let proof = MerkleProof::from_strict_serialized::<U16>(proof_data(31)).unwrap();
+        assert_eq!(proof.depth(), 31);
+        assert_eq!(proof.as_path(), &[node; 31]);
+        assert!(MerkleProof::from_strict_serialized::<U16>(proof_data(32)).is_err());
+    }
+}
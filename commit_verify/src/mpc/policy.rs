@@ -0,0 +1,216 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Business rules for the messages accepted into LNPBP-4 commitments.
+//!
+//! Aggregation services committing to messages from many clients may restrict
+//! the protocols they accept, require some protocols to be always present and
+//! limit the number of messages. [`SourcePolicy`] checks these rules before
+//! running the (expensive) cofactor search of the tree construction.
+
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+
+use crate::mpc::{self, MerkleTree, MultiSource, ProtocolId};
+use crate::{ErrorCode, ErrorCodes, TryCommitVerify};
+
+/// Violations of [`SourcePolicy`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PolicyError {
+    /// the number of messages {actual} exceeds the policy limit of {limit}.
+    TooManyMessages { limit: usize, actual: usize },
+
+    /// protocol {0} is outside of the protocol ranges allowed by the policy.
+    Forbidden(ProtocolId),
+
+    /// protocol {0} is required by the policy, but is absent from the source.
+    Missing(ProtocolId),
+
+    #[from]
+    #[display(inner)]
+    Tree(mpc::Error),
+}
+
+impl ErrorCode for PolicyError {
+    fn code(&self) -> &'static str {
+        match self {
+            PolicyError::TooManyMessages { .. } => "mpc.policy.too-many-messages",
+            PolicyError::Forbidden(_) => "mpc.policy.forbidden",
+            PolicyError::Missing(_) => "mpc.policy.missing",
+            PolicyError::Tree(err) => err.code(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            PolicyError::TooManyMessages { limit, actual } => {
+                vec![("actual", actual.to_string()), ("limit", limit.to_string())]
+            }
+            PolicyError::Forbidden(protocol_id) | PolicyError::Missing(protocol_id) => {
+                vec![("protocol_id", protocol_id.to_string())]
+            }
+            PolicyError::Tree(err) => err.params(),
+        }
+    }
+}
+
+impl ErrorCodes for PolicyError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.policy.too-many-messages",
+        "mpc.policy.forbidden",
+        "mpc.policy.missing",
+        "mpc.empty",
+        "mpc.too-many-messages",
+        "mpc.cant-fit",
+    ];
+}
+
+/// Rules for the messages accepted into LNPBP-4 commitment.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct SourcePolicy {
+    /// Maximal number of messages; unlimited if `None`.
+    pub max_messages: Option<usize>,
+
+    /// Ranges of allowed protocol ids; if empty, all protocols are allowed.
+    pub allowed: Vec<RangeInclusive<ProtocolId>>,
+
+    /// Protocols which must be present in each commitment.
+    pub required: BTreeSet<ProtocolId>,
+}
+
+impl SourcePolicy {
+    /// Constructs policy allowing any messages.
+    pub fn new() -> Self { default!() }
+
+    /// Limits the number of messages.
+    pub fn with_max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = Some(max_messages);
+        self
+    }
+
+    /// Adds a `range` of allowed protocol ids.
+    pub fn allow(mut self, range: RangeInclusive<ProtocolId>) -> Self {
+        self.allowed.push(range);
+        self
+    }
+
+    /// Adds a protocol which must be present in each commitment.
+    pub fn require(mut self, protocol_id: ProtocolId) -> Self {
+        self.required.insert(protocol_id);
+        self
+    }
+
+    /// Detects whether the policy allows the protocol.
+    pub fn is_allowed(&self, protocol_id: ProtocolId) -> bool {
+        self.allowed.is_empty() ||
+            self.allowed
+                .iter()
+                .any(|range| range.contains(&protocol_id))
+    }
+
+    /// Checks the source against the policy, reporting the first violation.
+    pub fn check(&self, source: &MultiSource) -> Result<(), PolicyError> {
+        let actual = source.messages.len();
+        if let Some(limit) = self.max_messages {
+            if actual > limit {
+                return Err(PolicyError::TooManyMessages { limit, actual });
+            }
+        }
+        if let Some(protocol_id) = source
+            .messages
+            .keys()
+            .find(|protocol_id| !self.is_allowed(**protocol_id))
+        {
+            return Err(PolicyError::Forbidden(*protocol_id));
+        }
+        if let Some(protocol_id) = self
+            .required
+            .iter()
+            .find(|protocol_id| !source.messages.contains_key(protocol_id))
+        {
+            return Err(PolicyError::Missing(*protocol_id));
+        }
+        Ok(())
+    }
+}
+
+impl MerkleTree {
+    /// Checks the source against the `policy` and, if it complies, constructs
+    /// the tree with [`MerkleTree::try_commit`].
+    pub fn try_commit_with_policy(
+        source: &MultiSource,
+        policy: &SourcePolicy,
+    ) -> Result<Self, PolicyError> {
+        policy.check(source)?;
+        Ok(MerkleTree::try_commit(source)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use amplify::num::u5;
+
+    use super::*;
+    use crate::mpc::Message;
+
+    fn source(pids: impl IntoIterator<Item = u8>) -> MultiSource {
+        let messages = pids
+            .into_iter()
+            .map(|no| (ProtocolId::from([no; 32]), Message::from([no; 32])));
+        MultiSource {
+            min_depth: u5::ZERO,
+            messages: Confined::try_from_iter(messages).unwrap(),
+            static_entropy: Some(0),
+        }
+    }
+
+    #[test]
+    fn policy() {
+        let policy = SourcePolicy::new()
+            .with_max_messages(4)
+            .allow(ProtocolId::from([0x10; 32])..=ProtocolId::from([0x1F; 32]))
+            .allow(ProtocolId::from([0xF0; 32])..=ProtocolId::from([0xF0; 32]))
+            .require(ProtocolId::from([0xF0; 32]));
+
+        assert!(MerkleTree::try_commit_with_policy(&source([0x10, 0x1F, 0xF0]), &policy).is_ok());
+        assert_eq!(
+            policy.check(&source([0x10, 0x11, 0x12, 0x13, 0xF0])),
+            Err(PolicyError::TooManyMessages {
+                limit: 4,
+                actual: 5
+            })
+        );
+        assert_eq!(
+            policy.check(&source([0x10, 0x20, 0xF0])),
+            Err(PolicyError::Forbidden(ProtocolId::from([0x20; 32])))
+        );
+        assert_eq!(
+            policy.check(&source([0x10])),
+            Err(PolicyError::Missing(ProtocolId::from([0xF0; 32])))
+        );
+        assert_eq!(
+            MerkleTree::try_commit_with_policy(&source([]), &SourcePolicy::new()).map(|_| ()),
+            Err(PolicyError::Tree(mpc::Error::Empty))
+        );
+    }
+}
@@ -0,0 +1,272 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Slow reference implementation of LNPBP-4 proof verification.
+//!
+//! The reference verifier places all the revealed nodes into their positions
+//! in the tree and recomputes the tree top-down, without any of the
+//! optimizations used by [`MerkleProof`] and [`MerkleBlock`]. It is used for
+//! differential testing and fuzzing, checking that the optimized verifiers
+//! never accept what the reference one rejects, and vice versa.
+
+use std::collections::BTreeMap;
+
+use crate::merkle::MerkleNode;
+use crate::mpc::{
+    Commitment, Leaf, MerkleBlock, MerkleProof, Message, ProtocolId, MERKLE_LNPBP4_TAG,
+};
+use crate::{CommitmentId, ErrorCode, ErrorCodes};
+
+/// optimized verifier produced {fast:?} while the reference one produced
+/// {reference:?}.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct DifferentialMismatch {
+    /// Result of the optimized verification.
+    pub fast: Option<Commitment>,
+    /// Result of the reference verification.
+    pub reference: Option<Commitment>,
+}
+
+impl ErrorCode for DifferentialMismatch {
+    fn code(&self) -> &'static str { "mpc.reference.mismatch" }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let param = |commitment: Option<Commitment>| {
+            commitment
+                .map(|commitment| commitment.to_string())
+                .unwrap_or_default()
+        };
+        vec![("fast", param(self.fast)), ("reference", param(self.reference))]
+    }
+}
+
+impl ErrorCodes for DifferentialMismatch {
+    const CODES: &'static [&'static str] = &["mpc.reference.mismatch"];
+}
+
+/// Known nodes of a tree, indexed by their depth and position at that depth.
+type KnownNodes = BTreeMap<(u8, u32), MerkleNode>;
+
+fn subtree_root(known: &KnownNodes, tree_depth: u8, depth: u8, index: u32) -> Option<MerkleNode> {
+    if let Some(node) = known.get(&(depth, index)) {
+        return Some(*node);
+    }
+    if depth >= tree_depth {
+        return None;
+    }
+    let left = subtree_root(known, tree_depth, depth + 1, index * 2)?;
+    let right = subtree_root(known, tree_depth, depth + 1, index * 2 + 1)?;
+    Some(MerkleNode::branches(
+        MERKLE_LNPBP4_TAG.to_be_bytes(),
+        depth,
+        1u32 << tree_depth,
+        left,
+        right,
+    ))
+}
+
+/// Computes position of the `protocol_id` in the tree by the schoolbook
+/// division of the little-endian protocol id bytes, independently from the
+/// 256-bit arithmetics used by the optimized verifiers.
+fn reference_pos(protocol_id: ProtocolId, cofactor: u16, width: u32) -> u32 {
+    let modulo = (width as u64).saturating_sub(cofactor as u64).max(1);
+    let rem = protocol_id
+        .to_byte_array()
+        .iter()
+        .rev()
+        .fold(0u64, |rem, byte| (rem * 256 + *byte as u64) % modulo);
+    rem as u32
+}
+
+/// Computes commitment from the proof of the `message` under `protocol_id`
+/// using the reference algorithm. Returns `None` if the proof is invalid.
+pub fn reference_convolve(
+    proof: &MerkleProof,
+    protocol_id: ProtocolId,
+    message: Message,
+) -> Option<Commitment> {
+    let depth = proof.depth();
+    let pos = proof.pos();
+    if reference_pos(protocol_id, proof.cofactor(), proof.width()) != pos {
+        return None;
+    }
+    let mut known = KnownNodes::new();
+    known.insert((depth, pos), Leaf::inhabited(protocol_id, message).commitment_id());
+    for (index, sibling) in proof.as_path().iter().enumerate() {
+        let sibling_depth = index as u8 + 1;
+        let sibling_index = (pos >> (depth - sibling_depth)) ^ 1;
+        known.insert((sibling_depth, sibling_index), *sibling);
+    }
+    subtree_root(&known, depth, 0, 0).map(Commitment::from_root)
+}
+
+/// Computes commitment of the merkle block using the reference algorithm.
+/// Returns `None` if the block cross-section is malformed.
+pub fn reference_block_commitment(block: &MerkleBlock) -> Option<Commitment> {
+    let tree_depth = block.depth().to_u8();
+    let mut known = KnownNodes::new();
+    let mut offset = 0u64;
+    for node in block.cross_section() {
+        let depth = node.depth_or(block.depth()).to_u8();
+        if depth > tree_depth {
+            return None;
+        }
+        let span = 1u64 << (tree_depth - depth);
+        if offset % span != 0 {
+            return None;
+        }
+        known.insert((depth, (offset / span) as u32), node.to_merkle_node());
+        offset += span;
+    }
+    if offset != block.width() as u64 {
+        return None;
+    }
+    subtree_root(&known, tree_depth, 0, 0).map(Commitment::from_root)
+}
+
+/// Verifies the proof of the `message` under `protocol_id` with both the
+/// optimized ([`MerkleProof::convolve`] and [`MerkleProof::convolve_in_place`])
+/// and the reference algorithms, failing if their results differ.
+///
+/// # Returns
+///
+/// Commitment the proof convolves to, or `None` if all of the algorithms
+/// reject the proof.
+pub fn differential_check(
+    proof: &MerkleProof,
+    protocol_id: ProtocolId,
+    message: Message,
+) -> Result<Option<Commitment>, DifferentialMismatch> {
+    let reference = reference_convolve(proof, protocol_id, message);
+    for fast in [
+        proof.convolve(protocol_id, message).ok(),
+        proof.convolve_in_place(protocol_id, message).ok(),
+    ] {
+        if fast != reference {
+            return Err(DifferentialMismatch { fast, reference });
+        }
+    }
+    Ok(reference)
+}
+
+/// Computes commitment of the merkle block with both the optimized and the
+/// reference algorithms, failing if their results differ.
+pub fn differential_check_block(block: &MerkleBlock) -> Result<Commitment, DifferentialMismatch> {
+    let fast = block.commitment_id();
+    let reference = reference_block_commitment(block);
+    if reference != Some(fast) {
+        return Err(DifferentialMismatch {
+            fast: Some(fast),
+            reference,
+        });
+    }
+    Ok(fast)
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use amplify::num::u5;
+
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::tree::protocol_id_pos;
+    use crate::mpc::TreeNode;
+
+    #[test]
+    fn placement() {
+        for protocol_id in make_random_messages(20).into_keys() {
+            for (cofactor, width) in [(0, 1), (0, 8), (3, 8), (7, 8), (100, 8), (5, 1 << 20)] {
+                assert_eq!(
+                    reference_pos(protocol_id, cofactor, width),
+                    protocol_id_pos(protocol_id, cofactor, width)
+                );
+            }
+        }
+        let protocol_id = ProtocolId::from([0xFF; 32]);
+        assert_eq!(reference_pos(protocol_id, 0, 1 << 31), 0x7FFF_FFFF);
+    }
+
+    #[test]
+    fn differential() {
+        for count in [1, 2, 5, 9, 17] {
+            let msgs = make_random_messages(count);
+            let tree = make_random_tree(&msgs);
+            let block = MerkleBlock::from(&tree);
+            let commitment = tree.commitment_id();
+            assert_eq!(differential_check_block(&block), Ok(commitment));
+
+            for (protocol_id, message) in &msgs {
+                let proof = block.to_merkle_proof(*protocol_id).unwrap();
+                assert_eq!(
+                    differential_check(&proof, *protocol_id, *message),
+                    Ok(Some(commitment))
+                );
+
+                let other = Message::from([0xFF; 32]);
+                let result = differential_check(&proof, *protocol_id, other).unwrap();
+                assert_ne!(result, Some(commitment));
+                assert!(result.is_some());
+
+                let mut partial = block.clone();
+                partial.conceal_except([*protocol_id]).unwrap();
+                assert_eq!(differential_check_block(&partial), Ok(commitment));
+            }
+        }
+    }
+
+    #[test]
+    fn differential_invalid() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+        let (protocol_id, message) = msgs.into_iter().next().unwrap();
+        let proof = block.to_merkle_proof(protocol_id).unwrap();
+
+        let foreign = ProtocolId::from([0xFF; 32]);
+        if protocol_id_pos(foreign, proof.cofactor(), proof.width()) != proof.pos() {
+            assert_eq!(differential_check(&proof, foreign, message), Ok(None));
+        }
+
+        let root = MerkleBlock::with_cross_section(
+            block.depth(),
+            block.cofactor(),
+            block.entropy(),
+            Confined::try_from(vec![TreeNode::ConcealedNode {
+                depth: u5::ZERO,
+                hash: tree.root(),
+            }])
+            .unwrap(),
+        );
+        assert_eq!(reference_block_commitment(&root), Some(tree.commitment_id()));
+
+        let mut nodes = block.cross_section().to_vec();
+        nodes.pop();
+        let truncated = MerkleBlock::with_cross_section(
+            block.depth(),
+            block.cofactor(),
+            block.entropy(),
+            Confined::try_from(nodes).unwrap(),
+        );
+        assert_eq!(reference_block_commitment(&truncated), None);
+    }
+}
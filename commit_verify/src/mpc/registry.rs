@@ -0,0 +1,357 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registry of semantic message types, committed alongside the messages.
+//!
+//! The registry is committed into the same LNPBP-4 tree as a message under a
+//! dedicated [`TypeRegistry::protocol_id`], allowing verifiers to check that
+//! they interpret the committed 32 bytes the same way as the producer.
+
+use amplify::confinement::{self, SmallOrdMap, U8};
+use amplify::{Bytes32, FromSliceError, Wrapper};
+use sha2::Sha256;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::digest::DigestExt;
+use crate::mpc::{
+    Commitment, InvalidProof, LeafNotKnown, MerkleBlock, MerkleProof, Message, ProtocolId,
+    ReservedSlot,
+};
+use crate::{
+    strategies, CommitStrategy, CommitmentId, ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY,
+};
+
+/// Semantic type of a message committed under some protocol, like "bundle id"
+/// or "state root".
+///
+/// Represented by a tagged hash of the type name.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct MessageType(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitStrategy for MessageType {
+    type Strategy = strategies::Strict;
+}
+
+impl MessageType {
+    /// Tag of the hash deriving message types from their names with
+    /// [`MessageType::named`].
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:mtyp:v01#23A";
+
+    /// Constructs message type from its name, failing if the name is longer
+    /// than 255 bytes.
+    pub fn named(name: &str) -> Result<Self, MessageTypeError> {
+        if name.len() > u8::MAX as usize {
+            return Err(MessageTypeError::NameTooLong(name.len()));
+        }
+        let mut engine = Sha256::from_tag(Self::TAG);
+        engine.input_with_len::<U8>(name.as_bytes());
+        Ok(engine.finish().into())
+    }
+
+    pub fn copy_from_slice(slice: &[u8]) -> Result<Self, FromSliceError> {
+        Bytes32::copy_from_slice(slice).map(Self)
+    }
+}
+
+/// Errors checking message types.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum MessageTypeError {
+    #[from]
+    #[display(inner)]
+    LeafNotKnown(LeafNotKnown),
+
+    #[from]
+    #[display(inner)]
+    InvalidProof(InvalidProof),
+
+    /// message type name has {0} bytes, while it must not exceed 255 bytes.
+    NameTooLong(usize),
+
+    /// protocol {0} is absent from the message type registry.
+    Unregistered(ProtocolId),
+
+    /// message type registry doesn't match the one committed in the LNPBP-4
+    /// tree.
+    RegistryMismatch,
+
+    /// message and message type registry proofs convolve to different LNPBP-4
+    /// commitments.
+    CommitmentMismatch,
+
+    /// message under protocol {protocol_id} has type {actual} while {expected}
+    /// was expected.
+    TypeMismatch {
+        protocol_id: ProtocolId,
+        expected: MessageType,
+        actual: MessageType,
+    },
+}
+
+impl ErrorCode for MessageTypeError {
+    fn code(&self) -> &'static str {
+        match self {
+            MessageTypeError::LeafNotKnown(err) => err.code(),
+            MessageTypeError::InvalidProof(err) => err.code(),
+            MessageTypeError::NameTooLong(_) => "mpc.registry.name-too-long",
+            MessageTypeError::Unregistered(_) => "mpc.registry.unregistered",
+            MessageTypeError::RegistryMismatch => "mpc.registry.mismatch",
+            MessageTypeError::CommitmentMismatch => "mpc.registry.commitment-mismatch",
+            MessageTypeError::TypeMismatch { .. } => "mpc.registry.type-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            MessageTypeError::LeafNotKnown(err) => err.params(),
+            MessageTypeError::InvalidProof(err) => err.params(),
+            MessageTypeError::NameTooLong(len) => vec![("len", len.to_string())],
+            MessageTypeError::Unregistered(protocol_id) => {
+                vec![("protocol_id", protocol_id.to_string())]
+            }
+            MessageTypeError::RegistryMismatch | MessageTypeError::CommitmentMismatch => vec![],
+            MessageTypeError::TypeMismatch {
+                protocol_id,
+                expected,
+                actual,
+            } => vec![
+                ("protocol_id", protocol_id.to_string()),
+                ("actual", actual.to_string()),
+                ("expected", expected.to_string()),
+            ],
+        }
+    }
+}
+
+impl ErrorCodes for MessageTypeError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.registry.name-too-long",
+        "mpc.registry.unregistered",
+        "mpc.registry.mismatch",
+        "mpc.registry.commitment-mismatch",
+        "mpc.registry.type-mismatch",
+        "mpc.leaf-not-known",
+        "mpc.invalid-proof",
+    ];
+}
+
+/// Registry of semantic message types for the protocols participating in a
+/// LNPBP-4 commitment.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[derive(CommitEncode)]
+#[commit_encode(crate = crate, strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct TypeRegistry(SmallOrdMap<ProtocolId, MessageType>);
+
+impl StrictSerialize for TypeRegistry {}
+impl StrictDeserialize for TypeRegistry {}
+
+impl CommitmentId for TypeRegistry {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:treg:v01#23A";
+    type Id = Message;
+}
+
+impl ReservedSlot for TypeRegistry {}
+
+impl TypeRegistry {
+    /// Constructs empty registry.
+    pub fn new() -> Self { default!() }
+
+    /// Registers semantic type of the messages under the given protocol.
+    pub fn register(
+        &mut self,
+        protocol_id: ProtocolId,
+        message_type: MessageType,
+    ) -> Result<Option<MessageType>, confinement::Error> {
+        self.0.insert(protocol_id, message_type)
+    }
+
+    /// Returns type of the messages under the given protocol, if registered.
+    pub fn message_type(&self, protocol_id: ProtocolId) -> Option<MessageType> {
+        self.0.get(&protocol_id).copied()
+    }
+}
+
+/// Merkle proof of a message accompanied with the message type registry and
+/// the proof of the registry being committed into the same LNPBP-4 tree.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct TypedProof {
+    /// Proof of the message.
+    proof: MerkleProof,
+
+    /// Message type registry.
+    registry: TypeRegistry,
+
+    /// Proof of the message type registry.
+    registry_proof: MerkleProof,
+}
+
+impl StrictSerialize for TypedProof {}
+impl StrictDeserialize for TypedProof {}
+
+impl TypedProof {
+    /// Extracts typed proof for the message under `protocol_id` from the
+    /// merkle block, which must reveal both that message and the commitment to
+    /// the `registry`.
+    pub fn with(
+        block: &MerkleBlock,
+        protocol_id: ProtocolId,
+        registry: TypeRegistry,
+    ) -> Result<Self, MessageTypeError> {
+        if registry.message_type(protocol_id).is_none() {
+            return Err(MessageTypeError::Unregistered(protocol_id));
+        }
+        let registry_proof = block.to_merkle_proof(TypeRegistry::protocol_id())?;
+        if !registry.verify_block(block) {
+            return Err(MessageTypeError::RegistryMismatch);
+        }
+        let proof = block.to_merkle_proof(protocol_id)?;
+        Ok(TypedProof {
+            proof,
+            registry,
+            registry_proof,
+        })
+    }
+
+    /// Returns semantic type of the message under `protocol_id` according to
+    /// the registry, if it is registered.
+    pub fn message_type(&self, protocol_id: ProtocolId) -> Option<MessageType> {
+        self.registry.message_type(protocol_id)
+    }
+
+    /// Verifies that both the `message` and the message type registry are
+    /// committed into the same LNPBP-4 commitment, and that the registry
+    /// declares the `expected` type for the messages under `protocol_id`.
+    ///
+    /// # Returns
+    ///
+    /// LNPBP-4 commitment the proof convolves to.
+    pub fn verify(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        expected: MessageType,
+    ) -> Result<Commitment, MessageTypeError> {
+        let commitment = self.proof.convolve(protocol_id, message)?;
+        let registry_commitment = self.registry.convolve(&self.registry_proof)?;
+        if commitment != registry_commitment {
+            return Err(MessageTypeError::CommitmentMismatch);
+        }
+        let actual = self
+            .message_type(protocol_id)
+            .ok_or(MessageTypeError::Unregistered(protocol_id))?;
+        if actual != expected {
+            return Err(MessageTypeError::TypeMismatch {
+                protocol_id,
+                expected,
+                actual,
+            });
+        }
+        Ok(commitment)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use amplify::num::u5;
+
+    use super::*;
+    use crate::mpc::tree::test_helpers::make_random_messages;
+    use crate::mpc::{MerkleTree, MultiSource};
+    use crate::TryCommitVerify;
+
+    #[test]
+    fn typed_proof() {
+        let msgs = make_random_messages(5);
+        let mut iter = msgs.iter();
+        let (pid1, msg1) = iter.next().unwrap();
+        let (pid2, msg2) = iter.next().unwrap();
+
+        let bundle_id = MessageType::named("bundle id").unwrap();
+        let state_root = MessageType::named("state root").unwrap();
+        assert_eq!(MessageType::named(&"x".repeat(256)), Err(MessageTypeError::NameTooLong(256)));
+        let mut registry = TypeRegistry::new();
+        registry.register(*pid1, bundle_id).unwrap();
+        registry.register(*pid2, state_root).unwrap();
+
+        let mut src = MultiSource {
+            min_depth: u5::ZERO,
+            messages: Confined::try_from_iter(msgs.iter().map(|(a, b)| (*a, *b))).unwrap(),
+            static_entropy: None,
+        };
+        registry.commit_into(&mut src).unwrap();
+        let tree = MerkleTree::try_commit(&src).unwrap();
+        let block = MerkleBlock::from(&tree);
+
+        let proof = TypedProof::with(&block, *pid1, registry.clone()).unwrap();
+        assert_eq!(proof.verify(*pid1, *msg1, bundle_id), Ok(tree.commitment_id()));
+        assert_eq!(
+            proof.verify(*pid1, *msg1, state_root),
+            Err(MessageTypeError::TypeMismatch {
+                protocol_id: *pid1,
+                expected: state_root,
+                actual: bundle_id,
+            })
+        );
+        assert_eq!(
+            proof.verify(*pid1, *msg2, bundle_id),
+            Err(MessageTypeError::CommitmentMismatch)
+        );
+
+        let (pid3, _) = iter.next().unwrap();
+        assert_eq!(
+            TypedProof::with(&block, *pid3, registry.clone()),
+            Err(MessageTypeError::Unregistered(*pid3))
+        );
+
+        registry.register(*pid3, state_root).unwrap();
+        assert_eq!(
+            TypedProof::with(&block, *pid3, registry),
+            Err(MessageTypeError::RegistryMismatch)
+        );
+    }
+}
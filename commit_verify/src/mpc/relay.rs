@@ -0,0 +1,230 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wire types for the protocol between clients and relays (aggregation
+//! services), which collect messages from multiple clients into a single
+//! LNPBP-4 commitment and publish it.
+
+use amplify::confinement::LargeVec;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::mpc::{
+    Commitment, InvalidProof, LeafNotKnown, MerkleBlock, MerkleProof, Message, MultiSource,
+    ProtocolId,
+};
+use crate::LIB_NAME_COMMIT_VERIFY;
+
+/// Request to commit a message under a protocol, submitted by a client to a
+/// relay.
+#[derive(Getters, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct RelayRequest {
+    /// Protocol under which the message has to be committed.
+    #[getter(as_copy)]
+    protocol_id: ProtocolId,
+
+    /// Message to commit.
+    #[getter(as_copy)]
+    message: Message,
+}
+
+impl StrictSerialize for RelayRequest {}
+impl StrictDeserialize for RelayRequest {}
+
+impl RelayRequest {
+    /// Constructs request for committing `message` under `protocol_id`.
+    pub fn new(protocol_id: ProtocolId, message: Message) -> Self {
+        RelayRequest {
+            protocol_id,
+            message,
+        }
+    }
+
+    /// Adds the requested message to the multi-message source of the relay,
+    /// failing with [`RelayRejection::ProtocolConflict`] if the source
+    /// already has a different message under the same protocol.
+    pub fn commit_into(&self, source: &mut MultiSource) -> Result<(), RelayRejection> {
+        match source.messages.get(&self.protocol_id) {
+            Some(message) if *message == self.message => Ok(()),
+            Some(_) => Err(RelayRejection::ProtocolConflict),
+            None => source
+                .messages
+                .insert(self.protocol_id, self.message)
+                .map(|_| ())
+                .map_err(|_| RelayRejection::QueueFull),
+        }
+    }
+}
+
+/// Reasons for a relay to reject a request.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+#[repr(u8)]
+pub enum RelayRejection {
+    /// relay is temporarily unavailable.
+    #[strict_type(dumb)]
+    Unavailable = 0,
+
+    /// a different message under the same protocol is already scheduled for
+    /// the commitment.
+    ProtocolConflict = 1,
+
+    /// relay can't accept more messages for the commitment.
+    QueueFull = 2,
+
+    /// client is not authorized to use the relay.
+    Unauthorized = 3,
+}
+
+/// Receipt returned by a relay once the commitment containing the requested
+/// message is published.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct RelayReceipt {
+    /// Proof of the message inside the published LNPBP-4 commitment.
+    proof: MerkleProof,
+
+    /// Medium-specific proof of the commitment publication (anchor), which is
+    /// opaque to this library.
+    anchor: LargeVec<u8>,
+}
+
+impl StrictSerialize for RelayReceipt {}
+impl StrictDeserialize for RelayReceipt {}
+
+impl RelayReceipt {
+    /// Constructs receipt for the request from the published merkle block and
+    /// the serialized anchor.
+    pub fn with(
+        block: &MerkleBlock,
+        request: &RelayRequest,
+        anchor: LargeVec<u8>,
+    ) -> Result<Self, LeafNotKnown> {
+        Ok(RelayReceipt {
+            proof: block.to_merkle_proof(request.protocol_id)?,
+            anchor,
+        })
+    }
+
+    /// Computes LNPBP-4 commitment, which must be published by the anchor,
+    /// for the requested message.
+    pub fn commitment(&self, request: &RelayRequest) -> Result<Commitment, InvalidProof> {
+        self.proof.convolve(request.protocol_id, request.message)
+    }
+}
+
+/// Response of a relay to a [`RelayRequest`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictDumb, StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY, tags = order, dumb = { RelayResponse::Pending })]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum RelayResponse {
+    /// Request is accepted, but the commitment is not published yet.
+    Pending,
+
+    /// Commitment containing the requested message is published.
+    Anchored(RelayReceipt),
+
+    /// Request is rejected.
+    Rejected(RelayRejection),
+}
+
+impl StrictSerialize for RelayResponse {}
+impl StrictDeserialize for RelayResponse {}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+
+    use super::*;
+    use crate::mpc::test_helpers::make_random_messages;
+    use crate::mpc::MerkleTree;
+    use crate::{CommitmentId, TryCommitVerify};
+
+    #[test]
+    fn relay_flow() {
+        let requests = make_random_messages(7)
+            .into_iter()
+            .map(|(protocol_id, message)| RelayRequest::new(protocol_id, message))
+            .collect::<Vec<_>>();
+
+        let mut source = MultiSource::with_static_entropy(0x2023);
+        for request in &requests {
+            request.commit_into(&mut source).unwrap();
+            request.commit_into(&mut source).unwrap();
+        }
+        let conflicting = RelayRequest::new(requests[0].protocol_id(), Message::from([0u8; 32]));
+        assert_eq!(conflicting.commit_into(&mut source), Err(RelayRejection::ProtocolConflict));
+
+        let tree = MerkleTree::try_commit(&source).unwrap();
+        let block = MerkleBlock::from(&tree);
+        let anchor = Confined::try_from(b"anchor".to_vec()).unwrap();
+        for request in &requests {
+            let data = request
+                .to_strict_serialized::<{ u16::MAX as usize }>()
+                .unwrap();
+            let request =
+                RelayRequest::from_strict_serialized::<{ u16::MAX as usize }>(data).unwrap();
+
+            let receipt = RelayReceipt::with(&block, &request, anchor.clone()).unwrap();
+            assert_eq!(receipt.commitment(&request), Ok(tree.commitment_id()));
+            assert_eq!(receipt.anchor().as_slice(), b"anchor");
+
+            let response = RelayResponse::Anchored(receipt);
+            let data = response
+                .to_strict_serialized::<{ u16::MAX as usize }>()
+                .unwrap();
+            let decoded =
+                RelayResponse::from_strict_serialized::<{ u16::MAX as usize }>(data).unwrap();
+            assert_eq!(decoded, response);
+        }
+
+        let rejection = RelayResponse::Rejected(RelayRejection::QueueFull);
+        let data = rejection
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .unwrap();
+        let decoded = RelayResponse::from_strict_serialized::<{ u16::MAX as usize }>(data).unwrap();
+        assert_eq!(decoded, rejection);
+    }
+}
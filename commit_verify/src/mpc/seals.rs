@@ -0,0 +1,231 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Self-describing anchors committing to the seals closed by their witness.
+//!
+//! A witness (like a bitcoin transaction) may close seals of many protocols,
+//! while each protocol verifies only its own seals. A producer may commit the
+//! whole set of the closed seals into the tree under a reserved
+//! [`ClosedSeals::protocol_id`], tying the seal closings and the message
+//! commitments into a single anchor. Verifiers knowing which seals the witness
+//! closes can then detect anchors omitting some of them.
+
+use amplify::confinement::{self, MediumOrdSet};
+use amplify::Bytes32;
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::mpc::{Commitment, InvalidProof, MerkleBlock, MerkleProof, Message, ReservedSlot};
+use crate::{CommitEncode, CommitmentId, ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+/// Errors verifying [`ClosedSeals`] commitment.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ClosedSealsError {
+    /// the anchor doesn't reveal the closed seals leaf.
+    Absent,
+
+    /// the anchor commits to a different set of closed seals.
+    Mismatch,
+
+    #[from]
+    #[display(inner)]
+    InvalidProof(InvalidProof),
+
+    /// closed seals are committed in {actual} instead of the expected
+    /// commitment {expected}.
+    CommitmentMismatch {
+        expected: Commitment,
+        actual: Commitment,
+    },
+
+    /// seal {0} is closed by the witness, but is absent from the anchor.
+    Omitted(Bytes32),
+}
+
+impl ErrorCode for ClosedSealsError {
+    fn code(&self) -> &'static str {
+        match self {
+            ClosedSealsError::Absent => "mpc.seals.absent",
+            ClosedSealsError::Mismatch => "mpc.seals.mismatch",
+            ClosedSealsError::InvalidProof(err) => err.code(),
+            ClosedSealsError::CommitmentMismatch { .. } => "mpc.seals.commitment-mismatch",
+            ClosedSealsError::Omitted(_) => "mpc.seals.omitted",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ClosedSealsError::Absent | ClosedSealsError::Mismatch => vec![],
+            ClosedSealsError::InvalidProof(err) => err.params(),
+            ClosedSealsError::CommitmentMismatch { expected, actual } => {
+                vec![("actual", actual.to_string()), ("expected", expected.to_string())]
+            }
+            ClosedSealsError::Omitted(seal) => vec![("seal", seal.to_string())],
+        }
+    }
+}
+
+impl ErrorCodes for ClosedSealsError {
+    const CODES: &'static [&'static str] = &[
+        "mpc.seals.absent",
+        "mpc.seals.mismatch",
+        "mpc.seals.commitment-mismatch",
+        "mpc.seals.omitted",
+        "mpc.invalid-proof",
+    ];
+}
+
+/// Set of the seals closed by a witness, identified by their hashes.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[derive(CommitEncode)]
+#[commit_encode(crate = crate, strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ClosedSeals {
+    /// Hashes of the closed seal definitions (see [`ClosedSeals::seal_hash`]).
+    seals: MediumOrdSet<Bytes32>,
+}
+
+impl CommitmentId for ClosedSeals {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:seal:v01#23A";
+    type Id = Message;
+}
+
+impl ReservedSlot for ClosedSeals {}
+
+impl ClosedSeals {
+    /// Tag of the hash of the seal definitions computed by
+    /// [`ClosedSeals::seal_hash`].
+    pub const SEAL_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:sdef:v01#23A";
+
+    /// Constructs set from the hashes of the closed seals, failing if their
+    /// number exceeds [`MediumOrdSet`] limit.
+    pub fn new(seals: impl IntoIterator<Item = Bytes32>) -> Result<Self, confinement::Error> {
+        Ok(ClosedSeals {
+            seals: MediumOrdSet::try_from_iter(seals)?,
+        })
+    }
+
+    /// Computes hash of a seal definition.
+    pub fn seal_hash(seal: &impl CommitEncode) -> Bytes32 {
+        let mut engine = Sha256::from_tag(Self::SEAL_TAG);
+        seal.commit_encode(&mut engine);
+        engine.finish().into()
+    }
+
+    /// Detects whether the seal with the given hash is in the set.
+    pub fn contains(&self, seal_hash: &Bytes32) -> bool { self.seals.contains(seal_hash) }
+
+    /// Verifies that the `proof` commits to the set of the closed seals within
+    /// the anchor `commitment`.
+    pub fn verify_proof(
+        &self,
+        proof: &MerkleProof,
+        commitment: Commitment,
+    ) -> Result<(), ClosedSealsError> {
+        let actual = self.convolve(proof)?;
+        if actual != commitment {
+            return Err(ClosedSealsError::CommitmentMismatch {
+                expected: commitment,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that all the seals closed by the witness are present in the set.
+    pub fn check_complete(
+        &self,
+        closed: impl IntoIterator<Item = Bytes32>,
+    ) -> Result<(), ClosedSealsError> {
+        match closed
+            .into_iter()
+            .find(|seal_hash| !self.contains(seal_hash))
+        {
+            Some(seal_hash) => Err(ClosedSealsError::Omitted(seal_hash)),
+            None => Ok(()),
+        }
+    }
+
+    /// Verifies that the anchor `block` reveals the set of the closed seals,
+    /// and that the set contains all the seals `closed` by the witness.
+    pub fn verify_anchor(
+        &self,
+        block: &MerkleBlock,
+        closed: impl IntoIterator<Item = Bytes32>,
+    ) -> Result<(), ClosedSealsError> {
+        match Self::message(block) {
+            None => return Err(ClosedSealsError::Absent),
+            Some(message) if message != self.commitment_id() => {
+                return Err(ClosedSealsError::Mismatch)
+            }
+            Some(_) => {}
+        }
+        self.check_complete(closed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::make_random_messages;
+    use crate::mpc::{MerkleTree, MultiSource};
+    use crate::TryCommitVerify;
+
+    #[test]
+    fn closed_seals() {
+        let seal1 = ClosedSeals::seal_hash(&1u64);
+        let seal2 = ClosedSeals::seal_hash(&2u64);
+        let seal3 = ClosedSeals::seal_hash(&3u64);
+        let seals = ClosedSeals::new([seal1, seal2]).unwrap();
+
+        let mut source = MultiSource::default();
+        for (protocol_id, message) in make_random_messages(5) {
+            source.messages.insert(protocol_id, message).unwrap();
+        }
+        seals.commit_into(&mut source).unwrap();
+        let tree = MerkleTree::try_commit(&source).unwrap();
+        assert!(seals.verify(&tree));
+
+        let mut block = MerkleBlock::from(&tree);
+        assert_eq!(seals.verify_anchor(&block, [seal2, seal1]), Ok(()));
+        assert_eq!(
+            seals.verify_anchor(&block, [seal1, seal3]),
+            Err(ClosedSealsError::Omitted(seal3))
+        );
+        let other = ClosedSeals::new([seal1]).unwrap();
+        assert!(!other.verify(&tree));
+        assert_eq!(other.verify_anchor(&block, [seal1]), Err(ClosedSealsError::Mismatch));
+
+        let proof = block.to_merkle_proof(ClosedSeals::protocol_id()).unwrap();
+        assert_eq!(seals.verify_proof(&proof, tree.commitment_id()), Ok(()));
+        assert!(other.verify_proof(&proof, tree.commitment_id()).is_err());
+
+        block.prune(|protocol_id| protocol_id != ClosedSeals::protocol_id());
+        assert_eq!(seals.verify_anchor(&block, [seal1]), Err(ClosedSealsError::Absent));
+    }
+}
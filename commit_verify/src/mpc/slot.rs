@@ -0,0 +1,102 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Leaves reserved in LNPBP-4 trees for the data describing the commitment
+//! itself, rather than messages of some protocol.
+//!
+//! Producers may commit auxiliary data (like the software descriptor, the
+//! entropy beacon round or the message type registry) into the same tree as
+//! the protocol messages. Each kind of such data is placed under its own
+//! protocol id, derived from the [`CommitmentId::TAG`] of the data type with
+//! [`RESERVED_SLOT_TAG`], such that the slot id never coincides with the
+//! commitment ids of the data.
+
+use amplify::confinement;
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::mpc::{
+    Commitment, InvalidProof, MerkleBlock, MerkleProof, MerkleTree, Message, MultiSource,
+    ProtocolId,
+};
+use crate::CommitmentId;
+
+/// Tag of the hash deriving protocol ids of the reserved slots.
+pub const RESERVED_SLOT_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:slot:v01#23A";
+
+/// Data committed into LNPBP-4 trees under a protocol id reserved for them.
+pub trait ReservedSlot: CommitmentId<Id = Message> {
+    /// Protocol id reserved for the data in LNPBP-4 trees.
+    fn protocol_id() -> ProtocolId {
+        let mut engine = Sha256::from_tag(RESERVED_SLOT_TAG);
+        engine.input_raw(&Self::TAG);
+        engine.finish().into()
+    }
+
+    /// Extracts the message of the reserved leaf from the block, if the leaf is
+    /// present and revealed in it.
+    fn message(block: &MerkleBlock) -> Option<Message> { block.known_message(Self::protocol_id()) }
+
+    /// Commits the data into the multi-message source.
+    fn commit_into(&self, source: &mut MultiSource) -> Result<(), confinement::Error> {
+        source
+            .messages
+            .insert(Self::protocol_id(), self.commitment_id())?;
+        Ok(())
+    }
+
+    /// Checks whether the tree commits to the data.
+    fn verify(&self, tree: &MerkleTree) -> bool {
+        tree.messages.get(&Self::protocol_id()) == Some(&self.commitment_id())
+    }
+
+    /// Checks whether the block reveals the reserved leaf committing to the
+    /// data.
+    fn verify_block(&self, block: &MerkleBlock) -> bool {
+        Self::message(block) == Some(self.commitment_id())
+    }
+
+    /// Convolves the `proof` of the reserved leaf with the data, producing
+    /// [`Commitment`].
+    fn convolve(&self, proof: &MerkleProof) -> Result<Commitment, InvalidProof> {
+        proof.convolve(Self::protocol_id(), self.commitment_id())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::mpc::{BeaconEntropy, ClosedSeals, ProducerInfo, TypeRegistry};
+
+    #[test]
+    fn slot_ids() {
+        let ids = BTreeSet::from([
+            ProducerInfo::protocol_id(),
+            BeaconEntropy::protocol_id(),
+            TypeRegistry::protocol_id(),
+            ClosedSeals::protocol_id(),
+        ]);
+        assert_eq!(ids.len(), 4);
+        assert!(!ids.contains(&ProtocolId::from(Sha256::from_tag(ProducerInfo::TAG).finish())));
+    }
+}
@@ -0,0 +1,296 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tombstone annotations of disputed leaves in LNPBP-4 blocks.
+//!
+//! Dispute workflows may need to mark some of the revealed leaves of a
+//! [`MerkleBlock`] as disputed or revoked. Tombstones are application metadata
+//! travelling together with the block: they are not committed to and never
+//! affect the block commitment or the verification of the proofs extracted
+//! from it.
+
+use amplify::confinement::{SmallOrdMap, TinyString};
+use strict_encoding::{DecodeError, StrictDecode, StrictDeserialize, StrictSerialize, TypedRead};
+
+use crate::mpc::{Commitment, LeafNotKnown, MerkleBlock, ProtocolId};
+use crate::{CommitmentId, ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+/// Errors tombstoning leaves of [`AnnotatedBlock`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum TombstoneError {
+    #[from]
+    #[display(inner)]
+    LeafNotKnown(LeafNotKnown),
+
+    /// tombstone reason has {0} bytes, while it must not exceed 255 bytes.
+    ReasonTooLong(usize),
+
+    /// block already has the maximal number of tombstones.
+    TooManyTombstones,
+}
+
+impl ErrorCode for TombstoneError {
+    fn code(&self) -> &'static str {
+        match self {
+            TombstoneError::LeafNotKnown(err) => err.code(),
+            TombstoneError::ReasonTooLong(_) => "mpc.tombstone.reason-too-long",
+            TombstoneError::TooManyTombstones => "mpc.tombstone.too-many",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            TombstoneError::LeafNotKnown(err) => err.params(),
+            TombstoneError::ReasonTooLong(len) => vec![("len", len.to_string())],
+            TombstoneError::TooManyTombstones => vec![],
+        }
+    }
+}
+
+impl ErrorCodes for TombstoneError {
+    const CODES: &'static [&'static str] =
+        &["mpc.tombstone.reason-too-long", "mpc.tombstone.too-many", "mpc.leaf-not-known"];
+}
+
+/// [`MerkleBlock`] annotated with tombstones for some of its revealed leaves.
+///
+/// Tombstones may annotate only the revealed leaves, which is checked when the
+/// annotated block is deserialized (see [`AnnotatedBlock::validate`]).
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase", try_from = "AnnotatedBlockUnchecked")
+)]
+pub struct AnnotatedBlock {
+    /// Annotated merkle block.
+    block: MerkleBlock,
+
+    /// Tombstoned protocols with the application-specific reason.
+    tombstones: SmallOrdMap<ProtocolId, TinyString>,
+}
+
+impl StrictDecode for AnnotatedBlock {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let annotated = AnnotatedBlock {
+            block: MerkleBlock::strict_decode(reader)?,
+            tombstones: StrictDecode::strict_decode(reader)?,
+        };
+        annotated
+            .validate()
+            .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))?;
+        Ok(annotated)
+    }
+}
+
+impl StrictSerialize for AnnotatedBlock {}
+impl StrictDeserialize for AnnotatedBlock {}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+struct AnnotatedBlockUnchecked {
+    block: MerkleBlock,
+    tombstones: SmallOrdMap<ProtocolId, TinyString>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<AnnotatedBlockUnchecked> for AnnotatedBlock {
+    type Error = LeafNotKnown;
+
+    fn try_from(unchecked: AnnotatedBlockUnchecked) -> Result<Self, Self::Error> {
+        let annotated = AnnotatedBlock {
+            block: unchecked.block,
+            tombstones: unchecked.tombstones,
+        };
+        annotated.validate()?;
+        Ok(annotated)
+    }
+}
+
+impl From<MerkleBlock> for AnnotatedBlock {
+    fn from(block: MerkleBlock) -> Self {
+        AnnotatedBlock {
+            block,
+            tombstones: empty!(),
+        }
+    }
+}
+
+impl AnnotatedBlock {
+    /// Validates that all tombstoned leaves are revealed in the block.
+    ///
+    /// Blocks annotated with [`AnnotatedBlock::tombstone`] are always valid;
+    /// the check is intended for the annotated blocks constructed from
+    /// untrusted data, and is performed on their deserialization.
+    ///
+    /// # Error
+    ///
+    /// Errors with the first tombstoned protocol which leaf is not revealed.
+    pub fn validate(&self) -> Result<(), LeafNotKnown> {
+        match self
+            .tombstones
+            .keys()
+            .find(|protocol_id| self.block.known_message(**protocol_id).is_none())
+        {
+            Some(protocol_id) => Err(LeafNotKnown(*protocol_id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns commitment of the underlying block, which is not affected by
+    /// the tombstones.
+    pub fn commitment_id(&self) -> Commitment { self.block.commitment_id() }
+
+    /// Marks the revealed leaf under `protocol_id` as tombstoned with the
+    /// given `reason`, replacing the previous reason, if any.
+    ///
+    /// # Error
+    ///
+    /// Errors if the leaf is not revealed in the block, the `reason` exceeds
+    /// 255 bytes or the block already has the maximal number of tombstones.
+    pub fn tombstone(
+        &mut self,
+        protocol_id: ProtocolId,
+        reason: &str,
+    ) -> Result<(), TombstoneError> {
+        self.block
+            .known_message(protocol_id)
+            .ok_or(LeafNotKnown(protocol_id))?;
+        let reason = TinyString::try_from(reason.to_owned())
+            .map_err(|_| TombstoneError::ReasonTooLong(reason.len()))?;
+        self.tombstones
+            .insert(protocol_id, reason)
+            .map_err(|_| TombstoneError::TooManyTombstones)?;
+        Ok(())
+    }
+
+    /// Removes tombstone from the leaf under `protocol_id`, returning its
+    /// reason.
+    pub fn revive(&mut self, protocol_id: ProtocolId) -> Option<TinyString> {
+        self.tombstones
+            .remove(&protocol_id)
+            .expect("removal never breaks the minimal length")
+    }
+
+    /// Returns the reason of the tombstone for the leaf under `protocol_id`,
+    /// if the leaf is tombstoned.
+    pub fn tombstone_reason(&self, protocol_id: ProtocolId) -> Option<&str> {
+        self.tombstones
+            .get(&protocol_id)
+            .map(|reason| reason.as_str())
+    }
+
+    /// Detects whether the leaf under `protocol_id` is tombstoned.
+    pub fn is_tombstoned(&self, protocol_id: ProtocolId) -> bool {
+        self.tombstones.contains_key(&protocol_id)
+    }
+
+    /// Removes tombstones of the leaves which are not revealed in the block
+    /// anymore (for instance after [`MerkleBlock::conceal_except`]), returning
+    /// the number of the removed tombstones.
+    pub fn prune(&mut self) -> usize {
+        let before = self.tombstones.len();
+        let tombstones = self
+            .tombstones
+            .iter()
+            .filter(|(protocol_id, _)| self.block.known_message(**protocol_id).is_some())
+            .map(|(protocol_id, reason)| (*protocol_id, reason.clone()));
+        self.tombstones =
+            SmallOrdMap::try_from_iter(tombstones).expect("number of tombstones can only decrease");
+        before - self.tombstones.len()
+    }
+
+    /// Removes all tombstones, returning the underlying block.
+    pub fn strip(self) -> MerkleBlock { self.block }
+
+    /// Provides mutable access to the underlying block.
+    ///
+    /// Tombstones of the leaves concealed through it must be removed with
+    /// [`AnnotatedBlock::prune`].
+    pub fn block_mut(&mut self) -> &mut MerkleBlock { &mut self.block }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+
+    #[test]
+    fn tombstones() {
+        let msgs = make_random_messages(7);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+        let commitment = block.commitment_id();
+        let mut pids = msgs.keys().copied();
+        let (disputed, kept) = (pids.next().unwrap(), pids.next().unwrap());
+
+        let mut annotated = AnnotatedBlock::from(block.clone());
+        annotated.tombstone(disputed, "double spend").unwrap();
+        assert!(annotated.is_tombstoned(disputed));
+        assert!(!annotated.is_tombstoned(kept));
+        assert_eq!(annotated.tombstone_reason(disputed), Some("double spend"));
+        assert_eq!(annotated.commitment_id(), commitment);
+        assert_eq!(
+            annotated.tombstone(ProtocolId::from([0xFF; 32]), "unknown"),
+            Err(TombstoneError::LeafNotKnown(LeafNotKnown(ProtocolId::from([0xFF; 32]))))
+        );
+        assert_eq!(
+            annotated.tombstone(kept, &"x".repeat(256)),
+            Err(TombstoneError::ReasonTooLong(256))
+        );
+        assert!(!annotated.is_tombstoned(kept));
+
+        annotated.block_mut().conceal_except([kept]).unwrap();
+        assert_eq!(annotated.prune(), 1);
+        assert!(!annotated.is_tombstoned(disputed));
+        assert_eq!(annotated.commitment_id(), commitment);
+
+        annotated.tombstone(kept, "revoked").unwrap();
+        assert_eq!(annotated.revive(kept).unwrap().as_str(), "revoked");
+        assert_eq!(annotated.clone().strip().commitment_id(), commitment);
+    }
+
+    #[test]
+    fn decode() {
+        let msgs = make_random_messages(5);
+        let tree = make_random_tree(&msgs);
+        let mut pids = msgs.keys().copied();
+        let (disputed, kept) = (pids.next().unwrap(), pids.next().unwrap());
+
+        let mut annotated = AnnotatedBlock::from(MerkleBlock::from(&tree));
+        annotated.tombstone(disputed, "double spend").unwrap();
+        assert_eq!(annotated.validate(), Ok(()));
+        let data = annotated.to_strict_serialized::<U32>().unwrap();
+        assert_eq!(AnnotatedBlock::from_strict_serialized::<U32>(data).unwrap(), annotated);
+
+        // Concealing the leaf without pruning leaves a dangling tombstone
+        annotated.block_mut().conceal_except([kept]).unwrap();
+        assert_eq!(annotated.validate(), Err(LeafNotKnown(disputed)));
+        let data = annotated.to_strict_serialized::<U32>().unwrap();
+        assert!(AnnotatedBlock::from_strict_serialized::<U32>(data).is_err());
+    }
+}
@@ -19,21 +19,36 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amplify::confinement::{MediumOrdMap, SmallVec};
+use std::collections::BTreeMap;
+
+use amplify::confinement::{Confined, SmallVec};
 use amplify::num::{u256, u5};
-use amplify::Wrapper;
+use amplify::{Bytes32, Wrapper};
+use sha2::Sha256;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
 
 pub use self::commit::Error;
+use crate::digest::DigestExt;
 use crate::merkle::MerkleNode;
 use crate::mpc::atoms::Leaf;
-use crate::mpc::{Commitment, Message, MessageMap, Proof, ProtocolId, MERKLE_LNPBP4_TAG};
-use crate::{CommitmentId, Conceal, LIB_NAME_COMMIT_VERIFY};
+use crate::mpc::limits::{MAX_MESSAGES, ORDERED_MAP_LIMIT};
+use crate::mpc::{
+    Commitment, Message, MessageMap, Precommitment, Proof, ProtocolId, MERKLE_LNPBP4_TAG,
+};
+use crate::{
+    CommitEncode, CommitmentId, Conceal, ErrorCode, ErrorCodes, VerifiableProof, VerifyError,
+    LIB_NAME_COMMIT_VERIFY,
+};
 
 /// Number of cofactor variants tried before moving to the next tree depth.
 #[allow(dead_code)]
 const COFACTOR_ATTEMPTS: u16 = 500;
 
-type OrderedMap = MediumOrdMap<u32, (ProtocolId, Message)>;
+/// Map from tree leaf positions to the protocols and messages placed at them.
+///
+/// Like [`MessageMap`], it is strictly ordered by its keys (leaf positions)
+/// both in strict and commit encodings.
+type OrderedMap = Confined<BTreeMap<u32, (ProtocolId, Message)>, 0, ORDERED_MAP_LIMIT>;
 
 /// Complete information about LNPBP-4 merkle tree.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -60,19 +75,30 @@ pub struct MerkleTree {
 
 impl Proof for MerkleTree {}
 
+impl StrictSerialize for MerkleTree {}
+impl StrictDeserialize for MerkleTree {}
+
 impl CommitmentId for MerkleTree {
     const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:tree:v01#23A";
     type Id = Commitment;
 }
 
+impl VerifiableProof for MerkleTree {
+    /// Verifies that the tree commits to the `commitment`.
+    fn verify_dyn(&self, commitment: &[u8; 32]) -> Result<(), VerifyError> {
+        if self.commitment_id() != Commitment::from(*commitment) {
+            return Err(VerifyError::InvalidCommitment);
+        }
+        Ok(())
+    }
+}
+
 impl MerkleTree {
+    /// Tag of the hash computed by [`MerkleTree::structural_hash`].
+    pub const STRUCTURE_TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:strc:v01#23A";
+
     pub fn root(&self) -> MerkleNode {
-        let iter = (0..self.width()).map(|pos| {
-            self.map
-                .get(&pos)
-                .map(|(protocol, msg)| Leaf::inhabited(*protocol, *msg))
-                .unwrap_or_else(|| Leaf::entropy(self.entropy, pos))
-        });
+        let iter = (0..self.width()).map(|pos| self.leaf(pos).expect("position within width"));
         let leaves = SmallVec::try_from_iter(iter).expect("u16-bound size");
         MerkleNode::merklize(MERKLE_LNPBP4_TAG.to_be_bytes(), &leaves)
     }
@@ -88,6 +114,8 @@ mod commit {
     use std::collections::BTreeMap;
 
     use amplify::confinement::Confined;
+    #[cfg(feature = "rand")]
+    use rand::{CryptoRng, RngCore};
 
     use super::*;
     use crate::mpc::MultiSource;
@@ -111,6 +139,30 @@ mod commit {
         CantFitInMaxSlots(usize),
     }
 
+    impl ErrorCode for Error {
+        fn code(&self) -> &'static str {
+            match self {
+                Error::Empty => "mpc.empty",
+                Error::TooManyMessages(_) => "mpc.too-many-messages",
+                Error::CantFitInMaxSlots(_) => "mpc.cant-fit",
+            }
+        }
+
+        fn params(&self) -> Vec<(&'static str, String)> {
+            match self {
+                Error::Empty => vec![],
+                Error::TooManyMessages(count) | Error::CantFitInMaxSlots(count) => {
+                    vec![("messages", count.to_string())]
+                }
+            }
+        }
+    }
+
+    impl ErrorCodes for Error {
+        const CODES: &'static [&'static str] =
+            &["mpc.empty", "mpc.too-many-messages", "mpc.cant-fit"];
+    }
+
     /// # Panics
     ///
     /// Panics if the crate is compiled without `rand` feature enabled and the
@@ -120,26 +172,51 @@ mod commit {
 
         fn try_commit(source: &MultiSource) -> Result<Self, Error> {
             #[cfg(feature = "rand")]
-            use rand::{thread_rng, RngCore};
+            use rand::thread_rng;
+
+            #[cfg(feature = "rand")]
+            let entropy = || {
+                source
+                    .static_entropy
+                    .unwrap_or_else(|| thread_rng().next_u64())
+            };
+            #[cfg(not(feature = "rand"))]
+            let entropy = || {
+                source.static_entropy.expect(
+                    "use must use `rand` feature for crate commit_verify if you do not provide \
+                     with a static entropy information in `MultiSource`",
+                )
+            };
+            Self::place(source, entropy)
+        }
+    }
+
+    impl MerkleTree {
+        /// Constructs tree like [`MerkleTree::try_commit`], taking entropy for
+        /// the placeholders from the provided `rng` instead of the thread
+        /// random number generator, unless the source has a static entropy.
+        ///
+        /// Allows to use deterministic random number generators in simulation
+        /// testing and external entropy sources (like HSMs).
+        #[cfg(feature = "rand")]
+        pub fn try_commit_with_rng(
+            source: &MultiSource,
+            rng: &mut (impl RngCore + CryptoRng),
+        ) -> Result<Self, Error> {
+            Self::place(source, || source.static_entropy.unwrap_or_else(|| rng.next_u64()))
+        }
 
+        fn place(source: &MultiSource, entropy: impl FnOnce() -> u64) -> Result<Self, Error> {
             let msg_count = source.messages.len();
 
             if source.min_depth == u5::ZERO && source.messages.is_empty() {
                 return Err(Error::Empty);
             }
-            if msg_count > 2usize.pow(u5::MAX.to_u8() as u32) {
+            if msg_count > MAX_MESSAGES {
                 return Err(Error::TooManyMessages(msg_count));
             }
 
-            #[cfg(feature = "rand")]
-            let entropy = source
-                .static_entropy
-                .unwrap_or_else(|| thread_rng().next_u64());
-            #[cfg(not(feature = "rand"))]
-            let entropy = source.static_entropy.expect(
-                "use must use `rand` feature for crate commit_verify if you do not provide with a \
-                 static entropy information in `MultiSource`",
-            );
+            let entropy = entropy();
 
             let mut map = BTreeMap::<u32, (ProtocolId, Message)>::new();
 
@@ -174,6 +251,62 @@ mod commit {
     }
 }
 
+/// Errors detected by [`MerkleTree::validate`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TreeValidationError {
+    /// tree contains {0} messages, but only {1} of them are placed into the
+    /// tree leaves.
+    CountMismatch(usize, usize),
+
+    /// message for the protocol {protocol_id} is placed at position {actual}
+    /// instead of {expected}.
+    Misplaced {
+        protocol_id: ProtocolId,
+        expected: u32,
+        actual: u32,
+    },
+
+    /// message placed into the tree for the protocol {0} doesn't match the
+    /// message committed under the protocol.
+    MessageMismatch(ProtocolId),
+}
+
+impl ErrorCode for TreeValidationError {
+    fn code(&self) -> &'static str {
+        match self {
+            TreeValidationError::CountMismatch(..) => "mpc.tree.count-mismatch",
+            TreeValidationError::Misplaced { .. } => "mpc.tree.misplaced",
+            TreeValidationError::MessageMismatch(_) => "mpc.tree.message-mismatch",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            TreeValidationError::CountMismatch(messages, placed) => {
+                vec![("messages", messages.to_string()), ("placed", placed.to_string())]
+            }
+            TreeValidationError::Misplaced {
+                protocol_id,
+                expected,
+                actual,
+            } => vec![
+                ("protocol_id", protocol_id.to_string()),
+                ("actual", actual.to_string()),
+                ("expected", expected.to_string()),
+            ],
+            TreeValidationError::MessageMismatch(protocol_id) => {
+                vec![("protocol_id", protocol_id.to_string())]
+            }
+        }
+    }
+}
+
+impl ErrorCodes for TreeValidationError {
+    const CODES: &'static [&'static str] =
+        &["mpc.tree.count-mismatch", "mpc.tree.misplaced", "mpc.tree.message-mismatch"];
+}
+
 pub(super) fn protocol_id_pos(protocol_id: ProtocolId, cofactor: u16, width: u32) -> u32 {
     debug_assert_ne!(width, 0);
     let rem = u256::from_le_bytes((*protocol_id).into_inner()) %
@@ -193,6 +326,86 @@ impl MerkleTree {
     pub fn depth(&self) -> u5 { self.depth }
 
     pub fn entropy(&self) -> u64 { self.entropy }
+
+    /// Detects whether the tree placeholders are filled with entropy derived
+    /// from the tree messages (see [`crate::mpc::MultiSource::deterministic`]).
+    pub fn is_deterministic(&self) -> bool { self.entropy == self.precommitment().fill_entropy() }
+
+    /// Returns leaf at the position `pos`, or `None` if the position is
+    /// outside of the tree width.
+    pub fn leaf(&self, pos: u32) -> Option<Leaf> {
+        if pos >= self.width() {
+            return None;
+        }
+        Some(
+            self.map
+                .get(&pos)
+                .map(|(protocol, msg)| Leaf::inhabited(*protocol, *msg))
+                .unwrap_or_else(|| Leaf::entropy(self.entropy, pos)),
+        )
+    }
+
+    /// Validates that the messages are placed in the tree at the positions
+    /// defined by their protocol ids, and that the placement map and the
+    /// message map are consistent.
+    ///
+    /// Trees constructed with [`MerkleTree::try_commit`] are always valid; the
+    /// check is intended for the trees decoded from untrusted data, which
+    /// otherwise may silently produce a root different from the one of the
+    /// tree constructed from the same messages.
+    pub fn validate(&self) -> Result<(), TreeValidationError> {
+        if self.map.len() != self.messages.len() {
+            return Err(TreeValidationError::CountMismatch(self.messages.len(), self.map.len()));
+        }
+        for (pos, (protocol_id, message)) in &self.map {
+            let expected = self.protocol_id_pos(*protocol_id);
+            if *pos != expected {
+                return Err(TreeValidationError::Misplaced {
+                    protocol_id: *protocol_id,
+                    expected,
+                    actual: *pos,
+                });
+            }
+            if self.messages.get(protocol_id) != Some(message) {
+                return Err(TreeValidationError::MessageMismatch(*protocol_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares trees ignoring their entropy, i.e. only by their depth,
+    /// cofactor and placement of the messages.
+    ///
+    /// Trees constructed from the same [`crate::mpc::MultiSource`] with
+    /// different random entropy are structurally equal.
+    pub fn structurally_eq(&self, other: &Self) -> bool {
+        self.depth == other.depth &&
+            self.cofactor == other.cofactor &&
+            self.messages == other.messages
+    }
+
+    /// Computes hash of the structural part of the tree (its depth, cofactor
+    /// and messages, but not entropy), which is equal for all
+    /// [`Self::structurally_eq`] trees.
+    pub fn structural_hash(&self) -> Bytes32 {
+        let mut engine = Sha256::from_tag(Self::STRUCTURE_TAG);
+        self.depth.to_u8().commit_encode(&mut engine);
+        self.cofactor.commit_encode(&mut engine);
+        self.precommitment().commit_encode(&mut engine);
+        engine.finish().into()
+    }
+
+    /// Computes pre-commitment to the set of messages committed by the tree,
+    /// which must match [`crate::mpc::MultiSource::precommitment`] of the
+    /// source used to construct the tree.
+    pub fn precommitment(&self) -> Precommitment { Precommitment::with(&self.messages) }
+
+    /// Computes extended commitment, which additionally commits to the tree
+    /// depth, cofactor and the number of messages. See [`Commitment::extend`].
+    pub fn extended_commitment(&self) -> Commitment {
+        self.commitment_id()
+            .extend(self.depth, self.cofactor, self.messages.len() as u32)
+    }
 }
 
 #[cfg(test)]
@@ -248,15 +461,17 @@ pub(crate) mod test_helpers {
 mod test {
     use std::collections::BTreeSet;
 
+    use amplify::confinement::{Confined, U16};
     use amplify::num::u5;
     use amplify::WriteCounter;
     use rand::random;
     use sha2::Sha256;
-    use strict_encoding::StrictEncode;
+    use strict_encoding::{StrictDeserialize, StrictEncode, StrictSerialize};
 
+    use crate::merkle::MerkleNode;
     use crate::mpc::tree::test_helpers::{make_random_messages, make_random_tree};
-    use crate::mpc::MerkleTree;
-    use crate::{CommitEncode, CommitmentId, Conceal, DigestExt};
+    use crate::mpc::{Leaf, MerkleTree, MultiSource, TreeValidationError};
+    use crate::{CommitEncode, CommitmentId, Conceal, DigestExt, TryCommitVerify};
 
     #[test]
     #[should_panic(expected = "Empty")]
@@ -312,6 +527,82 @@ mod test {
         }
     }
 
+    #[test]
+    fn tree_leaves() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        for (protocol, msg) in msgs {
+            let pos = tree.protocol_id_pos(protocol);
+            let leaf = tree.leaf(pos).unwrap();
+            assert_eq!(leaf, Leaf::inhabited(protocol, msg));
+
+            let mut engine = Sha256::from_tag(Leaf::TAG);
+            engine.input_raw(&[0x10]);
+            engine.input_raw(protocol.as_slice());
+            engine.input_raw(msg.as_slice());
+            assert_eq!(leaf.to_merkle_node(), MerkleNode::from(engine.finish()));
+        }
+        let pos = (0..tree.width())
+            .find(|pos| !tree.map.contains_key(pos))
+            .unwrap();
+        assert_eq!(tree.leaf(pos), Some(Leaf::entropy(tree.entropy(), pos)));
+        assert_eq!(tree.leaf(tree.width()), None);
+    }
+
+    #[test]
+    fn tree_deterministic() {
+        let msgs = make_random_messages(9);
+        let source = MultiSource::deterministic(Confined::try_from(msgs.clone()).unwrap());
+        let tree1 = MerkleTree::try_commit(&source).unwrap();
+        let tree2 = MerkleTree::try_commit(&source).unwrap();
+        assert!(tree1.is_deterministic());
+        assert_eq!(tree1, tree2);
+        assert_eq!(tree1.commitment_id(), tree2.commitment_id());
+        assert!(!make_random_tree(&msgs).is_deterministic());
+
+        let other = make_random_messages(9);
+        let source = MultiSource::deterministic(Confined::try_from(other).unwrap());
+        let tree3 = MerkleTree::try_commit(&source).unwrap();
+        assert!(tree3.is_deterministic());
+        assert_ne!(tree1.entropy(), tree3.entropy());
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn tree_with_rng() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let msgs = make_random_messages(9);
+        let source = MultiSource {
+            messages: Confined::try_from(msgs).unwrap(),
+            ..default!()
+        };
+        let tree1 =
+            MerkleTree::try_commit_with_rng(&source, &mut StdRng::seed_from_u64(1)).unwrap();
+        let tree2 =
+            MerkleTree::try_commit_with_rng(&source, &mut StdRng::seed_from_u64(1)).unwrap();
+        let tree3 =
+            MerkleTree::try_commit_with_rng(&source, &mut StdRng::seed_from_u64(2)).unwrap();
+        assert_eq!(tree1, tree2);
+        assert_ne!(tree1.entropy(), tree3.entropy());
+        assert!(tree1.structurally_eq(&tree3));
+    }
+
+    #[test]
+    fn tree_structural_eq() {
+        let msgs = make_random_messages(9);
+        let tree1 = make_random_tree(&msgs);
+        let tree2 = make_random_tree(&msgs);
+        assert_ne!(tree1, tree2);
+        assert!(tree1.structurally_eq(&tree2));
+        assert_eq!(tree1.structural_hash(), tree2.structural_hash());
+
+        let tree3 = make_random_tree(&make_random_messages(9));
+        assert!(!tree1.structurally_eq(&tree3));
+        assert_ne!(tree1.structural_hash(), tree3.structural_hash());
+    }
+
     #[test]
     fn tree_conceal() {
         let msgs = make_random_messages(9);
@@ -372,4 +663,50 @@ mod test {
         eprintln!("Cofactors: {cofacs:?}");
         assert!(davg <= 15f32);
     }
+
+    #[test]
+    fn tree_validate() {
+        let msgs = make_random_messages(9);
+        let mut tree = make_random_tree(&msgs);
+        tree.validate().unwrap();
+
+        let (pos, entry) = tree.map.iter().next().map(|(p, e)| (*p, *e)).unwrap();
+        let misplaced = (0..tree.width())
+            .find(|p| !tree.map.contains_key(p))
+            .unwrap();
+        tree.map.remove(&pos).unwrap();
+        assert_eq!(tree.validate(), Err(TreeValidationError::CountMismatch(9, 8)));
+        tree.map.insert(misplaced, entry).unwrap();
+        assert_eq!(
+            tree.validate(),
+            Err(TreeValidationError::Misplaced {
+                protocol_id: entry.0,
+                expected: pos,
+                actual: misplaced
+            })
+        );
+    }
+
+    #[test]
+    fn tree_decode_map_order() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let data = tree.to_strict_serialized::<U16>().unwrap();
+        assert_eq!(MerkleTree::from_strict_serialized::<U16>(data.clone()).unwrap(), tree);
+
+        // Messages start after the depth, entropy and cofactor (11 bytes) and
+        // the map length (3 bytes)
+        let entry = |no: usize| 14 + no * 64..14 + (no + 1) * 64;
+
+        let mut swapped = data.to_vec();
+        swapped[entry(0)].copy_from_slice(&data[entry(1)]);
+        swapped[entry(1)].copy_from_slice(&data[entry(0)]);
+        let swapped = Confined::try_from(swapped).unwrap();
+        assert!(MerkleTree::from_strict_serialized::<U16>(swapped).is_err());
+
+        let mut duplicated = data.to_vec();
+        duplicated[entry(1)].copy_from_slice(&data[entry(0)]);
+        let duplicated = Confined::try_from(duplicated).unwrap();
+        assert!(MerkleTree::from_strict_serialized::<U16>(duplicated).is_err());
+    }
 }
@@ -0,0 +1,185 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Watermarking of LNPBP-4 proofs with recipient identifiers.
+//!
+//! A proof producer may issue to each recipient a copy of the same proof bound
+//! to a recipient nonce. The binding is a message authentication code keyed
+//! with the producer secret ([`WatermarkKey`]), which doesn't affect the
+//! commitment the proof convolves to, but allows the producer to attribute a
+//! leaked copy of the proof to its recipient. The nonces are not a part of the
+//! watermarked proof: the producer keeps the mapping of the nonces to the
+//! recipients, so without the producer secret nobody can either recompute the
+//! watermark for another recipient, or tell which recipient the proof was
+//! issued to.
+//!
+//! NB: the watermark is detachable: a recipient may strip it and leak the
+//! bare proof.
+
+use std::fmt::{self, Debug, Formatter};
+
+use amplify::Bytes32;
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::mpc::{Commitment, InvalidProof, MerkleProof, Message, ProtocolId};
+use crate::{CommitEncode, LIB_NAME_COMMIT_VERIFY};
+
+/// Secret key of a proof producer used to watermark the proofs.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct WatermarkKey(Bytes32);
+
+impl Debug for WatermarkKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str("WatermarkKey(..)") }
+}
+
+impl WatermarkKey {
+    /// Tag of the keyed hash producing the watermark.
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:wmrk:key#23A";
+
+    /// Constructs watermarking key from the producer `secret`.
+    pub fn from_secret(secret: impl Into<Bytes32>) -> Self { WatermarkKey(secret.into()) }
+
+    /// Generates random watermarking key.
+    #[cfg(feature = "rand")]
+    pub fn random() -> Self { Self::random_with_rng(&mut rand::thread_rng()) }
+
+    /// Generates random watermarking key using the provided `rng`.
+    #[cfg(feature = "rand")]
+    pub fn random_with_rng(rng: &mut (impl rand::RngCore + rand::CryptoRng)) -> Self {
+        let mut secret = [0u8; 32];
+        rng.fill_bytes(&mut secret);
+        WatermarkKey(secret.into())
+    }
+
+    /// Computes watermark binding the `proof` to the recipient `nonce`.
+    ///
+    /// The key is mixed into a fixed-length input consisting of the key and
+    /// the digest of the proof and the nonce, which makes the keyed hash
+    /// resistant to length extension.
+    pub fn watermark(&self, proof: &MerkleProof, nonce: Bytes32) -> Bytes32 {
+        let mut engine = Sha256::from_tag(WatermarkedProof::TAG);
+        proof.commit_encode(&mut engine);
+        nonce.commit_encode(&mut engine);
+        let digest = engine.finish();
+
+        let mut engine = Sha256::from_tag(Self::TAG);
+        engine.input_raw(self.0.as_slice());
+        engine.input_raw(&digest);
+        engine.finish().into()
+    }
+
+    /// Binds the `proof` to the recipient `nonce`.
+    pub fn issue(&self, proof: MerkleProof, nonce: impl Into<Bytes32>) -> WatermarkedProof {
+        let watermark = self.watermark(&proof, nonce.into());
+        WatermarkedProof { proof, watermark }
+    }
+
+    /// Checks whether the `proof` was issued to the recipient with the
+    /// `nonce`.
+    pub fn is_issued_to(&self, proof: &WatermarkedProof, nonce: Bytes32) -> bool {
+        self.watermark(&proof.proof, nonce) == proof.watermark
+    }
+
+    /// Attributes a leaked watermarked `proof` to one of the recipient nonces
+    /// from `candidates`, returning the matching nonce.
+    pub fn attribute(
+        &self,
+        proof: &WatermarkedProof,
+        candidates: impl IntoIterator<Item = Bytes32>,
+    ) -> Option<Bytes32> {
+        candidates
+            .into_iter()
+            .find(|nonce| self.is_issued_to(proof, *nonce))
+    }
+}
+
+/// [`MerkleProof`] bound to a recipient nonce with a [`WatermarkKey`].
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct WatermarkedProof {
+    /// Watermarked proof.
+    proof: MerkleProof,
+
+    /// Watermark binding the proof to the recipient nonce.
+    #[getter(as_copy)]
+    watermark: Bytes32,
+}
+
+impl WatermarkedProof {
+    /// Tag of the digest of the proof and the recipient nonce, which is
+    /// authenticated with [`WatermarkKey`].
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0004:wmrk:v01#23A";
+
+    /// Removes the watermark, returning the underlying proof.
+    pub fn into_proof(self) -> MerkleProof { self.proof }
+
+    /// Convolves the underlying proof (see [`MerkleProof::convolve`]); the
+    /// watermark doesn't affect the commitment.
+    pub fn convolve(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+    ) -> Result<Commitment, InvalidProof> {
+        self.proof.convolve(protocol_id, message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    use crate::mpc::MerkleBlock;
+    use crate::CommitmentId;
+
+    #[test]
+    fn watermark() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+        let (protocol_id, message) = msgs.into_iter().next().unwrap();
+        let proof = block.to_merkle_proof(protocol_id).unwrap();
+
+        let key = WatermarkKey::from_secret([0x5E; 32]);
+        let alice = Bytes32::from([0xA1; 32]);
+        let bob = Bytes32::from([0xB0; 32]);
+        let for_alice = key.issue(proof.clone(), alice);
+        let for_bob = key.issue(proof.clone(), bob);
+        assert!(key.is_issued_to(&for_alice, alice));
+        assert!(!key.is_issued_to(&for_alice, bob));
+        assert_ne!(for_alice.watermark(), for_bob.watermark());
+        assert_eq!(for_alice.convolve(protocol_id, message), Ok(tree.commitment_id()));
+        assert_eq!(key.attribute(&for_bob, [alice, bob]), Some(bob));
+        assert_eq!(for_bob.into_proof(), proof);
+
+        // Without the producer secret the watermark can't be recomputed
+        let forger = WatermarkKey::from_secret([0xF0; 32]);
+        let framed = forger.issue(proof.clone(), bob);
+        assert_ne!(framed.watermark(), key.issue(proof, bob).watermark());
+        assert_eq!(key.attribute(&framed, [alice, bob]), None);
+    }
+}
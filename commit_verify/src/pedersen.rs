@@ -0,0 +1,214 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pedersen commitments over ristretto255 group.
+//!
+//! Provides algebraic (additively homomorphic) commitment scheme for the
+//! projects which do not depend on bitcoin cryptography. Both generators of
+//! the commitment are derived from the tag of the commitment protocol, such
+//! that the commitments made under different protocols are independent and
+//! nobody knows the discrete logarithm relation between the generators.
+
+use std::ops::Add;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::{CommitEncode, CommitVerify, CommitmentProtocol};
+
+/// Commitment protocol using Pedersen commitments over ristretto255.
+pub trait PedersenProtocol: CommitmentProtocol {
+    /// Tag from which the protocol generators are derived.
+    const TAG: [u8; 32];
+}
+
+/// Pair of ristretto255 generators used by Pedersen commitments.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Generators {
+    /// Generator for the committed value.
+    pub value: RistrettoPoint,
+    /// Generator for the blinding factor.
+    pub blinding: RistrettoPoint,
+}
+
+impl Generators {
+    /// Derives generators from the protocol `tag`.
+    pub fn with_tag(tag: [u8; 32]) -> Self {
+        Generators {
+            value: hash_to_point(tag, b"value"),
+            blinding: hash_to_point(tag, b"blinding"),
+        }
+    }
+
+    /// Derives generators of the protocol `P`.
+    pub fn of<P: PedersenProtocol>() -> Self { Self::with_tag(P::TAG) }
+}
+
+fn hash_to_point(tag: [u8; 32], label: &[u8]) -> RistrettoPoint {
+    let mut uniform = [0u8; 64];
+    for (no, chunk) in uniform.chunks_exact_mut(32).enumerate() {
+        let mut engine = Sha256::from_tag(tag);
+        engine.input_raw(label);
+        (no as u8).commit_encode(&mut engine);
+        chunk.copy_from_slice(&engine.finish());
+    }
+    RistrettoPoint::from_uniform_bytes(&uniform)
+}
+
+/// Opening of the Pedersen commitment: committed value and the blinding
+/// factor.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PedersenOpening {
+    /// Committed value.
+    pub value: Scalar,
+    /// Blinding factor, which must be kept secret.
+    pub blinding: Scalar,
+}
+
+impl Add for PedersenOpening {
+    type Output = PedersenOpening;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        PedersenOpening {
+            value: self.value + rhs.value,
+            blinding: self.blinding + rhs.blinding,
+        }
+    }
+}
+
+impl PedersenOpening {
+    /// Constructs opening for a numeric `value`.
+    pub fn with_value(value: u64, blinding: [u8; 32]) -> Self {
+        PedersenOpening {
+            value: Scalar::from(value),
+            blinding: Scalar::from_bytes_mod_order(blinding),
+        }
+    }
+
+    /// Constructs opening for an arbitrary `message`, which is hashed into the
+    /// value using the tag of the protocol `P`.
+    pub fn with_message<P: PedersenProtocol>(
+        message: impl AsRef<[u8]>,
+        blinding: [u8; 32],
+    ) -> Self {
+        let mut engine = Sha256::from_tag(P::TAG);
+        engine.input_raw(b"message");
+        engine.input_raw(message.as_ref());
+        PedersenOpening {
+            value: Scalar::from_bytes_mod_order(engine.finish()),
+            blinding: Scalar::from_bytes_mod_order(blinding),
+        }
+    }
+}
+
+/// Pedersen commitment over ristretto255: `value * V + blinding * B`, where
+/// `V` and `B` are [`Generators`] of the commitment protocol.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct PedersenCommitment(CompressedRistretto);
+
+impl<P: PedersenProtocol> CommitVerify<PedersenOpening, P> for PedersenCommitment {
+    fn commit(opening: &PedersenOpening) -> Self {
+        let generators = Generators::of::<P>();
+        let point = opening.value * generators.value + opening.blinding * generators.blinding;
+        PedersenCommitment(point.compress())
+    }
+}
+
+impl PedersenCommitment {
+    /// Constructs commitment from its serialized representation, failing if
+    /// the bytes do not represent a valid ristretto255 point.
+    pub fn from_byte_array(bytes: [u8; 32]) -> Option<Self> {
+        let point = CompressedRistretto(bytes);
+        point.decompress().map(|_| PedersenCommitment(point))
+    }
+
+    /// Returns serialized representation of the commitment.
+    pub fn to_byte_array(&self) -> [u8; 32] { self.0.to_bytes() }
+
+    /// Adds two commitments, producing commitment to the sum of their
+    /// openings. Returns `None` if any of the commitments is not a valid
+    /// ristretto255 point.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let sum = self.0.decompress()? + other.0.decompress()?;
+        Some(PedersenCommitment(sum.compress()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stability::TagRegistry;
+
+    struct TestProtocol;
+    impl CommitmentProtocol for TestProtocol {}
+    impl PedersenProtocol for TestProtocol {
+        const TAG: [u8; 32] = *b"urn:lnpbp:test:pedersen:v01#2023";
+    }
+
+    struct OtherProtocol;
+    impl CommitmentProtocol for OtherProtocol {}
+    impl PedersenProtocol for OtherProtocol {
+        const TAG: [u8; 32] = *b"urn:lnpbp:test:pedersen:v02#2023";
+    }
+
+    fn commit<P: PedersenProtocol>(opening: &PedersenOpening) -> PedersenCommitment {
+        <PedersenCommitment as CommitVerify<_, P>>::commit(opening)
+    }
+
+    #[test]
+    fn commit_verify() {
+        let opening = PedersenOpening::with_message::<TestProtocol>(b"test", [0x5A; 32]);
+        let commitment = commit::<TestProtocol>(&opening);
+        assert_eq!(
+            <PedersenCommitment as CommitVerify<_, TestProtocol>>::verify(&commitment, &opening),
+            Ok(())
+        );
+        assert_ne!(commit::<OtherProtocol>(&opening), commitment);
+
+        let other = PedersenOpening::with_message::<TestProtocol>(b"test", [0xA5; 32]);
+        assert_ne!(commit::<TestProtocol>(&other), commitment);
+
+        let bytes = commitment.to_byte_array();
+        assert_eq!(PedersenCommitment::from_byte_array(bytes), Some(commitment));
+    }
+
+    #[test]
+    fn homomorphism() {
+        let a = PedersenOpening::with_value(10, [0x01; 32]);
+        let b = PedersenOpening::with_value(32, [0x02; 32]);
+        let sum = commit::<TestProtocol>(&a)
+            .checked_add(&commit::<TestProtocol>(&b))
+            .unwrap();
+        assert_eq!(sum, commit::<TestProtocol>(&(a + b)));
+        assert_eq!(sum, commit::<TestProtocol>(&PedersenOpening::with_value(42, [0x03; 32])));
+    }
+
+    #[test]
+    fn tags() {
+        let mut registry = TagRegistry::library();
+        registry
+            .register_pedersen::<TestProtocol>()
+            .register_pedersen::<OtherProtocol>();
+        registry.check().unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common interface for the evidence of commitment publication.
+//!
+//! A commitment may be published in different media: anchored in a
+//! blockchain transaction, timestamped by a timestamping service or appended
+//! to an append-only log. [`ProofOfPublication`] allows applications to
+//! handle such heterogeneous evidence uniformly, for instance as
+//! `Box<dyn ProofOfPublication<Commitment = mpc::Commitment>>`.
+//!
+//! This library implements the trait for the append-only log inclusions
+//! ([`crate::mpc::LogInclusion`]); blockchain anchors and timestamps
+//! implement it in the libraries defining them.
+
+use amplify::Bytes32;
+
+/// Medium in which a commitment is published.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum PublicationMedium {
+    /// Blockchain-like medium, where the commitment is anchored in a
+    /// transaction.
+    Blockchain,
+
+    /// Timestamping service.
+    Timestamp,
+
+    /// Append-only log.
+    Log,
+}
+
+/// Evidence of publication of a commitment in some medium.
+pub trait ProofOfPublication {
+    /// Type of the commitment which publication is proven.
+    type Commitment;
+
+    /// Medium in which the commitment is published.
+    fn medium(&self) -> PublicationMedium;
+
+    /// Identifier of the item published in the medium (transaction id, log
+    /// root, timestamp digest etc), which includes the commitment.
+    fn published_id(&self) -> Bytes32;
+
+    /// Verifies that the `commitment` is included into the published item.
+    fn verify(&self, commitment: &Self::Commitment) -> bool;
+}
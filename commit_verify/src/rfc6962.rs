@@ -0,0 +1,90 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle trees of the shape defined in [RFC 6962], where the left subtree is
+//! always the largest perfect binary tree, shared by the blob commitments and
+//! the commitment logs. The node hashing function is provided by the callers,
+//! which use their own tags.
+//!
+//! [RFC 6962]: https://www.rfc-editor.org/rfc/rfc6962#section-2.1
+
+/// Returns the largest power of two which is less than `n`; `n` must be
+/// greater than 1.
+pub(crate) fn split_point(n: u64) -> u64 {
+    debug_assert!(n > 1);
+    1 << (63 - (n - 1).leading_zeros())
+}
+
+/// Computes merkle root over the non-empty list of `leaves`.
+pub(crate) fn tree_root<T: Copy>(leaves: &[T], node: fn(T, T) -> T) -> T {
+    match leaves.len() {
+        0 => unreachable!("root of an empty tree is undefined"),
+        1 => leaves[0],
+        n => {
+            let k = split_point(n as u64) as usize;
+            node(tree_root(&leaves[..k], node), tree_root(&leaves[k..], node))
+        }
+    }
+}
+
+/// Collects audit path for the leaf with the given `index`, starting from the
+/// bottom of the tree.
+pub(crate) fn audit_path<T: Copy>(
+    index: usize,
+    leaves: &[T],
+    node: fn(T, T) -> T,
+    path: &mut Vec<T>,
+) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split_point(n as u64) as usize;
+    if index < k {
+        audit_path(index, &leaves[..k], node, path);
+        path.push(tree_root(&leaves[k..], node));
+    } else {
+        audit_path(index - k, &leaves[k..], node, path);
+        path.push(tree_root(&leaves[..k], node));
+    }
+}
+
+/// Computes root of the tree with `size` leaves from the `leaf` with the given
+/// `index` and its audit `path`. Returns `None` if the path doesn't match the
+/// tree size.
+pub(crate) fn root_from_path<T: Copy>(
+    index: u64,
+    size: u64,
+    leaf: T,
+    path: &[T],
+    node: fn(T, T) -> T,
+) -> Option<T> {
+    if size <= 1 {
+        return if path.is_empty() { Some(leaf) } else { None };
+    }
+    let (sibling, rest) = path.split_last()?;
+    let k = split_point(size);
+    if index < k {
+        Some(node(root_from_path(index, k, leaf, rest, node)?, *sibling))
+    } else {
+        Some(node(*sibling, root_from_path(index - k, size - k, leaf, rest, node)?))
+    }
+}
@@ -0,0 +1,399 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Commitment id stability harness.
+//!
+//! Commitment ids are consensus-critical: any unintentional change in the
+//! commit encoding of a type changes its ids and breaks compatibility with
+//! the already existing commitments. Libraries defining committed types may
+//! pin golden commitment ids of sample values in their test suites with
+//! [`StabilitySuite`], so that such changes fail loudly.
+//!
+//! Another common failure is reuse of the same [`CommitmentId::TAG`] by
+//! different types (usually by copy-pasting an existing type), which can be
+//! detected with [`TagRegistry`]. [`TagRegistry::library`] contains all tags
+//! used by this library and may be extended with the tags of the downstream
+//! libraries.
+//!
+//! ```
+//! # use commit_verify::stability::StabilitySuite;
+//! # use commit_verify::structured::FieldValue;
+//! let mut suite = StabilitySuite::new();
+//! suite.register(
+//!     "FieldValue",
+//!     &FieldValue::with("amount", &100u64),
+//!     "8071bf7146f0eeea0b2dbd97f4910471f13cdc4b8fdb74a1edd8430f19ea8e79",
+//! );
+//! suite.validate().unwrap();
+//! ```
+
+use std::any::type_name;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+
+use amplify::hex::ToHex;
+
+#[cfg(feature = "ristretto")]
+use crate::pedersen::PedersenProtocol;
+use crate::CommitmentId;
+
+/// Registers tags named after the expressions providing them.
+macro_rules! register_tags {
+    ($registry:ident: $($(#[$attr:meta])* $tag:expr),+ $(,)?) => {
+        $( $(#[$attr])* $registry.register_tag(stringify!($tag), $tag); )+
+    };
+}
+
+/// Registered commitment id of a sample value.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct StabilityCase {
+    /// Name of the committed type.
+    pub type_name: &'static str,
+    /// Expected (golden) commitment id in hex encoding.
+    pub expected: String,
+    /// Actual commitment id in hex encoding.
+    pub actual: String,
+}
+
+impl StabilityCase {
+    /// Detects whether the actual commitment id matches the expected one.
+    pub fn is_stable(&self) -> bool { self.expected.to_lowercase() == self.actual }
+}
+
+/// Set of the commitment id stability cases.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct StabilitySuite(Vec<StabilityCase>);
+
+impl StabilitySuite {
+    /// Constructs empty suite.
+    pub fn new() -> Self { default!() }
+
+    /// Registers `sample` value of a type with the expected commitment id,
+    /// provided in hex encoding.
+    pub fn register<T: CommitmentId>(
+        &mut self,
+        type_name: &'static str,
+        sample: &T,
+        expected: &str,
+    ) -> &mut Self
+    where
+        T::Id: Borrow<[u8]>,
+    {
+        let id = sample.commitment_id();
+        self.0.push(StabilityCase {
+            type_name,
+            expected: expected.to_owned(),
+            actual: Borrow::<[u8]>::borrow(&id).to_hex(),
+        });
+        self
+    }
+
+    /// Returns all registered cases.
+    pub fn cases(&self) -> &[StabilityCase] { &self.0 }
+
+    /// Validates all registered cases, reporting all of the changed commitment
+    /// ids at once.
+    pub fn validate(&self) -> Result<(), StabilityReport> {
+        let unstable = self
+            .0
+            .iter()
+            .filter(|case| !case.is_stable())
+            .cloned()
+            .collect::<Vec<_>>();
+        if unstable.is_empty() {
+            Ok(())
+        } else {
+            Err(StabilityReport(unstable))
+        }
+    }
+}
+
+/// Report on the changed commitment ids.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Error)]
+pub struct StabilityReport(Vec<StabilityCase>);
+
+impl StabilityReport {
+    /// Returns cases with the changed commitment ids.
+    pub fn cases(&self) -> &[StabilityCase] { &self.0 }
+}
+
+impl Display for StabilityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "commitment ids of {} sample value(s) have changed:", self.0.len())?;
+        for case in &self.0 {
+            writeln!(
+                f,
+                "- {}: expected {}, actual {}",
+                case.type_name, case.expected, case.actual
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Registry of the tags used by [`CommitmentId`] implementations (and other
+/// tagged hashes), detecting tags shared by different types.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct TagRegistry(BTreeMap<[u8; 32], BTreeSet<&'static str>>);
+
+impl TagRegistry {
+    /// Constructs empty registry.
+    pub fn new() -> Self { default!() }
+
+    /// Constructs registry containing all tags used by this library.
+    pub fn library() -> Self {
+        use crate::blob::{BlobCommitment, BLOB_LEAF_TAG, BLOB_NODE_TAG};
+        use crate::chain::ChainContext;
+        use crate::merkle::MerkleNode;
+        use crate::mpc::{
+            Aggregation, AttachedMessage, BeaconEntropy, Checkpoint, ClosedSeals, Commitment, Leaf,
+            MerkleBlock, MerkleTree, MessageType, Precommitment, ProducerInfo, ProtocolId,
+            TypeRegistry, VerifyCheckpoint, WatermarkKey, WatermarkedProof, LOG_LEAF_TAG,
+            LOG_NODE_TAG, RESERVED_SLOT_TAG,
+        };
+        use crate::structured::FieldValue;
+        use crate::StrictEncodedProtocol;
+
+        let mut registry = TagRegistry::new();
+        registry
+            .register::<MerkleTree>()
+            .register_variant::<MerkleBlock, MerkleTree>()
+            .register::<TypeRegistry>()
+            .register::<AttachedMessage>()
+            .register::<BlobCommitment>()
+            .register::<MerkleNode>()
+            .register::<FieldValue>()
+            .register::<Leaf>()
+            .register::<BeaconEntropy>()
+            .register::<ProducerInfo>()
+            .register::<Checkpoint>()
+            .register::<ClosedSeals>();
+        register_tags!(registry:
+            Commitment::EXTENDED_TAG,
+            Precommitment::TAG,
+            Precommitment::FILL_TAG,
+            ClosedSeals::SEAL_TAG,
+            ProtocolId::NAME_TAG,
+            ProtocolId::CHILD_TAG,
+            MerkleTree::STRUCTURE_TAG,
+            VerifyCheckpoint::PREFIX_TAG,
+            BeaconEntropy::ENTROPY_TAG,
+            WatermarkedProof::TAG,
+            WatermarkKey::TAG,
+            MessageType::TAG,
+            BLOB_LEAF_TAG,
+            BLOB_NODE_TAG,
+            LOG_LEAF_TAG,
+            LOG_NODE_TAG,
+            RESERVED_SLOT_TAG,
+            Aggregation::TAG,
+            ChainContext::TAG,
+            ChainContext::ID_TAG,
+            ChainContext::MAGIC_TAG,
+            StrictEncodedProtocol::TAG,
+            #[cfg(feature = "secp256k1")]
+            crate::attestation::Attestation::TAG,
+            #[cfg(feature = "secp256k1")]
+            crate::escrow::AdaptorSignature::NONCE_TAG,
+            #[cfg(feature = "mnemonic")]
+            crate::mnemonic::MNEMONIC_TAG,
+        );
+        registry
+    }
+
+    /// Registers tag of a [`CommitmentId`] type.
+    pub fn register<T: CommitmentId>(&mut self) -> &mut Self {
+        self.register_tag(type_name::<T>(), T::TAG)
+    }
+
+    /// Registers tag of a [`CommitmentId`] type `T`, which is an alternative
+    /// representation of type `Of` and thus intentionally shares its tag (and
+    /// its commitment ids), like a merkle block and its source merkle tree.
+    pub fn register_variant<T: CommitmentId, Of: CommitmentId>(&mut self) -> &mut Self {
+        self.register_tag(type_name::<Of>(), T::TAG)
+    }
+
+    /// Registers tag of a [`PedersenProtocol`], from which the protocol
+    /// generators are derived.
+    #[cfg(feature = "ristretto")]
+    pub fn register_pedersen<P: PedersenProtocol>(&mut self) -> &mut Self {
+        self.register_tag(type_name::<P>(), P::TAG)
+    }
+
+    /// Registers arbitrary named tag.
+    pub fn register_tag(&mut self, name: &'static str, tag: [u8; 32]) -> &mut Self {
+        self.0.entry(tag).or_default().insert(name);
+        self
+    }
+
+    /// Checks that no tag is shared by different types, reporting all
+    /// collisions at once.
+    pub fn check(&self) -> Result<(), TagCollisions> {
+        let collisions = self
+            .0
+            .iter()
+            .filter(|(_, names)| names.len() > 1)
+            .map(|(tag, names)| (*tag, names.iter().copied().collect()))
+            .collect::<Vec<_>>();
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(TagCollisions(collisions))
+        }
+    }
+}
+
+/// Report on the tags shared by different types.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Error)]
+pub struct TagCollisions(Vec<([u8; 32], Vec<&'static str>)>);
+
+impl TagCollisions {
+    /// Returns colliding tags together with the names of the types using them.
+    pub fn collisions(&self) -> &[([u8; 32], Vec<&'static str>)] { &self.0 }
+}
+
+impl Display for TagCollisions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} tag(s) are used by multiple types:", self.0.len())?;
+        for (tag, names) in &self.0 {
+            writeln!(f, "- `{}`: {}", String::from_utf8_lossy(tag), names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::blob::BlobCommitment;
+    use crate::merkle::MerkleNode;
+    use crate::structured::FieldValue;
+
+    #[test]
+    fn golden_ids() {
+        let mut suite = StabilitySuite::new();
+        suite
+            .register(
+                "FieldValue",
+                &FieldValue::with("amount", &100u64),
+                "8071bf7146f0eeea0b2dbd97f4910471f13cdc4b8fdb74a1edd8430f19ea8e79",
+            )
+            .register(
+                "BlobCommitment",
+                &BlobCommitment::commit(b"hello"),
+                "fd439346d7ede255e86596ce3c51fcef423551595cdb7c7b90707141d6798a69",
+            );
+        suite.validate().unwrap();
+    }
+
+    #[test]
+    fn report() {
+        let mut suite = StabilitySuite::new();
+        suite
+            .register(
+                "FieldValue",
+                &FieldValue::with("amount", &101u64),
+                "8071bf7146f0eeea0b2dbd97f4910471f13cdc4b8fdb74a1edd8430f19ea8e79",
+            )
+            .register(
+                "BlobCommitment",
+                &BlobCommitment::commit(b"hello"),
+                "FD439346D7EDE255E86596CE3C51FCEF423551595CDB7C7B90707141D6798A69",
+            );
+        let report = suite.validate().unwrap_err();
+        assert_eq!(report.cases().len(), 1);
+        assert_eq!(report.cases()[0].type_name, "FieldValue");
+        assert!(report.to_string().contains("FieldValue: expected 8071bf71"));
+    }
+
+    #[test]
+    fn tags() {
+        let mut registry = TagRegistry::library();
+        registry.check().unwrap();
+
+        registry.register_tag("Copy", MerkleNode::TAG);
+        let collisions = registry.check().unwrap_err();
+        assert_eq!(collisions.collisions().len(), 1);
+        assert!(collisions.to_string().contains(
+            "`urn:lnpbp:lnpbp0081:node:v01#23A`: Copy, commit_verify::merkle::MerkleNode"
+        ));
+    }
+
+    #[test]
+    fn library_tags() {
+        // Adding a tag to the library requires adding it both to the registry
+        // and to this list
+        let mut expected = vec![
+            b"urn:lnpbp:chain:bind:cid:v01#23A",
+            b"urn:lnpbp:chain:bind:val:v01#23A",
+            b"urn:lnpbp:chain:netmagic:v01#23A",
+            b"urn:lnpbp:lnpbp0004:aggr:v01#23A",
+            b"urn:lnpbp:lnpbp0004:atch:v01#23A",
+            b"urn:lnpbp:lnpbp0004:bent:v01#23A",
+            b"urn:lnpbp:lnpbp0004:bcon:v01#23A",
+            b"urn:lnpbp:lnpbp0004:chkp:v01#23A",
+            b"urn:lnpbp:lnpbp0004:chld:v01#23A",
+            b"urn:lnpbp:lnpbp0004:extd:v01#23A",
+            b"urn:lnpbp:lnpbp0004:fill:v01#23A",
+            b"urn:lnpbp:lnpbp0004:leaf:v01#23A",
+            b"urn:lnpbp:lnpbp0004:log:leaf#23A",
+            b"urn:lnpbp:lnpbp0004:log:node#23A",
+            b"urn:lnpbp:lnpbp0004:meta:v01#23A",
+            b"urn:lnpbp:lnpbp0004:msgs:v01#23A",
+            b"urn:lnpbp:lnpbp0004:mtyp:v01#23A",
+            b"urn:lnpbp:lnpbp0004:name:v01#23A",
+            b"urn:lnpbp:lnpbp0004:prfx:v01#23A",
+            b"urn:lnpbp:lnpbp0004:sdef:v01#23A",
+            b"urn:lnpbp:lnpbp0004:seal:v01#23A",
+            b"urn:lnpbp:lnpbp0004:slot:v01#23A",
+            b"urn:lnpbp:lnpbp0004:strc:v01#23A",
+            b"urn:lnpbp:lnpbp0004:tree:v01#23A",
+            b"urn:lnpbp:lnpbp0004:treg:v01#23A",
+            b"urn:lnpbp:lnpbp0004:wmrk:key#23A",
+            b"urn:lnpbp:lnpbp0004:wmrk:v01#23A",
+            b"urn:lnpbp:lnpbp0007:strict:v01#A",
+            b"urn:lnpbp:lnpbp0009:blob:v01#23A",
+            b"urn:lnpbp:lnpbp0009:blobleaf#23A",
+            b"urn:lnpbp:lnpbp0009:blobnode#23A",
+            b"urn:lnpbp:lnpbp0081:fval:v01#23A",
+            b"urn:lnpbp:lnpbp0081:node:v01#23A",
+        ];
+        let optional = [
+            (b"urn:lnpbp:lnpbp0009:adaptr:v01#A", cfg!(feature = "secp256k1")),
+            (b"urn:lnpbp:lnpbp0009:attest:v01#A", cfg!(feature = "secp256k1")),
+            (b"urn:lnpbp:lnpbp0009:mnemonic#23A", cfg!(feature = "mnemonic")),
+        ];
+        expected.extend(
+            optional
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(tag, _)| tag),
+        );
+
+        let expected = expected.into_iter().copied().collect::<BTreeSet<_>>();
+        let registered = TagRegistry::library()
+            .0
+            .into_keys()
+            .collect::<BTreeSet<_>>();
+        assert_eq!(registered, expected);
+    }
+}
@@ -0,0 +1,814 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistence adapters for LNPBP-4 trees, blocks and anchors, keyed by their
+//! commitment ids, and keys for the indexes over them.
+//!
+//! The library provides in-memory and file-system (with `fs` feature)
+//! backends only; embedded databases (like `sled`) are expected to implement
+//! the storage traits in the downstream crates, using [`IndexKey`]s for their
+//! indexes.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::Infallible;
+
+use amplify::confinement::{Confined, MediumOrdSet};
+use amplify::num::u5;
+use amplify::Bytes32;
+
+use crate::merkle::MerkleNode;
+use crate::mpc::{
+    Commitment, MergeError, MerkleBlock, MerkleTree, Message, ProtocolId, TreeNode,
+    MERKLE_LNPBP4_TAG,
+};
+use crate::{CommitmentId, LIB_NAME_COMMIT_VERIFY};
+
+/// Key-value storage for LNPBP-4 Merkle trees and blocks, using their
+/// [`Commitment`] as a key.
+pub trait ProofStore {
+    /// Error accessing the storage.
+    type Error: std::error::Error;
+
+    /// Saves the tree to the storage, returning its commitment id.
+    fn store_tree(&mut self, tree: &MerkleTree) -> Result<Commitment, Self::Error>;
+
+    /// Retrieves the tree with the given commitment id, if it is known.
+    fn load_tree(&self, id: Commitment) -> Result<Option<MerkleTree>, Self::Error>;
+
+    /// Saves the block to the storage, returning its commitment id.
+    ///
+    /// If a block with the same commitment id is already present, the revealed
+    /// information from both blocks is merged; the storage must error and keep
+    /// the known block if the blocks can't be merged.
+    fn store_block(&mut self, block: &MerkleBlock) -> Result<Commitment, Self::Error>;
+
+    /// Retrieves the block with the given commitment id, if it is known.
+    fn load_block(&self, id: Commitment) -> Result<Option<MerkleBlock>, Self::Error>;
+
+    /// Checks whether a block with the given commitment id is present in the
+    /// storage.
+    fn has_block(&self, id: Commitment) -> Result<bool, Self::Error> {
+        self.load_block(id).map(|block| block.is_some())
+    }
+
+    /// Constructs a handle to the block with the given commitment id, which
+    /// loads the block from the storage only when it is accessed for the first
+    /// time.
+    fn lazy_block(&self, id: Commitment) -> LazyBlock<Self>
+    where Self: Sized {
+        LazyBlock {
+            store: self,
+            id,
+            block: None,
+        }
+    }
+}
+
+/// Handle to a [`MerkleBlock`] persisted in a [`ProofStore`], which is loaded
+/// on the first access.
+#[derive(Debug)]
+pub struct LazyBlock<'store, S: ProofStore> {
+    store: &'store S,
+    id: Commitment,
+    block: Option<MerkleBlock>,
+}
+
+impl<'store, S: ProofStore> LazyBlock<'store, S> {
+    /// Returns commitment id of the block.
+    pub fn id(&self) -> Commitment { self.id }
+
+    /// Detects whether the block was already loaded from the storage.
+    pub fn is_loaded(&self) -> bool { self.block.is_some() }
+
+    /// Loads the block from the storage, if it wasn't loaded before, and
+    /// returns a reference to it. Returns `None` if the block is absent from
+    /// the storage.
+    pub fn load(&mut self) -> Result<Option<&MerkleBlock>, S::Error> {
+        if self.block.is_none() {
+            self.block = self.store.load_block(self.id)?;
+        }
+        Ok(self.block.as_ref())
+    }
+
+    /// Loads the block and releases the handle.
+    pub fn into_block(mut self) -> Result<Option<MerkleBlock>, S::Error> {
+        self.load()?;
+        Ok(self.block)
+    }
+}
+
+/// Key-value storage for anchors of LNPBP-4 commitments, using the anchored
+/// [`Commitment`] as a key.
+///
+/// The anchor type `A` is defined by the anchoring protocol (for instance,
+/// bitcoin deterministic commitments) and is opaque to the storage.
+pub trait AnchorStore<A> {
+    /// Error accessing the storage.
+    type Error: std::error::Error;
+
+    /// Saves the anchor of the commitment `id`, replacing the previously
+    /// stored one.
+    fn store_anchor(&mut self, id: Commitment, anchor: &A) -> Result<(), Self::Error>;
+
+    /// Retrieves the anchor of the commitment `id`, if it is known.
+    fn load_anchor(&self, id: Commitment) -> Result<Option<A>, Self::Error>;
+}
+
+/// Node of the LNPBP-4 Merkle tree kept in a [`NodeStore`] under its hash.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(
+    lib = LIB_NAME_COMMIT_VERIFY,
+    tags = order,
+    dumb = { StoredNode::Branch([0u8; 32].into(), [0u8; 32].into()) }
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum StoredNode {
+    /// Tree branch, referencing the subtrees by their hashes.
+    Branch(MerkleNode, MerkleNode),
+    /// Revealed tree leaf.
+    Leaf {
+        /// Protocol under which the commitment is created.
+        protocol_id: ProtocolId,
+        /// Message the leaf commits to.
+        message: Message,
+    },
+}
+
+/// [`MerkleBlock`] which subtrees are kept in a [`NodeStore`], such that the
+/// subtrees shared by multiple blocks of the same tree are stored only once.
+///
+/// The reference contains only the tree root and the list of the protocols
+/// revealed by the block.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BlockRef {
+    /// Tree depth.
+    #[getter(as_copy)]
+    depth: u5,
+
+    /// Tree cofactor.
+    #[getter(as_copy)]
+    cofactor: u16,
+
+    /// Entropy used for placeholders, if known.
+    #[getter(as_copy)]
+    entropy: Option<u64>,
+
+    /// Hash of the tree root.
+    #[getter(as_copy)]
+    root: MerkleNode,
+
+    /// Protocols which leaves are revealed by the block.
+    revealed: MediumOrdSet<ProtocolId>,
+}
+
+/// Content-addressed storage for the subtrees of LNPBP-4 Merkle blocks, using
+/// the hash of the subtree root as a key.
+///
+/// Each tree branch is stored as a pair of hashes of its child subtrees, and
+/// each revealed leaf as its protocol id and message. Blocks of the same tree
+/// (for instance, revealing different protocols) are stored as [`BlockRef`]s,
+/// and the subtrees they share are stored only once.
+pub trait NodeStore {
+    /// Error accessing the storage.
+    type Error: std::error::Error;
+
+    /// Saves the node to the storage under the given hash.
+    fn store_node(&mut self, hash: MerkleNode, node: StoredNode) -> Result<(), Self::Error>;
+
+    /// Retrieves the node with the given hash, if it is known.
+    fn load_node(&self, hash: MerkleNode) -> Result<Option<StoredNode>, Self::Error>;
+
+    /// Saves all branches and revealed leaves of the block, returning the
+    /// block reference.
+    fn store_block_nodes(&mut self, block: &MerkleBlock) -> Result<BlockRef, Self::Error> {
+        let tree_depth = block.depth();
+        let width = block.width();
+        let mut revealed = BTreeSet::new();
+        let mut stack = Vec::<(u5, MerkleNode)>::with_capacity(tree_depth.to_usize() + 1);
+        for node in block.cross_section() {
+            let mut depth = node.depth_or(tree_depth);
+            let mut hash = node.to_merkle_node();
+            if let TreeNode::CommitmentLeaf {
+                protocol_id,
+                message,
+            } = *node
+            {
+                self.store_node(hash, StoredNode::Leaf {
+                    protocol_id,
+                    message,
+                })?;
+                revealed.insert(protocol_id);
+            }
+            while let Some((sibling_depth, sibling)) = stack.last().copied() {
+                if sibling_depth != depth || depth.to_u8() == 0 {
+                    break;
+                }
+                stack.pop();
+                depth = depth - 1;
+                let branch = StoredNode::Branch(sibling, hash);
+                hash = MerkleNode::branches(
+                    MERKLE_LNPBP4_TAG.to_be_bytes(),
+                    depth,
+                    width,
+                    sibling,
+                    hash,
+                );
+                self.store_node(hash, branch)?;
+            }
+            stack.push((depth, hash));
+        }
+        let (_, root) = stack.pop().expect("broken internal MerkleBlock structure");
+        Ok(BlockRef {
+            depth: tree_depth,
+            cofactor: block.cofactor(),
+            entropy: block.entropy(),
+            root,
+            revealed: Confined::try_from(revealed).expect("block leaves number"),
+        })
+    }
+
+    /// Reconstructs the block from its reference, returning `None` if some of
+    /// the revealed leaves are absent from the storage.
+    ///
+    /// Subtrees absent from the storage are represented by concealed nodes.
+    /// Since the subtrees are shared, the block may have more (but never
+    /// less) concealed nodes expanded into branches than the stored one,
+    /// keeping the same commitment id and revealed leaves.
+    fn load_block_nodes(&self, block_ref: &BlockRef) -> Result<Option<MerkleBlock>, Self::Error> {
+        let tree_depth = block_ref.depth;
+        let mut cross_section = Vec::new();
+        let mut found = 0usize;
+        let mut queue = vec![(u5::ZERO, block_ref.root)];
+        while let Some((depth, hash)) = queue.pop() {
+            match self.load_node(hash)? {
+                Some(StoredNode::Branch(left, right)) if depth < tree_depth => {
+                    queue.push((depth + 1, right));
+                    queue.push((depth + 1, left));
+                }
+                Some(StoredNode::Leaf {
+                    protocol_id,
+                    message,
+                }) if depth == tree_depth && block_ref.revealed.contains(&protocol_id) => {
+                    found += 1;
+                    cross_section.push(TreeNode::CommitmentLeaf {
+                        protocol_id,
+                        message,
+                    });
+                }
+                _ => cross_section.push(TreeNode::ConcealedNode { depth, hash }),
+            }
+        }
+        if found != block_ref.revealed.len() {
+            return Ok(None);
+        }
+        Ok(Some(MerkleBlock::with_cross_section(
+            tree_depth,
+            block_ref.cofactor,
+            block_ref.entropy,
+            Confined::try_from(cross_section).expect("block cross-section size"),
+        )))
+    }
+}
+
+/// In-memory [`ProofStore`] and [`NodeStore`] implementation.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MemStore {
+    trees: BTreeMap<Commitment, MerkleTree>,
+    blocks: BTreeMap<Commitment, MerkleBlock>,
+    nodes: BTreeMap<MerkleNode, StoredNode>,
+}
+
+impl MemStore {
+    /// Constructs empty storage.
+    pub fn new() -> Self { default!() }
+}
+
+impl ProofStore for MemStore {
+    type Error = MergeError;
+
+    fn store_tree(&mut self, tree: &MerkleTree) -> Result<Commitment, Self::Error> {
+        let id = tree.commitment_id();
+        self.trees.insert(id, tree.clone());
+        Ok(id)
+    }
+
+    fn load_tree(&self, id: Commitment) -> Result<Option<MerkleTree>, Self::Error> {
+        Ok(self.trees.get(&id).cloned())
+    }
+
+    fn store_block(&mut self, block: &MerkleBlock) -> Result<Commitment, Self::Error> {
+        let id = block.commitment_id();
+        match self.blocks.get_mut(&id) {
+            Some(known) => {
+                let mut merged = known.clone();
+                merged.merge_reveal(block.clone())?;
+                *known = merged;
+            }
+            None => {
+                self.blocks.insert(id, block.clone());
+            }
+        }
+        Ok(id)
+    }
+
+    fn load_block(&self, id: Commitment) -> Result<Option<MerkleBlock>, Self::Error> {
+        Ok(self.blocks.get(&id).cloned())
+    }
+
+    fn has_block(&self, id: Commitment) -> Result<bool, Self::Error> {
+        Ok(self.blocks.contains_key(&id))
+    }
+}
+
+impl NodeStore for MemStore {
+    type Error = Infallible;
+
+    fn store_node(&mut self, hash: MerkleNode, node: StoredNode) -> Result<(), Self::Error> {
+        self.nodes.entry(hash).or_insert(node);
+        Ok(())
+    }
+
+    fn load_node(&self, hash: MerkleNode) -> Result<Option<StoredNode>, Self::Error> {
+        Ok(self.nodes.get(&hash).copied())
+    }
+}
+
+impl MemStore {
+    /// Returns number of distinct branches and leaves kept by the
+    /// [`NodeStore`].
+    pub fn node_count(&self) -> usize { self.nodes.len() }
+}
+
+/// Kind of the [`IndexKey`], stored as its first byte.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(Debug)]
+#[repr(u8)]
+pub enum IndexKind {
+    /// Anchors by protocol id and witness transaction id.
+    ProtocolTx = 0x01,
+
+    /// Anchors by witness transaction id.
+    Txid = 0x02,
+
+    /// Anchors by LNPBP-4 [`Commitment`] id.
+    Commitment = 0x03,
+
+    /// Seals by their hash.
+    Seal = 0x04,
+}
+
+impl IndexKind {
+    /// Parses the kind from its byte representation.
+    pub fn from_u8(byte: u8) -> Option<Self> {
+        Some(match byte {
+            0x01 => IndexKind::ProtocolTx,
+            0x02 => IndexKind::Txid,
+            0x03 => IndexKind::Commitment,
+            0x04 => IndexKind::Seal,
+            _ => return None,
+        })
+    }
+}
+
+/// Fixed-length key for on-disk indexes of client-side-validated data, allowing
+/// different node implementations to build compatible databases.
+///
+/// All keys are [`IndexKey::LEN`] bytes long and have the following layout:
+///
+/// | Bytes    | Content                                                      |
+/// |----------|--------------------------------------------------------------|
+/// | `0`      | [`IndexKind`] byte                                           |
+/// | `1..33`  | primary id: protocol id, txid, commitment id or seal hash    |
+/// | `33..65` | secondary id (txid for [`IndexKind::ProtocolTx`]) or zeros   |
+///
+/// Transaction ids are used in their consensus byte order (i.e. not reversed,
+/// as they are displayed by bitcoin software). Since the primary id goes first,
+/// all anchors of a protocol can be found with a prefix scan using
+/// [`IndexKey::protocol_prefix`].
+#[derive(Wrapper, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice)]
+pub struct IndexKey([u8; IndexKey::LEN]);
+
+impl IndexKey {
+    /// Length of all index keys.
+    pub const LEN: usize = 65;
+
+    fn with(kind: IndexKind, primary: [u8; 32], secondary: [u8; 32]) -> Self {
+        let mut key = [0u8; Self::LEN];
+        key[0] = kind as u8;
+        key[1..33].copy_from_slice(&primary);
+        key[33..].copy_from_slice(&secondary);
+        IndexKey(key)
+    }
+
+    /// Constructs key for an anchor of a given protocol in a given witness
+    /// transaction.
+    pub fn by_protocol_tx(protocol_id: ProtocolId, txid: [u8; 32]) -> Self {
+        Self::with(IndexKind::ProtocolTx, protocol_id.to_byte_array(), txid)
+    }
+
+    /// Constructs key for anchors in a given witness transaction.
+    pub fn by_txid(txid: [u8; 32]) -> Self { Self::with(IndexKind::Txid, txid, [0u8; 32]) }
+
+    /// Constructs key for anchors with a given LNPBP-4 commitment id.
+    pub fn by_commitment(commitment: Commitment) -> Self {
+        Self::with(IndexKind::Commitment, commitment.to_byte_array(), [0u8; 32])
+    }
+
+    /// Constructs key for a seal with the given hash.
+    pub fn by_seal(seal_hash: Bytes32) -> Self {
+        Self::with(IndexKind::Seal, seal_hash.to_byte_array(), [0u8; 32])
+    }
+
+    /// Returns prefix shared by the keys of all anchors of a given protocol,
+    /// constructed with [`IndexKey::by_protocol_tx`].
+    pub fn protocol_prefix(protocol_id: ProtocolId) -> [u8; 33] {
+        let mut prefix = [0u8; 33];
+        prefix[0] = IndexKind::ProtocolTx as u8;
+        prefix[1..].copy_from_slice(protocol_id.as_slice());
+        prefix
+    }
+
+    /// Returns kind of the key, or `None` if the first byte of the key is not
+    /// a known [`IndexKind`].
+    pub fn kind(&self) -> Option<IndexKind> { IndexKind::from_u8(self.0[0]) }
+
+    /// Returns primary id of the key.
+    pub fn primary(&self) -> [u8; 32] {
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&self.0[1..33]);
+        id
+    }
+
+    /// Returns secondary id of the key.
+    pub fn secondary(&self) -> [u8; 32] {
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&self.0[33..]);
+        id
+    }
+
+    /// Returns byte representation of the key.
+    pub fn to_byte_array(&self) -> [u8; Self::LEN] { self.0 }
+}
+
+#[cfg(feature = "fs")]
+pub use fs::{FileStore, FileStoreError};
+
+#[cfg(feature = "fs")]
+mod fs {
+    use std::fs::File;
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use amplify::confinement::U32;
+    use strict_encoding::{DeserializeError, SerializeError, StrictDeserialize, StrictSerialize};
+
+    use super::*;
+    use crate::{ErrorCode, ErrorCodes};
+
+    /// Errors accessing [`FileStore`].
+    #[derive(Debug, Display, Error, From)]
+    #[display(inner)]
+    pub enum FileStoreError {
+        #[from]
+        Io(io::Error),
+
+        #[from]
+        Serialize(SerializeError),
+
+        #[from]
+        Deserialize(DeserializeError),
+
+        #[from]
+        Merge(MergeError),
+    }
+
+    impl ErrorCode for FileStoreError {
+        fn code(&self) -> &'static str {
+            match self {
+                FileStoreError::Io(_) => "store.io",
+                FileStoreError::Serialize(_) => "store.serialize",
+                FileStoreError::Deserialize(_) => "store.deserialize",
+                FileStoreError::Merge(err) => err.code(),
+            }
+        }
+
+        fn params(&self) -> Vec<(&'static str, String)> {
+            match self {
+                FileStoreError::Io(err) => vec![("kind", format!("{:?}", err.kind()))],
+                FileStoreError::Merge(err) => err.params(),
+                _ => vec![],
+            }
+        }
+    }
+
+    impl ErrorCodes for FileStoreError {
+        const CODES: &'static [&'static str] = &[
+            "store.io",
+            "store.serialize",
+            "store.deserialize",
+            "mpc.unrelated-blocks",
+            "mpc.invalid-proof",
+        ];
+    }
+
+    /// Counter making names of the temporary files unique within the process.
+    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    /// [`ProofStore`] and [`AnchorStore`] keeping each tree, block and anchor
+    /// in a separate file inside a directory.
+    ///
+    /// Files are named after the hex representation of the commitment id with
+    /// `.tree`, `.block` and `.anchor` extensions and contain strict-serialized
+    /// data. The data are first written to a uniquely named temporary file,
+    /// which then replaces the stored one, such that an interrupted write never
+    /// corrupts the storage.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    pub struct FileStore {
+        dir: PathBuf,
+    }
+
+    impl FileStore {
+        /// Opens the storage in the given directory, creating the directory if
+        /// it doesn't exist.
+        pub fn open(dir: impl AsRef<Path>) -> Result<Self, FileStoreError> {
+            let dir = dir.as_ref().to_path_buf();
+            std::fs::create_dir_all(&dir)?;
+            Ok(FileStore { dir })
+        }
+
+        /// Returns directory used by the storage.
+        pub fn dir(&self) -> &Path { &self.dir }
+
+        fn path(&self, id: Commitment, ext: &str) -> PathBuf {
+            self.dir.join(format!("{id}.{ext}"))
+        }
+
+        fn read<T: StrictDeserialize>(
+            &self,
+            id: Commitment,
+            ext: &str,
+        ) -> Result<Option<T>, FileStoreError> {
+            let data = match std::fs::read(self.path(id, ext)) {
+                Ok(data) => data,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let data = Confined::try_from(data)
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+            Ok(Some(T::from_strict_serialized::<U32>(data)?))
+        }
+
+        fn write(
+            &self,
+            id: Commitment,
+            ext: &str,
+            value: &impl StrictSerialize,
+        ) -> Result<(), FileStoreError> {
+            let data = value.to_strict_serialized::<U32>()?;
+            let no = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let tmp = self
+                .dir
+                .join(format!("{id}.{ext}.{}-{no}.tmp", std::process::id()));
+            let mut file = File::create(&tmp)?;
+            file.write_all(&data)?;
+            file.sync_all()?;
+            std::fs::rename(tmp, self.path(id, ext))?;
+            Ok(())
+        }
+    }
+
+    impl ProofStore for FileStore {
+        type Error = FileStoreError;
+
+        fn store_tree(&mut self, tree: &MerkleTree) -> Result<Commitment, Self::Error> {
+            let id = tree.commitment_id();
+            self.write(id, "tree", tree)?;
+            Ok(id)
+        }
+
+        fn load_tree(&self, id: Commitment) -> Result<Option<MerkleTree>, Self::Error> {
+            self.read(id, "tree")
+        }
+
+        fn store_block(&mut self, block: &MerkleBlock) -> Result<Commitment, Self::Error> {
+            let id = block.commitment_id();
+            let block = match self.load_block(id)? {
+                Some(mut known) => {
+                    known.merge_reveal(block.clone())?;
+                    known
+                }
+                None => block.clone(),
+            };
+            self.write(id, "block", &block)?;
+            Ok(id)
+        }
+
+        fn load_block(&self, id: Commitment) -> Result<Option<MerkleBlock>, Self::Error> {
+            self.read(id, "block")
+        }
+
+        fn has_block(&self, id: Commitment) -> Result<bool, Self::Error> {
+            match std::fs::metadata(self.path(id, "block")) {
+                Ok(_) => Ok(true),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+
+    impl<A: StrictSerialize + StrictDeserialize> AnchorStore<A> for FileStore {
+        type Error = FileStoreError;
+
+        fn store_anchor(&mut self, id: Commitment, anchor: &A) -> Result<(), Self::Error> {
+            self.write(id, "anchor", anchor)
+        }
+
+        fn load_anchor(&self, id: Commitment) -> Result<Option<A>, Self::Error> {
+            self.read(id, "anchor")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mpc::test_helpers::{make_random_messages, make_random_tree};
+    #[cfg(feature = "fs")]
+    use crate::mpc::MerkleProof;
+    use crate::Conceal;
+
+    #[test]
+    fn mem_store() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+
+        let mut store = MemStore::new();
+        let id = store.store_tree(&tree).unwrap();
+        assert_eq!(id, tree.commitment_id());
+        assert_eq!(store.load_tree(id).unwrap(), Some(tree));
+        assert!(!store.has_block(id).unwrap());
+
+        let mut iter = msgs.keys();
+        let mut partial1 = block.clone();
+        partial1.conceal_except([*iter.next().unwrap()]).unwrap();
+        let mut partial2 = block.clone();
+        partial2.conceal_except([*iter.next().unwrap()]).unwrap();
+        store.store_block(&partial1).unwrap();
+        store.store_block(&partial2).unwrap();
+
+        let mut lazy = store.lazy_block(id);
+        assert!(!lazy.is_loaded());
+        let loaded = lazy.load().unwrap().unwrap().clone();
+        assert!(lazy.is_loaded());
+        assert_eq!(loaded.commitment_id(), id);
+        assert_eq!(loaded.to_known_message_map().len(), 2);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn file_store() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+        let id = tree.commitment_id();
+
+        let dir = std::env::temp_dir().join(format!("lnpbp4-store-{id}"));
+        let mut store = FileStore::open(&dir).unwrap();
+        assert_eq!(store.load_tree(id).unwrap(), None);
+        assert_eq!(store.load_block(id).unwrap(), None);
+        assert!(!store.has_block(id).unwrap());
+
+        assert_eq!(store.store_tree(&tree).unwrap(), id);
+        assert_eq!(store.load_tree(id).unwrap(), Some(tree));
+
+        let mut iter = msgs.keys();
+        let mut partial1 = block.clone();
+        partial1.conceal_except([*iter.next().unwrap()]).unwrap();
+        let mut partial2 = block.clone();
+        partial2.conceal_except([*iter.next().unwrap()]).unwrap();
+        store.store_block(&partial1).unwrap();
+        store.store_block(&partial2).unwrap();
+        assert!(store.has_block(id).unwrap());
+        let loaded = store.load_block(id).unwrap().unwrap();
+        assert_eq!(loaded.to_known_message_map().len(), 2);
+
+        let anchor = block.to_merkle_proof(*msgs.keys().next().unwrap()).unwrap();
+        assert_eq!(AnchorStore::<MerkleProof>::load_anchor(&store, id).unwrap(), None);
+        store.store_anchor(id, &anchor).unwrap();
+        assert_eq!(AnchorStore::<MerkleProof>::load_anchor(&store, id).unwrap(), Some(anchor));
+
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files, 3);
+    }
+
+    #[test]
+    fn node_store() {
+        let msgs = make_random_messages(9);
+        let tree = make_random_tree(&msgs);
+        let block = MerkleBlock::from(&tree);
+
+        let mut store = MemStore::new();
+        let mut refs = vec![];
+        let mut blocks = vec![];
+        for protocol_id in msgs.keys() {
+            let mut partial = block.clone();
+            partial.conceal_except([*protocol_id]).unwrap();
+            let block_ref = store.store_block_nodes(&partial).unwrap();
+            assert_eq!(block_ref.root(), partial.conceal());
+            assert_eq!(block_ref.revealed().len(), 1);
+            refs.push(block_ref);
+            blocks.push(partial);
+        }
+        let total = blocks
+            .iter()
+            .map(|block| block.cross_section().len())
+            .sum::<usize>();
+        assert!(store.node_count() < total);
+
+        for (block_ref, block) in refs.iter().zip(&blocks) {
+            let loaded = store.load_block_nodes(block_ref).unwrap().unwrap();
+            assert_eq!(&loaded, block);
+            assert_eq!(loaded.commitment_id(), tree.commitment_id());
+        }
+
+        let full = store.store_block_nodes(&block).unwrap();
+        assert_eq!(store.load_block_nodes(&full).unwrap(), Some(block));
+        assert_eq!(store.load_block_nodes(&refs[0]).unwrap().as_ref(), Some(&blocks[0]));
+
+        let empty = MemStore::new();
+        assert_eq!(empty.load_block_nodes(&full).unwrap(), None);
+
+        let (protocol_id, message) = msgs.iter().next().unwrap();
+        let proof = block.to_merkle_proof(*protocol_id).unwrap();
+        let compact = MerkleBlock::with(&proof, *protocol_id, *message).unwrap();
+        let mut own = MemStore::new();
+        let compact_ref = own.store_block_nodes(&compact).unwrap();
+        assert_eq!(own.load_block_nodes(&compact_ref).unwrap(), Some(compact.clone()));
+
+        let expanded = store.load_block_nodes(&compact_ref).unwrap().unwrap();
+        assert_ne!(expanded, compact);
+        assert_eq!(expanded.commitment_id(), compact.commitment_id());
+        assert_eq!(expanded.to_known_message_map(), compact.to_known_message_map());
+    }
+
+    #[test]
+    fn index_keys() {
+        let msgs = make_random_messages(2);
+        let mut pids = msgs.keys().copied();
+        let (pid1, pid2) = (pids.next().unwrap(), pids.next().unwrap());
+        let txid = [0xAB; 32];
+
+        let key = IndexKey::by_protocol_tx(pid1, txid);
+        assert_eq!(key.len(), IndexKey::LEN);
+        assert_eq!(key.kind(), Some(IndexKind::ProtocolTx));
+        assert_eq!(key.primary(), pid1.to_byte_array());
+        assert_eq!(key.secondary(), txid);
+        assert!(key.starts_with(&IndexKey::protocol_prefix(pid1)));
+        assert!(!key.starts_with(&IndexKey::protocol_prefix(pid2)));
+        assert_ne!(key, IndexKey::by_protocol_tx(pid2, txid));
+
+        let by_txid = IndexKey::by_txid(txid);
+        assert_eq!(by_txid.kind(), Some(IndexKind::Txid));
+        assert_eq!(by_txid.secondary(), [0u8; 32]);
+        assert_ne!(by_txid, IndexKey::by_seal(txid.into()));
+        assert_ne!(
+            IndexKey::by_commitment(Commitment::from(txid)).to_byte_array(),
+            IndexKey::by_seal(txid.into()).to_byte_array()
+        );
+        assert_eq!(IndexKey::from([0xFF; IndexKey::LEN]).kind(), None);
+    }
+}
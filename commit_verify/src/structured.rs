@@ -0,0 +1,402 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured commitments, where the commitment to a data structure is a
+//! merkle root over its individual fields, each tagged with its path.
+//!
+//! This allows to prove that a specific field of a committed structure had a
+//! specific value without revealing the rest of the structure (selective
+//! disclosure).
+
+use std::io::Write;
+
+use amplify::confinement::{Confined, LargeVec, SmallVec, TinyString, U32, U8};
+use amplify::num::u5;
+use sha2::Sha256;
+
+use crate::digest::DigestExt;
+use crate::merkle::MerkleNode;
+use crate::{CommitEncode, CommitmentId, ErrorCode, ErrorCodes, LIB_NAME_COMMIT_VERIFY};
+
+/// Errors committing to structures and constructing [`FieldProof`]s.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum FieldProofError {
+    /// structure doesn't contain field `{0}`.
+    UnknownField(String),
+
+    /// structure has {0} fields, while structured commitments support at most
+    /// 65535 fields.
+    TooManyFields(usize),
+}
+
+impl ErrorCode for FieldProofError {
+    fn code(&self) -> &'static str {
+        match self {
+            FieldProofError::UnknownField(_) => "structured.unknown-field",
+            FieldProofError::TooManyFields(_) => "structured.too-many-fields",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            FieldProofError::UnknownField(path) => vec![("path", path.clone())],
+            FieldProofError::TooManyFields(count) => vec![("count", count.to_string())],
+        }
+    }
+}
+
+impl ErrorCodes for FieldProofError {
+    const CODES: &'static [&'static str] =
+        &["structured.unknown-field", "structured.too-many-fields"];
+}
+
+/// Value of a single structure field participating in the structured
+/// commitment.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FieldValue {
+    /// Path of the field within the structure, like `"inputs.0.amount"`.
+    pub path: TinyString,
+    /// Commit-encoded field value.
+    pub value: LargeVec<u8>,
+}
+
+impl FieldValue {
+    /// Constructs field value by commit-encoding the provided `value`.
+    ///
+    /// # Panics
+    ///
+    /// If the path exceeds 255 bytes or the commit-encoded value exceeds 4GB.
+    pub fn with(path: &str, value: &impl CommitEncode) -> Self {
+        let mut data = vec![];
+        value.commit_encode(&mut data);
+        FieldValue {
+            path: TinyString::try_from(path.to_owned()).expect("field path is too long"),
+            value: Confined::try_from(data).expect("field value is too large"),
+        }
+    }
+
+    /// Computes merkle leaf committing to the field path and value.
+    pub fn to_merkle_node(&self) -> MerkleNode { self.commitment_id() }
+}
+
+impl CommitEncode for FieldValue {
+    fn commit_encode(&self, e: &mut impl Write) {
+        let mut engine = Sha256::default();
+        engine.input_with_len::<U8>(self.path.as_bytes());
+        engine.input_with_len::<U32>(&self.value);
+        e.write_all(&engine.finish()).ok();
+    }
+}
+
+impl CommitmentId for FieldValue {
+    const TAG: [u8; 32] = *b"urn:lnpbp:lnpbp0081:fval:v01#23A";
+    type Id = MerkleNode;
+}
+
+/// Data structures which commit to their fields with a merkle tree, allowing
+/// selective disclosure of the individual fields with [`FieldProof`]s.
+///
+/// To commit to the structure with its [`StructuredCommit::structured_root`],
+/// use [`crate::strategies::Structured`] commit encoding strategy.
+pub trait StructuredCommit {
+    /// Tag used for the merklization of the fields.
+    const MERKLE_TAG: [u8; 16];
+
+    /// Returns all structure fields in a deterministic order.
+    ///
+    /// Structures can be committed to only if the number of their fields
+    /// doesn't exceed `u16::MAX`.
+    fn fields(&self) -> Vec<FieldValue>;
+
+    /// Computes merkle root over the structure fields.
+    ///
+    /// # Errors
+    ///
+    /// If the structure has more than `u16::MAX` fields.
+    fn structured_root(&self) -> Result<MerkleNode, FieldProofError> {
+        let leaves = self
+            .fields()
+            .iter()
+            .map(FieldValue::to_merkle_node)
+            .collect::<Vec<_>>();
+        merklize(Self::MERKLE_TAG, &leaves)
+    }
+
+    /// Constructs proof of the field with the given `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the structure doesn't contain such field or has more than
+    /// `u16::MAX` fields.
+    fn prove_field(&self, path: &str) -> Result<FieldProof, FieldProofError> {
+        let fields = self.fields();
+        let pos = fields
+            .iter()
+            .position(|field| field.path.as_str() == path)
+            .ok_or_else(|| FieldProofError::UnknownField(path.to_owned()))?;
+        let leaves = fields
+            .iter()
+            .map(FieldValue::to_merkle_node)
+            .collect::<Vec<_>>();
+        FieldProof::with(Self::MERKLE_TAG, &leaves, pos)
+    }
+}
+
+fn merklize(tag: [u8; 16], leaves: &[MerkleNode]) -> Result<MerkleNode, FieldProofError> {
+    let width =
+        u16::try_from(leaves.len()).map_err(|_| FieldProofError::TooManyFields(leaves.len()))?;
+    Ok(if width == 1 {
+        leaves[0]
+    } else {
+        MerkleNode::_merklize(tag, leaves.iter().copied(), u5::ZERO, u32::from(width))
+    })
+}
+
+/// Proof of a single field value inside a [`StructuredCommit`] merkle root.
+#[derive(Getters, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_COMMIT_VERIFY)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct FieldProof {
+    /// Position of the field among the structure fields.
+    #[getter(as_copy)]
+    pos: u16,
+
+    /// Total number of the structure fields.
+    #[getter(as_copy)]
+    width: u16,
+
+    /// Merkle roots of the sibling subtrees, starting from the top of the
+    /// tree.
+    path: SmallVec<MerkleNode>,
+}
+
+impl FieldProof {
+    fn with(tag: [u8; 16], leaves: &[MerkleNode], pos: usize) -> Result<Self, FieldProofError> {
+        let width = u16::try_from(leaves.len())
+            .map_err(|_| FieldProofError::TooManyFields(leaves.len()))?;
+        let mut path = vec![];
+        let mut depth = u5::ZERO;
+        let mut slice = leaves;
+        let mut offset = 0usize;
+        while slice.len() > 2 {
+            let div = slice.len() / 2 + slice.len() % 2;
+            let (left, right) = slice.split_at(div);
+            let (next, sibling) = if pos < offset + div {
+                (left, right)
+            } else {
+                (right, left)
+            };
+            path.push(MerkleNode::_merklize(
+                tag,
+                sibling.iter().copied(),
+                depth + 1,
+                u32::from(width),
+            ));
+            if pos >= offset + div {
+                offset += div;
+            }
+            slice = next;
+            depth = depth + 1;
+        }
+        if slice.len() == 2 {
+            let sibling = if pos == offset { slice[1] } else { slice[0] };
+            path.push(sibling);
+        }
+        Ok(FieldProof {
+            pos: u16::try_from(pos).expect("field position is less than the width"),
+            width,
+            path: Confined::try_from(path).expect("tree depth never exceeds u16"),
+        })
+    }
+
+    /// Computes the merkle root from the `field` using the proof.
+    ///
+    /// Returns `None` if the proof is malformed for its width.
+    pub fn convolve(&self, tag: [u8; 16], field: &FieldValue) -> Option<MerkleNode> {
+        let width = self.width as u32;
+        let leaf = field.to_merkle_node();
+        if self.pos >= self.width {
+            return None;
+        }
+        if width == 1 {
+            return if self.path.is_empty() {
+                Some(leaf)
+            } else {
+                None
+            };
+        }
+
+        // Descend the tree to find out the branching at each level
+        let mut steps = vec![];
+        let mut len = self.width as usize;
+        let mut offset = 0usize;
+        let pos = self.pos as usize;
+        while len > 2 {
+            let div = len / 2 + len % 2;
+            let left = pos < offset + div;
+            steps.push(left);
+            if left {
+                len = div;
+            } else {
+                offset += div;
+                len -= div;
+            }
+        }
+        let expected = steps.len() + if len == 2 { 1 } else { 0 };
+        if self.path.len() != expected {
+            return None;
+        }
+
+        let mut depth = u5::with(steps.len() as u8);
+        let mut node = if len == 2 {
+            let sibling = self.path[steps.len()];
+            if pos == offset {
+                MerkleNode::branches(tag, depth, width, leaf, sibling)
+            } else {
+                MerkleNode::branches(tag, depth, width, sibling, leaf)
+            }
+        } else {
+            MerkleNode::single(tag, depth, width, leaf)
+        };
+        for (step, left) in steps.into_iter().enumerate().rev() {
+            let sibling = self.path[step];
+            depth = u5::with(step as u8);
+            node = if left {
+                MerkleNode::branches(tag, depth, width, node, sibling)
+            } else {
+                MerkleNode::branches(tag, depth, width, sibling, node)
+            };
+        }
+        Some(node)
+    }
+
+    /// Verifies that the `field` is a part of the structure with the given
+    /// merkle `root`.
+    pub fn verify(&self, tag: [u8; 16], field: &FieldValue, root: MerkleNode) -> bool {
+        self.convolve(tag, field) == Some(root)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{strategies, CommitStrategy};
+
+    struct Transfer {
+        from: u64,
+        to: u64,
+        amount: u64,
+        memo: SmallVec<u8>,
+        fee: u16,
+    }
+
+    impl StructuredCommit for Transfer {
+        const MERKLE_TAG: [u8; 16] = *b"urn:test:transfr";
+
+        fn fields(&self) -> Vec<FieldValue> {
+            vec![
+                FieldValue::with("from", &self.from),
+                FieldValue::with("to", &self.to),
+                FieldValue::with("amount", &self.amount),
+                FieldValue::with("memo", &self.memo),
+                FieldValue::with("fee", &self.fee),
+            ]
+        }
+    }
+
+    impl CommitStrategy for Transfer {
+        type Strategy = strategies::Structured;
+    }
+
+    #[test]
+    fn field_proofs() {
+        let transfer = Transfer {
+            from: 1,
+            to: 2,
+            amount: 100,
+            memo: Confined::try_from(b"rent".to_vec()).unwrap(),
+            fee: 3,
+        };
+        let tag = Transfer::MERKLE_TAG;
+        let root = transfer.structured_root().unwrap();
+        for field in transfer.fields() {
+            let proof = transfer.prove_field(&field.path).unwrap();
+            assert!(proof.verify(tag, &field, root));
+        }
+        assert_eq!(
+            transfer.prove_field("unknown"),
+            Err(FieldProofError::UnknownField("unknown".to_owned()))
+        );
+
+        let proof = transfer.prove_field("amount").unwrap();
+        assert!(!proof.verify(tag, &FieldValue::with("amount", &101u64), root));
+        assert!(!proof.verify(tag, &FieldValue::with("fee", &100u64), root));
+    }
+
+    #[test]
+    fn field_proofs_all_widths() {
+        for width in 1u16..=17 {
+            let fields = (0..width)
+                .map(|no| FieldValue::with(&format!("field{no}"), &no))
+                .collect::<Vec<_>>();
+            let leaves = fields
+                .iter()
+                .map(FieldValue::to_merkle_node)
+                .collect::<Vec<_>>();
+            let tag = *b"urn:test:widths ";
+            let root = merklize(tag, &leaves).unwrap();
+            for (pos, field) in fields.iter().enumerate() {
+                let proof = FieldProof::with(tag, &leaves, pos).unwrap();
+                assert!(proof.verify(tag, field, root), "width {width}, pos {pos}");
+            }
+        }
+    }
+
+    #[test]
+    fn too_many_fields() {
+        let tag = *b"urn:test:widths ";
+        let leaves = vec![MerkleNode::from([0u8; 32]); u16::MAX as usize + 1];
+        let err = FieldProofError::TooManyFields(u16::MAX as usize + 1);
+        assert_eq!(merklize(tag, &leaves), Err(err.clone()));
+        assert_eq!(FieldProof::with(tag, &leaves, u16::MAX as usize), Err(err));
+        assert!(merklize(tag, &leaves[1..]).is_ok());
+    }
+
+    #[test]
+    fn commit_encode() {
+        let transfer = Transfer {
+            from: 1,
+            to: 2,
+            amount: 100,
+            memo: default!(),
+            fee: 3,
+        };
+        let mut data = vec![];
+        transfer.commit_encode(&mut data);
+        assert_eq!(data, transfer.structured_root().unwrap().to_byte_array());
+    }
+}
@@ -0,0 +1,66 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compile-time checks of the public API, which are run in CI using the
+//! minimal supported rust version (MSRV).
+
+use commit_verify::blob::{BlobCommitment, BlobHasher, BlobProof};
+use commit_verify::merkle::MerkleNode;
+use commit_verify::mpc::{
+    self, Commitment, Leaf, MerkleBlock, MerkleProof, MerkleTree, Message, MultiSource, ProtocolId,
+    VerifyCheckpoint, VerifyProgress,
+};
+use commit_verify::store::{MemStore, ProofStore};
+use commit_verify::structured::{FieldProof, FieldValue};
+use commit_verify::{CommitEncode, CommitmentId, Conceal, TryCommitVerify};
+
+fn assert_commitment_id<T: CommitmentId>() {}
+fn assert_conceal<T: Conceal>() {}
+fn assert_commit_encode<T: CommitEncode>() {}
+
+#[test]
+fn public_api() {
+    assert_commitment_id::<MerkleTree>();
+    assert_commitment_id::<MerkleBlock>();
+    assert_commitment_id::<BlobCommitment>();
+    assert_commitment_id::<FieldValue>();
+    assert_commitment_id::<Leaf>();
+    assert_conceal::<MerkleBlock>();
+    assert_commit_encode::<MerkleProof>();
+    assert_commit_encode::<MerkleNode>();
+
+    let _: fn(&MultiSource) -> Result<MerkleTree, mpc::Error> = MerkleTree::try_commit;
+    let _: fn(&MerkleProof, ProtocolId, Message) -> Result<Commitment, mpc::InvalidProof> =
+        MerkleProof::convolve;
+    let _: fn(
+        &MerkleBlock,
+        Commitment,
+        Option<VerifyCheckpoint>,
+        u32,
+    ) -> Result<VerifyProgress, mpc::ResumableError> = MerkleBlock::verify_resumable;
+    let _: fn(&MerkleTree, u32) -> Option<Leaf> = MerkleTree::leaf;
+    let _: fn(&Leaf) -> MerkleNode = Leaf::to_merkle_node;
+    let _: fn(&[u8]) -> BlobCommitment = BlobCommitment::commit;
+    let _: fn(u32) -> BlobHasher = BlobHasher::new;
+    let _ = BlobProof::prove_range;
+    let _ = FieldProof::verify;
+    let _ = <MemStore as ProofStore>::load_block;
+}
@@ -0,0 +1,158 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sets of seals which must be closed atomically by a single witness.
+
+#[cfg(feature = "commit_verify")]
+use crate::caused_by;
+use crate::SealWitness;
+
+/// Set of seals which must be closed atomically by the same witness (like a
+/// single bitcoin transaction): either all of them or none. Used for atomic
+/// multi-asset swaps.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SealBundle<Seal> {
+    seals: Vec<Seal>,
+}
+
+/// Errors verifying closing of [`SealBundle`].
+#[derive(Clone, Copy, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SealBundleError<E: std::error::Error> {
+    /// seal bundle doesn't contain any seals.
+    Empty,
+
+    /// seal #{index} from the bundle is not closed by the bundle witness:
+    /// {error}
+    ClosedElsewhere {
+        /// Index of the seal in the bundle
+        index: usize,
+        /// Error verifying the seal
+        error: E,
+    },
+}
+
+#[cfg(feature = "commit_verify")]
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCode for SealBundleError<E> {
+    fn code(&self) -> &'static str {
+        match self {
+            SealBundleError::Empty => "seals.bundle.empty",
+            SealBundleError::ClosedElsewhere { .. } => "seals.bundle.closed-elsewhere",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            SealBundleError::Empty => vec![],
+            SealBundleError::ClosedElsewhere { index, error } => {
+                let mut params = vec![("index", index.to_string())];
+                params.extend(caused_by(error));
+                params
+            }
+        }
+    }
+}
+
+#[cfg(feature = "commit_verify")]
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCodes for SealBundleError<E> {
+    const CODES: &'static [&'static str] = &["seals.bundle.empty", "seals.bundle.closed-elsewhere"];
+}
+
+impl<Seal> FromIterator<Seal> for SealBundle<Seal> {
+    fn from_iter<T: IntoIterator<Item = Seal>>(iter: T) -> Self {
+        SealBundle {
+            seals: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<Seal> SealBundle<Seal> {
+    /// Constructs bundle from a set of seals.
+    pub fn new(seals: impl IntoIterator<Item = Seal>) -> Self { seals.into_iter().collect() }
+
+    /// Returns seals of the bundle.
+    pub fn seals(&self) -> &[Seal] { &self.seals }
+
+    /// Returns number of seals in the bundle.
+    pub fn len(&self) -> usize { self.seals.len() }
+
+    /// Detects whether the bundle doesn't contain any seals.
+    pub fn is_empty(&self) -> bool { self.seals.is_empty() }
+
+    /// Verifies that all seals of the bundle are closed over the message by
+    /// the same `witness`, returning the index of the first seal which is
+    /// not.
+    pub fn verify_seals<W>(
+        &self,
+        witness: &W,
+        msg: &W::Message,
+    ) -> Result<(), SealBundleError<W::Error>>
+    where
+        W: SealWitness<Seal>,
+    {
+        if self.seals.is_empty() {
+            return Err(SealBundleError::Empty);
+        }
+        for (index, seal) in self.seals.iter().enumerate() {
+            witness
+                .verify_seal(seal, msg)
+                .map_err(|error| SealBundleError::ClosedElsewhere { index, error })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn seal_bundle() {
+        let bundle = SealBundle::new([1, 2, 3]);
+        assert_eq!(bundle.len(), 3);
+        assert_eq!(bundle.seals(), &[1, 2, 3]);
+        bundle
+            .verify_seals(&witness([3, 2, 1], None), &MSG)
+            .unwrap();
+        assert!(matches!(
+            bundle.verify_seals(&witness([1, 3], None), &MSG),
+            Err(SealBundleError::ClosedElsewhere {
+                index: 1,
+                error: TestError::NotClosed
+            })
+        ));
+        assert!(matches!(
+            bundle.verify_seals(&witness([1, 2, 3], None), &(MSG + 1)),
+            Err(SealBundleError::ClosedElsewhere {
+                index: 0,
+                error: TestError::MessageMismatch
+            })
+        ));
+
+        let empty = SealBundle::<u8>::new([]);
+        assert!(empty.is_empty());
+        assert!(matches!(
+            empty.verify_seals(&witness([1], None), &MSG),
+            Err(SealBundleError::Empty)
+        ));
+    }
+}
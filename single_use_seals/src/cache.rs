@@ -0,0 +1,412 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Caching of the witnesses retrieved by witness resolvers.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::{WitnessHeight, WitnessResolver};
+
+/// Hooks for persisting the witnesses cached by [`CachedResolver`], allowing
+/// the cache to survive application restarts.
+///
+/// The unit type provides a no-op implementation used by default.
+pub trait WitnessCachePersistence<Id, Witness> {
+    /// Called when a witness resolved from the underlying resolver is added to
+    /// the cache.
+    fn persist(&self, id: &Id, witness: &Witness);
+
+    /// Called when a witness is removed from the cache because of eviction,
+    /// expiry or invalidation.
+    fn forget(&self, id: &Id);
+}
+
+impl<Id, Witness> WitnessCachePersistence<Id, Witness> for () {
+    fn persist(&self, _id: &Id, _witness: &Witness) {}
+    fn forget(&self, _id: &Id) {}
+}
+
+/// Configuration of [`CachedResolver`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CacheConfig {
+    /// Maximal number of cached witnesses; when exceeded, the least recently
+    /// used witness is evicted.
+    pub capacity: usize,
+
+    /// Time after which a cached witness must be resolved again; `None` if
+    /// witnesses never expire.
+    pub ttl: Option<Duration>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            capacity: 1024,
+            ttl: Some(Duration::from_secs(600)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry<Witness> {
+    witness: Witness,
+    cached_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct WitnessCache<Id, Witness> {
+    entries: BTreeMap<Id, CacheEntry<Witness>>,
+    /// Ids of the cached witnesses by the tick of their last use, such that
+    /// the least recently used witness is the first one.
+    recency: BTreeMap<u64, Id>,
+    tick: u64,
+}
+
+impl<Id: Ord + Clone, Witness> WitnessCache<Id, Witness> {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get(&mut self, id: &Id) -> Option<&CacheEntry<Witness>> {
+        let tick = self.next_tick();
+        let entry = self.entries.get_mut(id)?;
+        self.recency.remove(&entry.last_used);
+        self.recency.insert(tick, id.clone());
+        entry.last_used = tick;
+        Some(entry)
+    }
+
+    fn insert(&mut self, id: Id, witness: Witness) {
+        self.remove(&id);
+        let tick = self.next_tick();
+        self.recency.insert(tick, id.clone());
+        self.entries.insert(id, CacheEntry {
+            witness,
+            cached_at: Instant::now(),
+            last_used: tick,
+        });
+    }
+
+    fn remove(&mut self, id: &Id) -> bool {
+        let Some(entry) = self.entries.remove(id) else {
+            return false;
+        };
+        self.recency.remove(&entry.last_used);
+        true
+    }
+
+    fn pop_lru(&mut self) -> Option<Id> {
+        let (_, id) = self.recency.pop_first()?;
+        self.entries.remove(&id);
+        Some(id)
+    }
+}
+
+/// Caching decorator for [`WitnessResolver`], preventing repeated resolution
+/// of the same witnesses (like bitcoin transactions retrieved from Electrum
+/// servers) during verification.
+///
+/// The cache is least recently used (LRU) one, with entries expiring after the
+/// configured time to live (TTL). Since the cached witnesses may be affected
+/// by the chain re-organizations, the cache can be invalidated starting from a
+/// given height with [`CachedResolver::invalidate_from`]. Publication proofs
+/// are not cached and always verified by the underlying resolver.
+#[derive(Debug)]
+pub struct CachedResolver<R, Id, Witness, P = ()> {
+    resolver: R,
+    persistence: P,
+    config: CacheConfig,
+    cache: Mutex<WitnessCache<Id, Witness>>,
+}
+
+impl<R, Id, Witness> CachedResolver<R, Id, Witness>
+where Id: Ord + Clone
+{
+    /// Wraps `resolver` into a cache with the given configuration.
+    pub fn new(resolver: R, config: CacheConfig) -> Self {
+        CachedResolver::with_persistence(resolver, config, ())
+    }
+}
+
+impl<R, Id, Witness, P> CachedResolver<R, Id, Witness, P>
+where
+    Id: Ord + Clone,
+    P: WitnessCachePersistence<Id, Witness>,
+{
+    /// Wraps `resolver` into a cache with the given configuration, reporting
+    /// changes in the cached witnesses to `persistence` hooks.
+    pub fn with_persistence(resolver: R, config: CacheConfig, persistence: P) -> Self {
+        CachedResolver {
+            resolver,
+            persistence,
+            config,
+            cache: Mutex::new(WitnessCache {
+                entries: BTreeMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+            }),
+        }
+    }
+
+    /// Returns the underlying resolver.
+    pub fn resolver(&self) -> &R { &self.resolver }
+
+    /// Returns the cache configuration.
+    pub fn config(&self) -> CacheConfig { self.config }
+
+    /// Returns number of the witnesses in the cache, including expired ones
+    /// which were not removed yet.
+    pub fn len(&self) -> usize { self.lock().entries.len() }
+
+    /// Detects whether the cache is empty.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Adds witnesses to the cache, for instance loaded from a persistent
+    /// storage. The witnesses are not reported to the persistence hooks.
+    pub fn preload(&self, witnesses: impl IntoIterator<Item = (Id, Witness)>) {
+        let mut cache = self.lock();
+        for (id, witness) in witnesses {
+            self.insert(&mut cache, id, witness);
+        }
+    }
+
+    /// Removes a witness from the cache.
+    pub fn invalidate(&self, id: &Id) {
+        if self.lock().remove(id) {
+            self.persistence.forget(id);
+        }
+    }
+
+    /// Removes all witnesses published at `height` or above, and all
+    /// unpublished witnesses. Must be called on chain re-organizations.
+    pub fn invalidate_from(&self, height: u32)
+    where Witness: WitnessHeight {
+        let mut cache = self.lock();
+        let stale = cache
+            .entries
+            .iter()
+            .filter(|(_, entry)| !matches!(entry.witness.witness_height(), Some(h) if h < height))
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        for id in stale {
+            cache.remove(&id);
+            self.persistence.forget(&id);
+        }
+    }
+
+    /// Removes all witnesses from the cache.
+    pub fn clear(&self) {
+        let mut cache = self.lock();
+        for id in cache.entries.keys() {
+            self.persistence.forget(id);
+        }
+        cache.entries.clear();
+        cache.recency.clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, WitnessCache<Id, Witness>> {
+        // Cache is always left in a consistent state, so we can ignore the
+        // poisoning
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns whether the witness was actually cached, which is never the
+    /// case for a zero-capacity cache.
+    fn insert(&self, cache: &mut WitnessCache<Id, Witness>, id: Id, witness: Witness) -> bool {
+        if self.config.capacity == 0 {
+            return false;
+        }
+        while cache.entries.len() >= self.config.capacity && !cache.entries.contains_key(&id) {
+            let lru = cache.pop_lru().expect("non-zero capacity");
+            self.persistence.forget(&lru);
+        }
+        cache.insert(id, witness);
+        true
+    }
+}
+
+impl<R, Id, Witness, Proof, P> WitnessResolver<Id, Witness, Proof>
+    for CachedResolver<R, Id, Witness, P>
+where
+    R: WitnessResolver<Id, Witness, Proof>,
+    Id: Ord + Clone,
+    Witness: Clone,
+    P: WitnessCachePersistence<Id, Witness>,
+{
+    type Error = R::Error;
+
+    fn resolve_witness(&self, id: &Id) -> Result<Witness, Self::Error> {
+        let mut cache = self.lock();
+        let ttl = self.config.ttl;
+        match cache.get(id) {
+            Some(entry) if !matches!(ttl, Some(ttl) if entry.cached_at.elapsed() >= ttl) => {
+                return Ok(entry.witness.clone());
+            }
+            Some(_) => {
+                cache.remove(id);
+                self.persistence.forget(id);
+            }
+            None => {}
+        }
+        // We do not hold the lock while querying the resolver, which may take
+        // a while
+        drop(cache);
+
+        let witness = self.resolver.resolve_witness(id)?;
+        let mut cache = self.lock();
+        if self.insert(&mut cache, id.clone(), witness.clone()) {
+            self.persistence.persist(id, &witness);
+        }
+        Ok(witness)
+    }
+
+    fn verify_publication(&self, witness: &Witness, proof: &Proof) -> Result<(), Self::Error> {
+        self.resolver.verify_publication(witness, proof)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn cached_resolver() {
+        let witnesses = (1..=3).map(|id| (id, witness([id as u8], Some(id * 10))));
+        let config = CacheConfig {
+            capacity: 2,
+            ttl: None,
+        };
+        let cached = CachedResolver::new(resolver(witnesses), config);
+        let calls = || cached.resolver().calls.get();
+
+        assert_eq!(cached.resolve_witness(&1), Ok(witness([1], Some(10))));
+        assert_eq!(cached.resolve_witness(&1), Ok(witness([1], Some(10))));
+        assert_eq!(calls(), 1);
+        assert_eq!(cached.resolve_witness(&4), Err(TestError::Unknown));
+        assert_eq!(cached.len(), 1);
+
+        // Touching witness 1 makes witness 2 the least recently used one
+        cached.resolve_witness(&2).unwrap();
+        cached.resolve_witness(&1).unwrap();
+        cached.resolve_witness(&3).unwrap();
+        assert_eq!(cached.len(), 2);
+        assert_eq!(calls(), 4);
+        cached.resolve_witness(&1).unwrap();
+        cached.resolve_witness(&3).unwrap();
+        assert_eq!(calls(), 4);
+        cached.resolve_witness(&2).unwrap();
+        assert_eq!(calls(), 5);
+
+        cached.invalidate(&2);
+        cached.resolve_witness(&2).unwrap();
+        assert_eq!(calls(), 6);
+        cached.clear();
+        assert!(cached.is_empty());
+
+        let config = CacheConfig {
+            capacity: 0,
+            ttl: None,
+        };
+        let uncached = CachedResolver::new(resolver([(1, witness([1], None))]), config);
+        uncached.resolve_witness(&1).unwrap();
+        uncached.resolve_witness(&1).unwrap();
+        assert!(uncached.is_empty());
+        assert_eq!(uncached.resolver().calls.get(), 2);
+    }
+
+    #[test]
+    fn cached_resolver_expiry() {
+        let config = CacheConfig {
+            capacity: 16,
+            ttl: Some(Duration::ZERO),
+        };
+        let cached = CachedResolver::new(resolver([(1, witness([1], None))]), config);
+        cached.resolve_witness(&1).unwrap();
+        cached.resolve_witness(&1).unwrap();
+        assert_eq!(cached.resolver().calls.get(), 2);
+        assert_eq!(cached.len(), 1);
+
+        // Publication proofs are never cached
+        let witness = witness([1], Some(10));
+        cached.verify_publication(&witness, &10).unwrap();
+        assert_eq!(cached.verify_publication(&witness, &11), Err(TestError::Unpublished));
+    }
+
+    #[test]
+    fn cached_resolver_reorg() {
+        /// Records the calls to the persistence hooks.
+        #[derive(Default)]
+        struct Journal(RefCell<Vec<(&'static str, u32)>>);
+
+        impl WitnessCachePersistence<u32, TestWitness> for &Journal {
+            fn persist(&self, id: &u32, _witness: &TestWitness) {
+                self.0.borrow_mut().push(("persist", *id));
+            }
+            fn forget(&self, id: &u32) { self.0.borrow_mut().push(("forget", *id)); }
+        }
+
+        let journal = Journal::default();
+        let config = CacheConfig {
+            capacity: 16,
+            ttl: None,
+        };
+        let cached = CachedResolver::with_persistence(
+            resolver([(4, witness([4], Some(40)))]),
+            config,
+            &journal,
+        );
+        cached.preload([
+            (1, witness([1], Some(10))),
+            (2, witness([2], Some(20))),
+            (3, witness([3], None)),
+        ]);
+        cached.resolve_witness(&4).unwrap();
+        assert_eq!(cached.len(), 4);
+
+        cached.invalidate_from(20);
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached.resolve_witness(&1), Ok(witness([1], Some(10))));
+        assert_eq!(cached.resolve_witness(&2), Err(TestError::Unknown));
+        assert_eq!(journal.0.take(), vec![
+            ("persist", 4),
+            ("forget", 2),
+            ("forget", 3),
+            ("forget", 4)
+        ]);
+
+        // Nothing is persisted by a cache which does not keep the witnesses
+        let config = CacheConfig {
+            capacity: 0,
+            ttl: None,
+        };
+        let uncached =
+            CachedResolver::with_persistence(resolver([(1, witness([1], None))]), config, &journal);
+        uncached.resolve_witness(&1).unwrap();
+        assert!(journal.0.take().is_empty());
+    }
+}
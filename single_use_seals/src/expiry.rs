@@ -0,0 +1,199 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Seals which can't be closed after some height.
+
+#[cfg(feature = "commit_verify")]
+use crate::caused_by;
+use crate::{SealWitness, WitnessHeight};
+
+/// Seal definition with an optional expiry height, after which the seal can't
+/// be closed anymore. Used for time-bounded offers and escrows.
+///
+/// With `commit_verify` feature the expiry is committed together with the
+/// seal definition, preceded by a tag byte (`0x00` for seals without expiry
+/// and `0x01` for seals with it), such that the encoding of a seal without
+/// expiry can't collide with the encoding of another seal with expiry.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ExpiringSeal<Seal> {
+    /// Seal definition.
+    pub seal: Seal,
+
+    /// The last height at which the seal may be closed.
+    pub expiry: Option<u32>,
+}
+
+/// Errors verifying closing of [`ExpiringSeal`].
+#[derive(Clone, Copy, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ExpiringSealError<E: std::error::Error> {
+    /// the seal expiring at height {expiry} was closed at height {height}.
+    Expired {
+        /// Seal expiry height
+        expiry: u32,
+        /// Height of the witness publication
+        height: u32,
+    },
+
+    /// the seal has an expiry, but the witness is not published.
+    Unpublished,
+
+    /// the seal is not closed over the message by the witness: {0}
+    Seal(E),
+}
+
+#[cfg(feature = "commit_verify")]
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCode for ExpiringSealError<E> {
+    fn code(&self) -> &'static str {
+        match self {
+            ExpiringSealError::Expired { .. } => "seals.expiring.expired",
+            ExpiringSealError::Unpublished => "seals.expiring.unpublished",
+            ExpiringSealError::Seal(_) => "seals.expiring.not-closed",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ExpiringSealError::Expired { expiry, height } => {
+                vec![("expiry", expiry.to_string()), ("height", height.to_string())]
+            }
+            ExpiringSealError::Unpublished => vec![],
+            ExpiringSealError::Seal(err) => caused_by(err),
+        }
+    }
+}
+
+#[cfg(feature = "commit_verify")]
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCodes for ExpiringSealError<E> {
+    const CODES: &'static [&'static str] =
+        &["seals.expiring.expired", "seals.expiring.unpublished", "seals.expiring.not-closed"];
+}
+
+impl<Seal> From<Seal> for ExpiringSeal<Seal> {
+    fn from(seal: Seal) -> Self { ExpiringSeal::new(seal) }
+}
+
+impl<Seal> ExpiringSeal<Seal> {
+    /// Constructs seal without expiry.
+    pub fn new(seal: Seal) -> Self { ExpiringSeal { seal, expiry: None } }
+
+    /// Constructs seal which can be closed no later than at `expiry` height.
+    pub fn with_expiry(seal: Seal, expiry: u32) -> Self {
+        ExpiringSeal {
+            seal,
+            expiry: Some(expiry),
+        }
+    }
+
+    /// Detects whether the seal can't be closed at the given `height`.
+    pub fn is_expired_at(&self, height: u32) -> bool {
+        matches!(self.expiry, Some(expiry) if height > expiry)
+    }
+
+    /// Verifies that the seal was closed over the message with the witness
+    /// not later than at the expiry height.
+    pub fn verify_seal<W>(
+        &self,
+        witness: &W,
+        msg: &W::Message,
+    ) -> Result<(), ExpiringSealError<W::Error>>
+    where
+        W: SealWitness<Seal> + WitnessHeight,
+    {
+        if let Some(expiry) = self.expiry {
+            let height = witness
+                .witness_height()
+                .ok_or(ExpiringSealError::Unpublished)?;
+            if height > expiry {
+                return Err(ExpiringSealError::Expired { expiry, height });
+            }
+        }
+        witness
+            .verify_seal(&self.seal, msg)
+            .map_err(ExpiringSealError::Seal)
+    }
+}
+
+#[cfg(feature = "commit_verify")]
+impl<Seal: commit_verify::CommitEncode> commit_verify::CommitEncode for ExpiringSeal<Seal> {
+    fn commit_encode(&self, e: &mut impl std::io::Write) {
+        self.seal.commit_encode(e);
+        match self.expiry {
+            None => 0u8.commit_encode(e),
+            Some(expiry) => {
+                1u8.commit_encode(e);
+                expiry.commit_encode(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn expiring_seal() {
+        let seal = ExpiringSeal::new(1);
+        assert!(!seal.is_expired_at(u32::MAX));
+        seal.verify_seal(&witness([1], None), &MSG).unwrap();
+        assert!(matches!(
+            seal.verify_seal(&witness([2], Some(100)), &MSG),
+            Err(ExpiringSealError::Seal(TestError::NotClosed))
+        ));
+
+        let seal = ExpiringSeal::with_expiry(1, 100);
+        assert!(!seal.is_expired_at(100));
+        assert!(seal.is_expired_at(101));
+        seal.verify_seal(&witness([1], Some(100)), &MSG).unwrap();
+        assert!(matches!(
+            seal.verify_seal(&witness([1], Some(101)), &MSG),
+            Err(ExpiringSealError::Expired {
+                expiry: 100,
+                height: 101
+            })
+        ));
+        assert!(matches!(
+            seal.verify_seal(&witness([1], None), &MSG),
+            Err(ExpiringSealError::Unpublished)
+        ));
+        assert!(matches!(
+            seal.verify_seal(&witness([2], Some(99)), &MSG),
+            Err(ExpiringSealError::Seal(TestError::NotClosed))
+        ));
+    }
+
+    #[cfg(feature = "commit_verify")]
+    #[test]
+    fn expiring_seal_encoding() {
+        use commit_verify::CommitEncode;
+
+        fn encode(seal: ExpiringSeal<u8>) -> Vec<u8> {
+            let mut data = vec![];
+            seal.commit_encode(&mut data);
+            data
+        }
+
+        assert_eq!(encode(ExpiringSeal::new(7)), vec![7, 0]);
+        assert_eq!(encode(ExpiringSeal::with_expiry(7, 0x0302_0100)), vec![7, 1, 0, 1, 2, 3]);
+    }
+}
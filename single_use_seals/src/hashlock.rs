@@ -0,0 +1,149 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Seal definitions committed under a hash lock for deferred reveal.
+
+use commit_verify::{CommitEncode, Digest, DigestExt, Sha256};
+
+/// Commitment to a seal definition (like a revealed outpoint) under a hash
+/// lock, used in deferred-reveal flows of swap protocols.
+///
+/// The counterparty receives the commitment and the hash lock immediately,
+/// but can verify the revealed seal definition only after the preimage of the
+/// hash lock gets disclosed (for instance, by claiming an HTLC). The preimage
+/// also blinds the commitment, such that the seal definition can't be guessed
+/// from it before the disclosure.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct HashLockedSeal {
+    /// SHA256 hash of the preimage, as used in HTLC scripts.
+    pub hash_lock: [u8; 32],
+
+    /// Tagged hash committing to the preimage and the seal definition.
+    pub commitment: [u8; 32],
+}
+
+/// Errors verifying reveal of [`HashLockedSeal`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum HashLockError {
+    /// the preimage doesn't match the hash lock.
+    PreimageMismatch,
+
+    /// the revealed seal definition doesn't match the commitment.
+    RevealMismatch,
+}
+
+impl commit_verify::ErrorCode for HashLockError {
+    fn code(&self) -> &'static str {
+        match self {
+            HashLockError::PreimageMismatch => "seals.hashlock.preimage-mismatch",
+            HashLockError::RevealMismatch => "seals.hashlock.reveal-mismatch",
+        }
+    }
+}
+
+impl commit_verify::ErrorCodes for HashLockError {
+    const CODES: &'static [&'static str] =
+        &["seals.hashlock.preimage-mismatch", "seals.hashlock.reveal-mismatch"];
+}
+
+impl HashLockedSeal {
+    /// Tag of the commitment hash.
+    pub const TAG: [u8; 32] = *b"urn:lnpbp:seals:hashlock:v01#23A";
+
+    /// Commits to the `seal` definition under the hash lock of the `preimage`.
+    pub fn lock<Seal: CommitEncode>(seal: &Seal, preimage: [u8; 32]) -> Self {
+        HashLockedSeal {
+            hash_lock: Self::hash_lock(preimage),
+            commitment: Self::commit(seal, preimage),
+        }
+    }
+
+    /// Commits to the `seal` definition under the hash lock of a preimage
+    /// generated with the provided `rng`, returning the preimage together with
+    /// the locked seal.
+    #[cfg(feature = "rand")]
+    pub fn lock_with_rng<Seal: CommitEncode>(
+        seal: &Seal,
+        rng: &mut (impl rand::RngCore + rand::CryptoRng),
+    ) -> (Self, [u8; 32]) {
+        let mut preimage = [0u8; 32];
+        rng.fill_bytes(&mut preimage);
+        (Self::lock(seal, preimage), preimage)
+    }
+
+    /// Verifies the disclosed `preimage` against the hash lock and the
+    /// revealed `seal` definition against the commitment.
+    pub fn verify_reveal<Seal: CommitEncode>(
+        &self,
+        seal: &Seal,
+        preimage: [u8; 32],
+    ) -> Result<(), HashLockError> {
+        if Self::hash_lock(preimage) != self.hash_lock {
+            return Err(HashLockError::PreimageMismatch);
+        }
+        if Self::commit(seal, preimage) != self.commitment {
+            return Err(HashLockError::RevealMismatch);
+        }
+        Ok(())
+    }
+
+    fn hash_lock(preimage: [u8; 32]) -> [u8; 32] { Sha256::digest(preimage).into() }
+
+    fn commit<Seal: CommitEncode>(seal: &Seal, preimage: [u8; 32]) -> [u8; 32] {
+        let mut engine = Sha256::from_tag(Self::TAG);
+        engine.input_raw(&preimage);
+        seal.commit_encode(&mut engine);
+        engine.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hash_locked_seal() {
+        let preimage = [7u8; 32];
+        let locked = HashLockedSeal::lock(&1u8, preimage);
+        assert_eq!(locked.hash_lock, <[u8; 32]>::from(Sha256::digest(preimage)));
+        locked.verify_reveal(&1u8, preimage).unwrap();
+        assert_eq!(locked.verify_reveal(&1u8, [8u8; 32]), Err(HashLockError::PreimageMismatch));
+        assert_eq!(locked.verify_reveal(&2u8, preimage), Err(HashLockError::RevealMismatch));
+
+        // The preimage blinds the commitment to the seal definition
+        assert_ne!(locked.commitment, HashLockedSeal::lock(&1u8, [8u8; 32]).commitment);
+        assert_ne!(locked.commitment, HashLockedSeal::lock(&2u8, preimage).commitment);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn hash_locked_seal_with_rng() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let (locked, preimage) = HashLockedSeal::lock_with_rng(&1u8, &mut StdRng::seed_from_u64(1));
+        assert_eq!(locked, HashLockedSeal::lock(&1u8, preimage));
+        locked.verify_reveal(&1u8, preimage).unwrap();
+        let (other, _) = HashLockedSeal::lock_with_rng(&1u8, &mut StdRng::seed_from_u64(2));
+        assert_ne!(locked, other);
+    }
+}
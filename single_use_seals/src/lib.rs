@@ -98,6 +98,13 @@
 //! The module provides two options of implementing such medium: synchronous
 //! [`SealProtocol`] and asynchronous `SealProtocolAsync`.
 //!
+//! ## Error codes
+//!
+//! With `commit_verify` feature the errors of this crate implement
+//! `commit_verify::ErrorCode`, providing stable codes with `seals.` prefix for
+//! the localization of the error messages. The codes each error may report are
+//! listed in its `commit_verify::ErrorCodes::CODES`.
+//!
 //! ## Sample implementation
 //!
 //! Examples of implementations can be found in `bp::seals` module of `bp-core`
@@ -117,6 +124,36 @@ extern crate amplify_derive;
 #[cfg(feature = "async")]
 #[macro_use]
 extern crate async_trait;
+#[cfg(feature = "commit_verify")]
+#[macro_use]
+extern crate strict_encoding;
+
+mod bundle;
+mod cache;
+mod expiry;
+#[cfg(feature = "commit_verify")]
+mod hashlock;
+mod policy;
+#[cfg(feature = "commit_verify")]
+mod transition;
+mod witness;
+
+pub use bundle::{SealBundle, SealBundleError};
+pub use cache::{CacheConfig, CachedResolver, WitnessCachePersistence};
+pub use expiry::{ExpiringSeal, ExpiringSealError};
+#[cfg(feature = "commit_verify")]
+pub use hashlock::{HashLockError, HashLockedSeal};
+pub use policy::{SealPolicy, SealPolicyError};
+#[cfg(feature = "commit_verify")]
+pub use transition::{BundleCommitment, TransitionBundle, TransitionBundleError};
+pub use witness::{
+    LeveledWitness, LeveledWitnessError, SealEvidence, Witness, WitnessHeight, WitnessLevel,
+    WitnessResolver,
+};
+
+/// Name of the strict type library for the single-use-seals data types.
+#[cfg(feature = "commit_verify")]
+pub const LIB_NAME_SINGLE_USE_SEALS: &str = "SingleUseSeals";
 
 /// Trait for proof-of-publication medium on which the seals are defined,
 /// closed, verified and which can be used for convenience operations related to
@@ -284,6 +321,33 @@ pub trait SealWitness<Seal> {
         }
         Ok(())
     }
+
+    /// Verifies that the seal was closed over the message with the witness,
+    /// and that the way the seal was closed satisfies the `policy`.
+    fn verify_seal_with_policy<P>(
+        &self,
+        seal: &Seal,
+        msg: &Self::Message,
+        policy: &P,
+    ) -> Result<(), SealPolicyError<Self::Error, P::Violation>>
+    where
+        Self: Sized,
+        P: SealPolicy<Seal, Self>,
+    {
+        self.verify_seal(seal, msg).map_err(SealPolicyError::Seal)?;
+        policy
+            .check_closing(seal, self)
+            .map_err(SealPolicyError::Violation)
+    }
+}
+
+/// Parameters of an error variant caused by another error: the code of the
+/// cause followed by its own parameters.
+#[cfg(feature = "commit_verify")]
+pub(crate) fn caused_by(err: &impl commit_verify::ErrorCode) -> Vec<(&'static str, String)> {
+    let mut params = vec![("cause", err.code().to_owned())];
+    params.extend(err.params());
+    params
 }
 
 /// Asynchronous version of the [`SealProtocol`] trait.
@@ -492,10 +556,187 @@ pub enum SealStatus {
 #[derive(Clone, Copy, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum SealMediumError<E: std::error::Error> {
-    /// Can't access the publication medium
+    /// can't access the publication medium: {0}
     #[from]
     MediumAccessError(E),
 
-    /// Publication id is not supported
+    /// publication id is not supported by the medium.
     PublicationNotSupported,
 }
+
+#[cfg(feature = "commit_verify")]
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCode for SealMediumError<E> {
+    fn code(&self) -> &'static str {
+        match self {
+            SealMediumError::MediumAccessError(_) => "seals.medium.access",
+            SealMediumError::PublicationNotSupported => "seals.medium.not-supported",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            SealMediumError::MediumAccessError(err) => caused_by(err),
+            SealMediumError::PublicationNotSupported => vec![],
+        }
+    }
+}
+
+#[cfg(feature = "commit_verify")]
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCodes for SealMediumError<E> {
+    const CODES: &'static [&'static str] = &["seals.medium.access", "seals.medium.not-supported"];
+}
+
+/// Fixtures shared by the tests of the crate modules.
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+    use std::marker::PhantomData;
+
+    use super::*;
+
+    pub const MSG: u64 = 42;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Error)]
+    #[display(doc_comments)]
+    pub enum TestError {
+        /// the seal is not closed by the witness.
+        NotClosed,
+
+        /// the witness closes seals over a different message.
+        MessageMismatch,
+
+        /// the witness is not known to the resolver.
+        Unknown,
+
+        /// the witness is not published at the proven height.
+        Unpublished,
+    }
+
+    /// Log entry closing the listed seals over the message.
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub struct Entry<Msg> {
+        pub closes: Vec<u8>,
+        pub message: Msg,
+        pub height: Option<u32>,
+    }
+
+    /// Append-only log with seals defined as single bytes.
+    pub struct Log<Msg>(PhantomData<Msg>);
+
+    impl<Msg: Eq> SealEvidence for Log<Msg> {
+        type Seal = u8;
+        type Message = Msg;
+        type Evidence = Entry<Msg>;
+        type Error = TestError;
+
+        fn verify_evidence(seal: &u8, evidence: &Entry<Msg>, msg: &Msg) -> Result<(), TestError> {
+            if evidence.message != *msg {
+                return Err(TestError::MessageMismatch);
+            }
+            if !evidence.closes.contains(seal) {
+                return Err(TestError::NotClosed);
+            }
+            Ok(())
+        }
+
+        fn evidence_height(evidence: &Entry<Msg>) -> Option<u32> { evidence.height }
+    }
+
+    pub type TestWitness<Msg = u64> = Witness<Log<Msg>>;
+
+    pub fn witness(closes: impl Into<Vec<u8>>, height: Option<u32>) -> TestWitness {
+        Witness::new(Entry {
+            closes: closes.into(),
+            message: MSG,
+            height,
+        })
+    }
+
+    /// Resolver of the witnesses by their ids, accepting the witness height as
+    /// the proof of its publication.
+    pub struct Resolver {
+        witnesses: BTreeMap<u32, TestWitness>,
+        pub calls: Cell<usize>,
+    }
+
+    pub fn resolver(witnesses: impl IntoIterator<Item = (u32, TestWitness)>) -> Resolver {
+        Resolver {
+            witnesses: witnesses.into_iter().collect(),
+            calls: Cell::new(0),
+        }
+    }
+
+    impl WitnessResolver<u32, TestWitness, u32> for Resolver {
+        type Error = TestError;
+
+        fn resolve_witness(&self, id: &u32) -> Result<TestWitness, TestError> {
+            self.calls.set(self.calls.get() + 1);
+            self.witnesses.get(id).cloned().ok_or(TestError::Unknown)
+        }
+
+        fn verify_publication(&self, witness: &TestWitness, height: &u32) -> Result<(), TestError> {
+            if witness.witness_height() != Some(*height) {
+                return Err(TestError::Unpublished);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "commit_verify"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tags() {
+        use commit_verify::stability::TagRegistry;
+
+        let mut registry = TagRegistry::library();
+        registry
+            .register::<BundleCommitment>()
+            .register_tag("HashLockedSeal::TAG", HashLockedSeal::TAG);
+        registry.check().unwrap();
+    }
+
+    #[test]
+    fn error_codes() {
+        use std::collections::BTreeSet;
+
+        use commit_verify::{ErrorCode, ErrorCodes, VerifyError};
+
+        // Error types with the codes of the errors they wrap and report as-is
+        type E = VerifyError;
+        let catalog: [(_, &[&[&str]]); 7] = [
+            (SealPolicyError::<E, E>::CODES, &[]),
+            (LeveledWitnessError::<E, E>::CODES, &[]),
+            (ExpiringSealError::<E>::CODES, &[]),
+            (HashLockError::CODES, &[]),
+            (SealBundleError::<E>::CODES, &[]),
+            (TransitionBundleError::<E>::CODES, &[SealBundleError::<E>::CODES]),
+            (SealMediumError::<E>::CODES, &[]),
+        ];
+        let mut own = BTreeSet::new();
+        for (codes, wrapped) in catalog {
+            let wrapped = wrapped.concat();
+            assert!(wrapped.iter().all(|code| codes.contains(code)));
+            for code in codes.iter().filter(|code| !wrapped.contains(*code)) {
+                assert!(code.starts_with("seals."));
+                assert!(own.insert(*code), "code {code} is used twice");
+            }
+        }
+
+        let cause = VerifyError::InvalidCommitment;
+        let err = SealBundleError::ClosedElsewhere {
+            index: 1,
+            error: cause,
+        };
+        assert_eq!(err.params(), vec![
+            ("index", "1".to_owned()),
+            ("cause", "commit.mismatch".to_owned())
+        ]);
+        let err = TransitionBundleError::Seals(err);
+        assert_eq!(err.code(), "seals.bundle.closed-elsewhere");
+        assert!(TransitionBundleError::<E>::CODES.contains(&err.code()));
+    }
+}
@@ -0,0 +1,115 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Policies constraining the way seals may be closed.
+
+#[cfg(feature = "commit_verify")]
+use crate::caused_by;
+
+/// Policy constraining the way a seal may be closed by a witness (for
+/// instance, requiring the closing bitcoin input to be a key-path spend, or to
+/// satisfy some output descriptor).
+pub trait SealPolicy<Seal, Witness> {
+    /// Error describing the policy violation
+    type Violation: std::error::Error;
+
+    /// Checks that the closing of the seal by the witness satisfies the
+    /// policy.
+    fn check_closing(&self, seal: &Seal, witness: &Witness) -> Result<(), Self::Violation>;
+}
+
+/// Errors verifying seal closing under some [`SealPolicy`].
+#[derive(Clone, Copy, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SealPolicyError<S: std::error::Error, P: std::error::Error> {
+    /// the seal is not closed over the message by the witness: {0}
+    Seal(S),
+
+    /// seal closing violates the policy: {0}
+    Violation(P),
+}
+
+#[cfg(feature = "commit_verify")]
+impl<S, P> commit_verify::ErrorCode for SealPolicyError<S, P>
+where
+    S: commit_verify::ErrorCode,
+    P: commit_verify::ErrorCode,
+{
+    fn code(&self) -> &'static str {
+        match self {
+            SealPolicyError::Seal(_) => "seals.policy.not-closed",
+            SealPolicyError::Violation(_) => "seals.policy.violation",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            SealPolicyError::Seal(err) => caused_by(err),
+            SealPolicyError::Violation(err) => caused_by(err),
+        }
+    }
+}
+
+#[cfg(feature = "commit_verify")]
+impl<S, P> commit_verify::ErrorCodes for SealPolicyError<S, P>
+where
+    S: commit_verify::ErrorCode,
+    P: commit_verify::ErrorCode,
+{
+    const CODES: &'static [&'static str] = &["seals.policy.not-closed", "seals.policy.violation"];
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::*;
+    use crate::{SealWitness, WitnessHeight};
+
+    #[test]
+    fn seal_policy() {
+        /// Requires seals to be closed at or above the given height.
+        struct MinHeight(u32);
+
+        impl SealPolicy<u8, TestWitness> for MinHeight {
+            type Violation = TestError;
+
+            fn check_closing(&self, _seal: &u8, witness: &TestWitness) -> Result<(), TestError> {
+                match witness.witness_height() {
+                    Some(height) if height >= self.0 => Ok(()),
+                    _ => Err(TestError::Unpublished),
+                }
+            }
+        }
+
+        let policy = MinHeight(100);
+        witness([1], Some(100))
+            .verify_seal_with_policy(&1, &MSG, &policy)
+            .unwrap();
+        assert!(matches!(
+            witness([1], Some(99)).verify_seal_with_policy(&1, &MSG, &policy),
+            Err(SealPolicyError::Violation(TestError::Unpublished))
+        ));
+        assert!(matches!(
+            witness([1], None).verify_seal_with_policy(&2, &MSG, &policy),
+            Err(SealPolicyError::Seal(TestError::NotClosed))
+        ));
+    }
+}
@@ -0,0 +1,247 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! State transition bundles anchored with LNPBP-4 commitments.
+
+use amplify::confinement::{self, MediumVec};
+use amplify::Bytes32;
+use commit_verify::mpc::{ClosedSeals, Commitment, InvalidProof, MerkleProof, Message, ProtocolId};
+use commit_verify::{CommitEncode, CommitmentId};
+
+use crate::{SealBundle, SealBundleError, SealWitness, LIB_NAME_SINGLE_USE_SEALS};
+
+/// Strict-encoded commitment to a [`TransitionBundle`]. Its commitment id is
+/// placed into the LNPBP-4 tree under the bundle protocol id.
+///
+/// Seals are represented by their hashes (see [`ClosedSeals::seal_hash`]), such
+/// that the commitment has the same layout for all seal types.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_SINGLE_USE_SEALS)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict)]
+pub struct BundleCommitment {
+    /// Protocol under which the bundle is committed in the LNPBP-4 tree.
+    pub protocol_id: ProtocolId,
+
+    /// Hashes of the seals closed by the transition.
+    pub closed: MediumVec<Bytes32>,
+
+    /// Hashes of the new seals defined by the transition.
+    pub defined: MediumVec<Bytes32>,
+
+    /// Message committed by the transition.
+    pub message: Bytes32,
+}
+
+impl strict_encoding::StrictSerialize for BundleCommitment {}
+impl strict_encoding::StrictDeserialize for BundleCommitment {}
+
+impl CommitmentId for BundleCommitment {
+    const TAG: [u8; 32] = *b"urn:lnpbp:seals:bundleid:v01#23A";
+    type Id = Message;
+}
+
+/// State transition bundle anchored with LNPBP-4 commitment: a set of the
+/// closed seals, the new seal definitions and the committed message, together
+/// with the anchor proving the commitment and the witness closing the seals.
+///
+/// The bundle commits to the closed and the new seals, the message and the
+/// protocol id with [`BundleCommitment`]; its commitment id is placed into the
+/// LNPBP-4 tree under the bundle protocol id. All closed seals must be closed
+/// over the resulting LNPBP-4 commitment by the same witness.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TransitionBundle<Seal, W> {
+    /// Seals closed by the transition.
+    pub closed: SealBundle<Seal>,
+
+    /// New seals defined by the transition.
+    pub defined: Vec<Seal>,
+
+    /// Message committed by the transition (like a state transition id).
+    pub message: [u8; 32],
+
+    /// Protocol under which the bundle is committed in the LNPBP-4 tree.
+    pub protocol_id: ProtocolId,
+
+    /// LNPBP-4 proof of the bundle commitment.
+    pub proof: MerkleProof,
+
+    /// Witness closing the seals over the LNPBP-4 commitment.
+    pub witness: W,
+}
+
+/// Errors verifying [`TransitionBundle`].
+#[derive(Clone, Copy, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TransitionBundleError<E: std::error::Error> {
+    /// bundle contains too many seals to be committed.
+    TooManySeals,
+
+    /// invalid bundle anchor: {0}
+    Anchor(InvalidProof),
+
+    /// {0}
+    Seals(SealBundleError<E>),
+}
+
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCode for TransitionBundleError<E> {
+    fn code(&self) -> &'static str {
+        match self {
+            TransitionBundleError::TooManySeals => "seals.transition.too-many-seals",
+            TransitionBundleError::Anchor(_) => "seals.transition.invalid-anchor",
+            TransitionBundleError::Seals(err) => err.code(),
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            TransitionBundleError::TooManySeals => vec![],
+            TransitionBundleError::Anchor(err) => err.params(),
+            TransitionBundleError::Seals(err) => err.params(),
+        }
+    }
+}
+
+impl<E: commit_verify::ErrorCode> commit_verify::ErrorCodes for TransitionBundleError<E> {
+    const CODES: &'static [&'static str] = &[
+        "seals.transition.too-many-seals",
+        "seals.transition.invalid-anchor",
+        "seals.bundle.empty",
+        "seals.bundle.closed-elsewhere",
+    ];
+}
+
+impl<Seal: CommitEncode, W> TransitionBundle<Seal, W> {
+    /// Constructs commitment to the closed and the new seals, the message and
+    /// the protocol id of the bundle, failing if the bundle contains more seals
+    /// than [`MediumVec`] can hold.
+    pub fn to_commitment(&self) -> Result<BundleCommitment, confinement::Error> {
+        Ok(BundleCommitment {
+            protocol_id: self.protocol_id,
+            closed: MediumVec::try_from_iter(
+                self.closed.seals().iter().map(ClosedSeals::seal_hash),
+            )?,
+            defined: MediumVec::try_from_iter(self.defined.iter().map(ClosedSeals::seal_hash))?,
+            message: self.message.into(),
+        })
+    }
+
+    /// Computes id of the bundle commitment (see [`Self::to_commitment`]).
+    pub fn commitment_id(&self) -> Result<Message, confinement::Error> {
+        self.to_commitment()
+            .map(|commitment| commitment.commitment_id())
+    }
+
+    /// Verifies that the bundle is committed in the LNPBP-4 commitment proven
+    /// by the anchor, and that all closed seals are closed over this
+    /// commitment by the bundle witness. Returns the LNPBP-4 commitment.
+    pub fn verify(&self) -> Result<Commitment, TransitionBundleError<W::Error>>
+    where W: SealWitness<Seal, Message = Commitment> {
+        let message = self
+            .commitment_id()
+            .map_err(|_| TransitionBundleError::TooManySeals)?;
+        let commitment = self
+            .proof
+            .convolve(self.protocol_id, message)
+            .map_err(TransitionBundleError::Anchor)?;
+        self.closed
+            .verify_seals(&self.witness, &commitment)
+            .map_err(TransitionBundleError::Seals)?;
+        Ok(commitment)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::*;
+    use crate::Witness;
+
+    #[test]
+    fn transition_bundle() {
+        use commit_verify::mpc::{MerkleBlock, MerkleTree, MultiSource};
+        use commit_verify::TryCommitVerify;
+
+        let protocol_id = ProtocolId::from([1u8; 32]);
+        let anchor = |message: [u8; 32]| {
+            // Static entropy since `rand` feature of commit_verify may be off
+            let mut source = MultiSource::with_static_entropy(0);
+            source.extend([
+                (protocol_id, Message::from(message)),
+                (ProtocolId::from([2u8; 32]), Message::from([0xFFu8; 32])),
+            ]);
+            let tree = MerkleTree::try_commit(&source).unwrap();
+            let proof = MerkleBlock::from(&tree)
+                .to_merkle_proof(protocol_id)
+                .unwrap();
+            (proof, tree.commitment_id())
+        };
+        let closing = |closes: &[u8], commitment: Commitment| {
+            Witness::<Log<Commitment>>::new(Entry {
+                closes: closes.to_vec(),
+                message: commitment,
+                height: None,
+            })
+        };
+
+        let (proof, commitment) = anchor([0u8; 32]);
+        let mut bundle = TransitionBundle {
+            closed: SealBundle::new([1, 2]),
+            defined: vec![3],
+            message: [5u8; 32],
+            protocol_id,
+            proof,
+            witness: closing(&[1, 2], commitment),
+        };
+        let (proof, commitment) = anchor(bundle.commitment_id().unwrap().to_byte_array());
+        bundle.proof = proof;
+        bundle.witness = closing(&[1, 2], commitment);
+        assert_eq!(bundle.verify().unwrap(), commitment);
+
+        bundle.witness = closing(&[1], commitment);
+        assert!(matches!(
+            bundle.verify(),
+            Err(TransitionBundleError::Seals(SealBundleError::ClosedElsewhere {
+                index: 1,
+                error: TestError::NotClosed
+            }))
+        ));
+
+        // Any change to the bundle changes the anchored commitment
+        bundle.witness = closing(&[1, 2], commitment);
+        let mut tampered = bundle.clone();
+        tampered.defined = vec![4];
+        assert_ne!(tampered.commitment_id().unwrap(), bundle.commitment_id().unwrap());
+        tampered.defined = bundle.defined.clone();
+        tampered.protocol_id = ProtocolId::from([2u8; 32]);
+        assert_ne!(tampered.commitment_id().unwrap(), bundle.commitment_id().unwrap());
+        tampered.protocol_id = bundle.protocol_id;
+        tampered.defined = vec![4];
+        assert!(matches!(
+            tampered.verify(),
+            Err(TransitionBundleError::Seals(SealBundleError::ClosedElsewhere {
+                index: 0,
+                error: TestError::MessageMismatch
+            }))
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+// Client-side-validation foundation libraries.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Seal witnesses in generic publication media and witnesses provided with
+//! different levels of trust.
+
+#[cfg(feature = "commit_verify")]
+use crate::caused_by;
+use crate::SealWitness;
+
+/// Evidence of the seal closing in the publication media other than bitcoin
+/// transactions: federated logs, other chains etc.
+///
+/// Unlike [`SealProtocol`](crate::SealProtocol), which provides access to the
+/// medium for checking seal status and publishing witnesses, this trait only
+/// defines the type of evidence of the seal closing and its verification.
+/// [`Witness`] wraps this evidence into a [`SealWitness`], such that seals
+/// defined over the medium share the proof containers ([`LeveledWitness`])
+/// and validation pipeline with bitcoin-based seals.
+pub trait SealEvidence {
+    /// Seal definition in the medium.
+    type Seal;
+
+    /// Message type the seals are closed over.
+    type Message;
+
+    /// Medium-specific evidence of the seal closing (like an entry of the log
+    /// signed by the federation).
+    type Evidence;
+
+    /// Error type that contains reasons of the evidence verification failure.
+    type Error: std::error::Error;
+
+    /// Verifies that the seal was closed over the message with the evidence.
+    fn verify_evidence(
+        seal: &Self::Seal,
+        evidence: &Self::Evidence,
+        msg: &Self::Message,
+    ) -> Result<(), Self::Error>;
+
+    /// Returns height (log index, block height etc) of the evidence
+    /// publication, if it is known.
+    fn evidence_height(_evidence: &Self::Evidence) -> Option<u32> { None }
+}
+
+/// Seal closing witness in a generic publication medium `M`.
+pub struct Witness<M: SealEvidence> {
+    /// Medium-specific evidence of the seal closing.
+    pub evidence: M::Evidence,
+}
+
+impl<M: SealEvidence> Witness<M> {
+    /// Constructs witness from the medium-specific evidence.
+    pub fn new(evidence: M::Evidence) -> Self { Witness { evidence } }
+
+    /// Releases the medium-specific evidence.
+    pub fn into_evidence(self) -> M::Evidence { self.evidence }
+}
+
+impl<M: SealEvidence> Clone for Witness<M>
+where M::Evidence: Clone
+{
+    fn clone(&self) -> Self { Witness::new(self.evidence.clone()) }
+}
+
+impl<M: SealEvidence> PartialEq for Witness<M>
+where M::Evidence: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool { self.evidence == other.evidence }
+}
+
+impl<M: SealEvidence> Eq for Witness<M> where M::Evidence: Eq {}
+
+impl<M: SealEvidence> std::fmt::Debug for Witness<M>
+where M::Evidence: std::fmt::Debug
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Witness")
+            .field("evidence", &self.evidence)
+            .finish()
+    }
+}
+
+impl<M: SealEvidence> SealWitness<M::Seal> for Witness<M> {
+    type Message = M::Message;
+    type Error = M::Error;
+
+    fn verify_seal(&self, seal: &M::Seal, msg: &Self::Message) -> Result<(), Self::Error> {
+        M::verify_evidence(seal, &self.evidence, msg)
+    }
+}
+
+impl<M: SealEvidence> WitnessHeight for Witness<M> {
+    fn witness_height(&self) -> Option<u32> { M::evidence_height(&self.evidence) }
+}
+
+/// Witness which knows the height (block height, sequence number etc) of its
+/// publication in the seal medium.
+pub trait WitnessHeight {
+    /// Returns height of the witness publication, or `None` if the witness is
+    /// not (yet) published.
+    fn witness_height(&self) -> Option<u32>;
+}
+
+/// Level of trust provided by a [`LeveledWitness`], ordered from the least to
+/// the most trustless one.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(Debug)]
+#[repr(u8)]
+pub enum WitnessLevel {
+    /// Only the witness id is known; the witness itself is provided by a
+    /// trusted resolver.
+    Id = 0,
+
+    /// Full witness is known and verified locally, but its publication in the
+    /// medium is not proven.
+    Witness = 1,
+
+    /// Full witness is known together with the proof of its publication in
+    /// the medium (like SPV proof for a bitcoin transaction), allowing its
+    /// verification by light clients.
+    Published = 2,
+}
+
+/// Seal witness which may be provided with different levels of details,
+/// allowing applications to declare the trust model explicitly.
+///
+/// Generic arguments are the witness id type (like bitcoin txid), the
+/// witness type itself (like bitcoin transaction) and the proof of the witness
+/// publication (like SPV proof).
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LeveledWitness<Id, Witness, Proof> {
+    /// Witness id for resolution with trusted resolvers.
+    Id(Id),
+
+    /// Full witness for local validation.
+    Witness(Witness),
+
+    /// Full witness with the proof of its publication, for light clients.
+    Published(Witness, Proof),
+}
+
+/// Resolver of the witness data used in [`LeveledWitness`] verification.
+pub trait WitnessResolver<Id, Witness, Proof> {
+    /// Error type returned by the resolver
+    type Error: std::error::Error;
+
+    /// Retrieves full witness by its id from a trusted source.
+    fn resolve_witness(&self, id: &Id) -> Result<Witness, Self::Error>;
+
+    /// Verifies that the witness was published in the medium using the
+    /// provided proof.
+    fn verify_publication(&self, witness: &Witness, proof: &Proof) -> Result<(), Self::Error>;
+}
+
+/// Errors verifying [`LeveledWitness`].
+#[derive(Clone, Copy, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum LeveledWitnessError<R: std::error::Error, S: std::error::Error> {
+    /// the witness provides {0} level of trust, while at least {1} is required.
+    InsufficientLevel(WitnessLevel, WitnessLevel),
+
+    /// unable to resolve the witness or verify its publication: {0}
+    Resolver(R),
+
+    /// the seal is not closed over the message by the witness: {0}
+    Seal(S),
+}
+
+#[cfg(feature = "commit_verify")]
+impl<R, S> commit_verify::ErrorCode for LeveledWitnessError<R, S>
+where
+    R: commit_verify::ErrorCode,
+    S: commit_verify::ErrorCode,
+{
+    fn code(&self) -> &'static str {
+        match self {
+            LeveledWitnessError::InsufficientLevel(..) => "seals.witness.insufficient-level",
+            LeveledWitnessError::Resolver(_) => "seals.witness.resolver",
+            LeveledWitnessError::Seal(_) => "seals.witness.not-closed",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            LeveledWitnessError::InsufficientLevel(level, required) => {
+                vec![("level", level.to_string()), ("required", required.to_string())]
+            }
+            LeveledWitnessError::Resolver(err) => caused_by(err),
+            LeveledWitnessError::Seal(err) => caused_by(err),
+        }
+    }
+}
+
+#[cfg(feature = "commit_verify")]
+impl<R, S> commit_verify::ErrorCodes for LeveledWitnessError<R, S>
+where
+    R: commit_verify::ErrorCode,
+    S: commit_verify::ErrorCode,
+{
+    const CODES: &'static [&'static str] =
+        &["seals.witness.insufficient-level", "seals.witness.resolver", "seals.witness.not-closed"];
+}
+
+impl<Id, Witness, Proof> LeveledWitness<Id, Witness, Proof> {
+    /// Returns the level of trust provided by the witness.
+    pub fn level(&self) -> WitnessLevel {
+        match self {
+            LeveledWitness::Id(_) => WitnessLevel::Id,
+            LeveledWitness::Witness(_) => WitnessLevel::Witness,
+            LeveledWitness::Published(_, _) => WitnessLevel::Published,
+        }
+    }
+
+    /// Verifies that the seal was closed over the message with the witness,
+    /// using the `resolver` to resolve witness ids and verify publication
+    /// proofs.
+    ///
+    /// Errors with [`LeveledWitnessError::InsufficientLevel`] if the witness
+    /// provides a level of trust below `min_level`.
+    pub fn verify_seal<Seal, R>(
+        &self,
+        seal: &Seal,
+        msg: &Witness::Message,
+        resolver: &R,
+        min_level: WitnessLevel,
+    ) -> Result<(), LeveledWitnessError<R::Error, Witness::Error>>
+    where
+        Witness: SealWitness<Seal>,
+        R: WitnessResolver<Id, Witness, Proof>,
+    {
+        if self.level() < min_level {
+            return Err(LeveledWitnessError::InsufficientLevel(self.level(), min_level));
+        }
+        match self {
+            LeveledWitness::Id(id) => resolver
+                .resolve_witness(id)
+                .map_err(LeveledWitnessError::Resolver)?
+                .verify_seal(seal, msg),
+            LeveledWitness::Witness(witness) => witness.verify_seal(seal, msg),
+            LeveledWitness::Published(witness, proof) => {
+                resolver
+                    .verify_publication(witness, proof)
+                    .map_err(LeveledWitnessError::Resolver)?;
+                witness.verify_seal(seal, msg)
+            }
+        }
+        .map_err(LeveledWitnessError::Seal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn seal_evidence() {
+        let witness = witness([1, 2], Some(100));
+        assert_eq!(witness.witness_height(), Some(100));
+        witness.verify_seal(&1, &MSG).unwrap();
+        witness.verify_many_seals([&2, &1], &MSG).unwrap();
+        assert_eq!(witness.verify_seal(&3, &MSG), Err(TestError::NotClosed));
+        assert_eq!(witness.verify_many_seals([&1, &3], &MSG), Err(TestError::NotClosed));
+        assert_eq!(witness.verify_seal(&1, &(MSG + 1)), Err(TestError::MessageMismatch));
+        assert_eq!(witness.into_evidence(), Entry {
+            closes: vec![1, 2],
+            message: MSG,
+            height: Some(100)
+        });
+    }
+
+    #[test]
+    fn leveled_witness() {
+        assert!(WitnessLevel::Id < WitnessLevel::Witness);
+        assert!(WitnessLevel::Witness < WitnessLevel::Published);
+
+        let resolver = resolver([(7, witness([1], Some(100)))]);
+        let by_id = LeveledWitness::<_, TestWitness, u32>::Id(7);
+        let local = LeveledWitness::<u32, _, u32>::Witness(witness([1], Some(100)));
+        let published = LeveledWitness::<u32, _, _>::Published(witness([1], Some(100)), 100);
+        assert_eq!(by_id.level(), WitnessLevel::Id);
+        assert_eq!(local.level(), WitnessLevel::Witness);
+        assert_eq!(published.level(), WitnessLevel::Published);
+
+        for leveled in [&by_id, &local, &published] {
+            leveled
+                .verify_seal(&1, &MSG, &resolver, WitnessLevel::Id)
+                .unwrap();
+            assert!(matches!(
+                leveled.verify_seal(&2, &MSG, &resolver, WitnessLevel::Id),
+                Err(LeveledWitnessError::Seal(TestError::NotClosed))
+            ));
+        }
+
+        assert!(matches!(
+            by_id.verify_seal(&1, &MSG, &resolver, WitnessLevel::Witness),
+            Err(LeveledWitnessError::InsufficientLevel(WitnessLevel::Id, WitnessLevel::Witness))
+        ));
+        assert!(matches!(
+            local.verify_seal(&1, &MSG, &resolver, WitnessLevel::Published),
+            Err(LeveledWitnessError::InsufficientLevel(
+                WitnessLevel::Witness,
+                WitnessLevel::Published
+            ))
+        ));
+        published
+            .verify_seal(&1, &MSG, &resolver, WitnessLevel::Published)
+            .unwrap();
+
+        let unknown = LeveledWitness::<_, TestWitness, u32>::Id(8);
+        assert!(matches!(
+            unknown.verify_seal(&1, &MSG, &resolver, WitnessLevel::Id),
+            Err(LeveledWitnessError::Resolver(TestError::Unknown))
+        ));
+        let misproven = LeveledWitness::<u32, _, _>::Published(witness([1], Some(100)), 99);
+        assert!(matches!(
+            misproven.verify_seal(&1, &MSG, &resolver, WitnessLevel::Id),
+            Err(LeveledWitnessError::Resolver(TestError::Unpublished))
+        ));
+    }
+}